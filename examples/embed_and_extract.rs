@@ -0,0 +1,49 @@
+// Hides a message in a PNG with the library API, saves it, reads it back and
+// extracts the message again.
+//
+//     cargo run --example embed_and_extract -- [input.png] [message]
+
+use std::{path::Path, str::FromStr};
+
+use pingu::{chunk::Chunk, chunk_type::ChunkType, png::Png, Result};
+
+fn embed_and_extract(input: &Path, output: &Path, message: &str) -> Result<String> {
+    let mut png = Png::try_from(std::fs::read(input)?.as_slice())?;
+
+    let chunk_type = ChunkType::from_str("ruSt")?;
+    png.insert_chunk_before_iend(Chunk::new(chunk_type, message.as_bytes().to_vec()));
+    std::fs::write(output, png.as_bytes())?;
+
+    let reloaded = Png::try_from(std::fs::read(output)?.as_slice())?;
+    let chunk = reloaded
+        .chunk_by_type("ruSt")
+        .ok_or("the ruSt chunk did not survive the round trip")?;
+    Ok(chunk.data_as_string()?)
+}
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let input = args.next().unwrap_or_else(|| "download.png".to_string());
+    let message = args
+        .next()
+        .unwrap_or_else(|| "hello from pingu".to_string());
+    let output = std::env::temp_dir().join("pingu_example.png");
+
+    let extracted = embed_and_extract(Path::new(&input), &output, &message)?;
+    println!("embedded into {}: {}", output.display(), extracted);
+
+    if extracted != message {
+        return Err("extracted message does not match".into());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_embed_and_extract() {
+    let output = std::env::temp_dir().join(format!("pingu_example_{}.png", std::process::id()));
+
+    let extracted = embed_and_extract(Path::new("download.png"), &output, "round trip").unwrap();
+    std::fs::remove_file(&output).unwrap();
+
+    assert_eq!(extracted, "round trip");
+}
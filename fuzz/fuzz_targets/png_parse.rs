@@ -0,0 +1,17 @@
+// Feeds arbitrary bytes to the PNG parser; any panic is a bug.
+//
+// Requires a nightly toolchain and cargo-fuzz (`cargo install cargo-fuzz`).
+// Run from the repository root, seeded with the PNGs in fuzz/corpus/png_parse:
+//
+//     cargo +nightly fuzz run png_parse
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pingu::png::Png;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(png) = Png::try_from(data) {
+        // Whatever parses must serialize back to the bytes it came from
+        assert_eq!(png.as_bytes(), data);
+    }
+});
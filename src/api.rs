@@ -0,0 +1,100 @@
+// Byte-in, byte-out versions of the basic commands, with no file or terminal
+// access, for callers embedding pingu (including WASM builds). Each is a thin
+// wrapper over a function on a parsed Png, which the CLI calls directly.
+
+use crate::{chunk::Chunk, chunk_type::ChunkType, png::Png, Result};
+
+// Adds the message in a new chunk just before IEND, returning its index
+pub fn add_message(png: &mut Png, chunk_type: ChunkType, message: &[u8]) -> usize {
+    png.insert_chunk_before_iend(Chunk::new(chunk_type, message.to_vec()))
+}
+
+pub fn encode_message(png_bytes: &[u8], chunk_type: ChunkType, message: &[u8]) -> Result<Vec<u8>> {
+    let mut png = Png::try_from(png_bytes)?;
+    add_message(&mut png, chunk_type, message);
+    Ok(png.as_bytes())
+}
+
+// The first chunk of this type, which is where decode looks for a message
+pub fn message_chunk<'a>(png: &'a Png, chunk_type: &ChunkType) -> Option<&'a Chunk> {
+    png.chunk_by_type(&chunk_type.to_string())
+}
+
+// Data of the first chunk of this type, or None when there is no such chunk
+pub fn decode_message(png_bytes: &[u8], chunk_type: &ChunkType) -> Result<Option<Vec<u8>>> {
+    let png = Png::try_from(png_bytes)?;
+    Ok(message_chunk(&png, chunk_type).map(|chunk| chunk.data().to_vec()))
+}
+
+// Takes the first chunk of this type out of the PNG
+pub fn take_chunk(png: &mut Png, chunk_type: &ChunkType) -> Result<Chunk> {
+    png.remove_chunk(&chunk_type.to_string())
+}
+
+// Removes the first chunk of this type, returning the new PNG and the chunk
+pub fn remove_chunk(png_bytes: &[u8], chunk_type: &ChunkType) -> Result<(Vec<u8>, Chunk)> {
+    let mut png = Png::try_from(png_bytes)?;
+    let removed = take_chunk(&mut png, chunk_type)?;
+    Ok((png.as_bytes(), removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn ru_st() -> ChunkType {
+        ChunkType::from_str("ruSt").unwrap()
+    }
+
+    fn original() -> Vec<u8> {
+        std::fs::read("download.png").unwrap()
+    }
+
+    #[test]
+    fn test_encode_then_decode() {
+        let encoded = encode_message(&original(), ru_st(), b"in memory").unwrap();
+
+        assert_eq!(
+            decode_message(&encoded, &ru_st()).unwrap().unwrap(),
+            b"in memory"
+        );
+        let png = Png::try_from(encoded.as_slice()).unwrap();
+        assert!(png.iter_chunks().last().unwrap().is_type("IEND"));
+    }
+
+    #[test]
+    fn test_parsed_png_functions() {
+        let mut png = Png::try_from(original().as_slice()).unwrap();
+
+        let index = add_message(&mut png, ru_st(), b"parsed");
+        assert_eq!(index, png.chunk_count() - 2);
+        assert_eq!(message_chunk(&png, &ru_st()).unwrap().data(), b"parsed");
+
+        assert_eq!(take_chunk(&mut png, &ru_st()).unwrap().data(), b"parsed");
+        assert!(message_chunk(&png, &ru_st()).is_none());
+        assert_eq!(png.as_bytes(), original());
+    }
+
+    #[test]
+    fn test_decode_missing_chunk() {
+        assert_eq!(decode_message(&original(), &ru_st()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_remove_restores_original() {
+        let encoded = encode_message(&original(), ru_st(), b"temporary").unwrap();
+
+        let (stripped, removed) = remove_chunk(&encoded, &ru_st()).unwrap();
+
+        assert_eq!(stripped, original());
+        assert_eq!(removed.data(), b"temporary");
+        assert!(remove_chunk(&stripped, &ru_st()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_png() {
+        assert!(encode_message(b"not a png", ru_st(), b"x").is_err());
+        assert!(decode_message(b"", &ru_st()).is_err());
+    }
+}
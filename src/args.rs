@@ -0,0 +1,847 @@
+use std::{
+    io::{BufReader, Read},
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, ValueEnum};
+
+use pingu::{
+    chunk::{self, CrcEndian},
+    chunk_type::{ChunkType, ChunkTypeErr},
+    png::{Png, PngError},
+    zlib,
+};
+
+use crate::mmap::Mmap;
+
+#[derive(Args, Debug, Clone)]
+pub struct InputArgs {
+    /// PNG file to read, - for stdin, or a plain http:// URL (https is not
+    /// supported)
+    #[arg(short, long)]
+    pub png: PathBuf,
+    /// Memory-map the file instead of reading it into memory
+    #[arg(long)]
+    pub mmap: bool,
+    /// What the input holds
+    #[arg(long, value_enum, default_value_t)]
+    pub input_format: InputFormat,
+    /// Parse the input as a bare chunk stream with no PNG signature; the
+    /// same as --input-format chunks
+    #[arg(long, conflicts_with = "input_format")]
+    pub no_signature: bool,
+    /// Accept any 8-byte signature and write it back unchanged
+    #[arg(long, conflicts_with = "no_signature")]
+    pub any_signature: bool,
+    /// Give up reading stdin or a URL, or inflating gzip or IDAT data, after
+    /// this many bytes
+    #[arg(long, default_value_t = DEFAULT_MAX_INPUT_SIZE)]
+    pub max_input_size: u64,
+    /// Ignore this many bytes before the signature; a UTF-8 BOM is skipped
+    /// without it
+    #[arg(long)]
+    pub skip_leading: Option<usize>,
+}
+
+const DEFAULT_MAX_INPUT_SIZE: u64 = 256 * 1024 * 1024;
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+// Like InputArgs, but --png may be repeated
+#[derive(Args, Debug)]
+pub struct MultiInputArgs {
+    /// PNG file to read, - for stdin, or a plain http:// URL (https is not
+    /// supported); may be given more than once
+    #[arg(short, long, required = true)]
+    pub png: Vec<PathBuf>,
+    /// Memory-map the files instead of reading them into memory
+    #[arg(long)]
+    pub mmap: bool,
+    /// What the input holds
+    #[arg(long, value_enum, default_value_t)]
+    pub input_format: InputFormat,
+    /// Parse the input as a bare chunk stream with no PNG signature; the
+    /// same as --input-format chunks
+    #[arg(long, conflicts_with = "input_format")]
+    pub no_signature: bool,
+    /// Accept any 8-byte signature and write it back unchanged
+    #[arg(long, conflicts_with = "no_signature")]
+    pub any_signature: bool,
+    /// Give up reading stdin or a URL, or inflating gzip or IDAT data, after
+    /// this many bytes
+    #[arg(long, default_value_t = DEFAULT_MAX_INPUT_SIZE)]
+    pub max_input_size: u64,
+    /// Ignore this many bytes before the signature; a UTF-8 BOM is skipped
+    /// without it
+    #[arg(long)]
+    pub skip_leading: Option<usize>,
+}
+
+impl MultiInputArgs {
+    pub fn inputs(&self) -> impl Iterator<Item = InputArgs> + '_ {
+        self.png.iter().map(|png| InputArgs {
+            png: png.clone(),
+            mmap: self.mmap,
+            input_format: self.input_format,
+            no_signature: self.no_signature,
+            any_signature: self.any_signature,
+            max_input_size: self.max_input_size,
+            skip_leading: self.skip_leading,
+        })
+    }
+}
+
+pub enum InputBytes {
+    Read(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl InputArgs {
+    pub fn read(&self) -> std::io::Result<InputBytes> {
+        if let Some(url) = self.png.to_str().filter(|p| is_url(p)) {
+            Ok(InputBytes::Read(fetch(url, self.max_input_size)?))
+        } else if is_stdio(&self.png) {
+            let bytes = read_limited(std::io::stdin().lock(), self.max_input_size)?;
+            Ok(InputBytes::Read(bytes))
+        } else if self.mmap {
+            Ok(InputBytes::Mapped(Mmap::open(&self.png)?))
+        } else {
+            Ok(InputBytes::Read(std::fs::read(&self.png)?))
+        }
+    }
+
+    // For reading chunk by chunk through a buffer of the given capacity; a URL
+    // is still fetched whole first
+    pub fn reader(&self, capacity: usize) -> std::io::Result<Box<dyn Read>> {
+        if let Some(url) = self.png.to_str().filter(|p| is_url(p)) {
+            Ok(Box::new(std::io::Cursor::new(fetch(
+                url,
+                self.max_input_size,
+            )?)))
+        } else if is_stdio(&self.png) {
+            // The limit goes outside the buffer, which may read ahead of the
+            // bytes actually used
+            let stdin = BufReader::with_capacity(capacity.max(1), std::io::stdin().lock());
+            Ok(Box::new(LimitedReader::new(stdin, self.max_input_size)))
+        } else {
+            let file = std::fs::File::open(&self.png)?;
+            Ok(Box::new(BufReader::with_capacity(capacity.max(1), file)))
+        }
+    }
+
+    pub fn parse(&self, bytes: &[u8]) -> Result<Png, PngError> {
+        let skip = leading_bytes(bytes, self.skip_leading);
+        if skip > 0 {
+            eprintln!("note: skipped {} leading bytes", skip);
+        }
+        let bytes = &bytes[skip..];
+
+        // A .png.gz is unpacked first, so the rest never sees the gzip layer.
+        // Only one layer: gzip inside gzip is left for the PNG parser to reject.
+        if zlib::has_gzip_header(bytes) {
+            let limit = usize::try_from(self.max_input_size).unwrap_or(usize::MAX);
+            let unpacked = zlib::gzip_decompress(bytes, limit)?;
+            return self.parse_unpacked(&unpacked);
+        }
+        self.parse_unpacked(bytes)
+    }
+
+    fn parse_unpacked(&self, bytes: &[u8]) -> Result<Png, PngError> {
+        if self.no_signature || self.input_format == InputFormat::Chunks {
+            Png::try_from_chunk_stream(bytes)
+        } else if self.any_signature {
+            Png::try_from_any_signature(bytes)
+        } else {
+            Png::try_from(bytes)
+        }
+    }
+}
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+// How much junk a broken transfer left ahead of the PNG: an explicit count,
+// or a UTF-8 byte order mark if one is there
+pub fn leading_bytes(bytes: &[u8], skip: Option<usize>) -> usize {
+    match skip {
+        Some(skip) => skip.min(bytes.len()),
+        None if bytes.starts_with(&UTF8_BOM) => UTF8_BOM.len(),
+        None => 0,
+    }
+}
+
+// Reads to the end, failing once more than limit bytes arrive so that an
+// endless pipe can't exhaust memory
+pub fn read_limited<R: Read>(reader: R, limit: u64) -> std::io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    LimitedReader::new(reader, limit).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+// Like Read::take, but going past the limit is an error rather than an early
+// end of file, so a streamed input isn't mistaken for a truncated PNG
+pub struct LimitedReader<R> {
+    inner: R,
+    limit: u64,
+    remaining: u64,
+}
+
+impl<R: Read> LimitedReader<R> {
+    pub fn new(inner: R, limit: u64) -> Self {
+        LimitedReader {
+            inner,
+            limit,
+            remaining: limit,
+        }
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // One byte past the limit is enough to tell that there is more
+        let max = usize::try_from(self.remaining.saturating_add(1)).unwrap_or(usize::MAX);
+        let len = buf.len().min(max);
+        let read = self.inner.read(&mut buf[..len])?;
+        if read as u64 > self.remaining {
+            return Err(std::io::Error::other(format!(
+                "input is larger than {} bytes; raise --max-input-size to allow it",
+                self.limit
+            )));
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+#[cfg(feature = "network")]
+fn fetch(url: &str, limit: u64) -> std::io::Result<Vec<u8>> {
+    crate::http::fetch(url, limit).map_err(std::io::Error::other)
+}
+
+#[cfg(not(feature = "network"))]
+fn fetch(url: &str, _limit: u64) -> std::io::Result<Vec<u8>> {
+    Err(std::io::Error::other(format!(
+        "Cannot fetch {}: pingu was built without the network feature",
+        url
+    )))
+}
+
+// A path of - stands for stdin or stdout, as in most Unix tools
+pub fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+impl Deref for InputBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            InputBytes::Read(bytes) => bytes,
+            InputBytes::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct ChunkTypeArgs {
+    #[arg(short, long)]
+    chunk_type: Option<ChunkType>,
+    /// Chunk type as 8 hex digits, e.g. 72755374 for ruSt
+    #[arg(long, value_parser = parse_chunk_type_hex)]
+    chunk_type_hex: Option<ChunkType>,
+}
+
+impl ChunkTypeArgs {
+    pub fn chunk_type(&self) -> ChunkType {
+        self.chunk_type
+            .or(self.chunk_type_hex)
+            .expect("clap requires one of --chunk-type or --chunk-type-hex")
+    }
+}
+
+// Remove can pick the hidden chunk itself instead of taking a type
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct RemoveTargetArgs {
+    #[arg(short, long)]
+    chunk_type: Option<ChunkType>,
+    /// Chunk type as 8 hex digits, e.g. 72755374 for ruSt
+    #[arg(long, value_parser = parse_chunk_type_hex)]
+    chunk_type_hex: Option<ChunkType>,
+    /// Remove the first non-standard ancillary chunk, whatever its type
+    #[arg(long)]
+    auto: bool,
+}
+
+impl RemoveTargetArgs {
+    pub fn chunk_type(&self) -> Option<ChunkType> {
+        self.chunk_type.or(self.chunk_type_hex)
+    }
+
+    pub fn auto(&self) -> bool {
+        self.auto
+    }
+}
+
+// Encode can hide the message in the pixel data instead of a chunk
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct EncodeTargetArgs {
+    #[arg(short, long)]
+    chunk_type: Option<ChunkType>,
+    /// Chunk type as 8 hex digits, e.g. 72755374 for ruSt
+    #[arg(long, value_parser = parse_chunk_type_hex)]
+    chunk_type_hex: Option<ChunkType>,
+    /// Hide the message in the least-significant bits of the image pixels
+    #[arg(long)]
+    lsb: bool,
+}
+
+impl EncodeTargetArgs {
+    pub fn chunk_type(&self) -> Option<ChunkType> {
+        self.chunk_type.or(self.chunk_type_hex)
+    }
+
+    pub fn lsb(&self) -> bool {
+        self.lsb
+    }
+}
+
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct MessageArgs {
+    #[arg(short, long)]
+    message: Option<String>,
+    /// Read the message from a file instead of the command line
+    #[arg(long)]
+    message_file: Option<PathBuf>,
+}
+
+impl MessageArgs {
+    pub fn read(&self) -> std::io::Result<Vec<u8>> {
+        match (&self.message, &self.message_file) {
+            (Some(message), _) => Ok(message.as_bytes().to_vec()),
+            (None, Some(path)) => std::fs::read(path),
+            (None, None) => unreachable!("clap requires --message or --message-file"),
+        }
+    }
+
+    pub fn file_name(&self) -> Option<String> {
+        self.message_file
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+}
+
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct ExpectedArgs {
+    /// Message the chunk must contain
+    #[arg(long)]
+    equals: Option<String>,
+    /// File whose contents the chunk must match exactly
+    #[arg(long)]
+    equals_file: Option<PathBuf>,
+}
+
+impl ExpectedArgs {
+    pub fn read(&self) -> std::io::Result<Vec<u8>> {
+        match (&self.equals, &self.equals_file) {
+            (Some(expected), _) => Ok(expected.as_bytes().to_vec()),
+            (None, Some(path)) => std::fs::read(path),
+            (None, None) => unreachable!("clap requires --equals or --equals-file"),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChunkNaming {
+    /// Position in the file and chunk type, e.g. 0002-ruSt.bin
+    #[default]
+    Index,
+    /// Digest of the chunk data, e.g. <sha256>.chunk; identical chunks get
+    /// the same name wherever they come from
+    Hash,
+}
+
+// Algorithms for the hash command; which ones exist depends on crate features
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgo {
+    #[cfg(feature = "sha256")]
+    Sha256,
+    Crc32,
+}
+
+impl Default for HashAlgo {
+    fn default() -> Self {
+        #[cfg(feature = "sha256")]
+        return HashAlgo::Sha256;
+        #[cfg(not(feature = "sha256"))]
+        return HashAlgo::Crc32;
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InputFormat {
+    /// A PNG file, starting with the PNG signature
+    #[default]
+    Png,
+    /// Chunks back to back with no signature, like a raw chunk dump
+    Chunks,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Newlines {
+    Lf,
+    Crlf,
+    #[default]
+    None,
+}
+
+// Decode also accepts a type prefix in place of a single chunk type
+#[derive(Args, Debug)]
+#[group(required = true, multiple = false)]
+pub struct DecodeTargetArgs {
+    #[arg(short, long)]
+    chunk_type: Option<ChunkType>,
+    /// Chunk type as 8 hex digits, e.g. 72755374 for ruSt
+    #[arg(long, value_parser = parse_chunk_type_hex)]
+    chunk_type_hex: Option<ChunkType>,
+    /// Concatenate every chunk whose type starts with this 2-letter prefix
+    #[arg(long, value_parser = parse_type_prefix)]
+    type_prefix: Option<String>,
+    /// Read a message hidden in the least-significant bits of the image pixels
+    #[arg(long)]
+    lsb: bool,
+}
+
+impl DecodeTargetArgs {
+    pub fn chunk_type(&self) -> Option<ChunkType> {
+        self.chunk_type.or(self.chunk_type_hex)
+    }
+
+    pub fn type_prefix(&self) -> Option<&str> {
+        self.type_prefix.as_deref()
+    }
+
+    pub fn lsb(&self) -> bool {
+        self.lsb
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CrcEndianArg {
+    /// Big-endian, as the PNG spec requires
+    #[default]
+    Be,
+    /// Little-endian; non-standard, only for diagnosing broken readers
+    Le,
+}
+
+impl From<CrcEndianArg> for CrcEndian {
+    fn from(endian: CrcEndianArg) -> Self {
+        match endian {
+            CrcEndianArg::Be => CrcEndian::Big,
+            CrcEndianArg::Le => CrcEndian::Little,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecodeFormat {
+    #[default]
+    Text,
+    Hex,
+    Json,
+}
+
+pub fn parse_chunk_type_hex(s: &str) -> Result<ChunkType, ChunkTypeErr> {
+    if s.len() != 8 || !s.is_ascii() {
+        return Err(ChunkTypeErr::InvalidChunkType(
+            "hex chunk type must be 8 hex digits".to_string(),
+        ));
+    }
+
+    let bytes: [u8; 4] = chunk::decode_hex(s)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| ChunkTypeErr::InvalidChunkType(format!("invalid hex digits in {}", s)))?;
+    ChunkType::try_from(bytes)
+}
+
+pub fn parse_type_prefix(s: &str) -> Result<String, ChunkTypeErr> {
+    if s.len() != 2 {
+        return Err(ChunkTypeErr::InvalidChunkType(
+            "type prefix must be 2 characters".to_string(),
+        ));
+    }
+
+    match s.bytes().find(|byte| !byte.is_ascii_alphabetic()) {
+        Some(byte) => Err(ChunkTypeErr::InvalidByte(
+            byte,
+            "byte is not a ascii alphabet".to_string(),
+        )),
+        None => Ok(s.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        chunk_type: ChunkTypeArgs,
+    }
+
+    #[derive(Parser)]
+    struct TestDecodeCli {
+        #[command(flatten)]
+        target: DecodeTargetArgs,
+    }
+
+    #[derive(Parser)]
+    struct TestEncodeCli {
+        #[command(flatten)]
+        target: EncodeTargetArgs,
+    }
+
+    #[derive(Parser)]
+    struct TestExpectedCli {
+        #[command(flatten)]
+        expected: ExpectedArgs,
+    }
+
+    #[derive(Parser)]
+    struct TestMessageCli {
+        #[command(flatten)]
+        message: MessageArgs,
+    }
+
+    #[derive(Parser)]
+    struct TestInputCli {
+        #[command(flatten)]
+        input: InputArgs,
+    }
+
+    #[derive(Parser)]
+    struct TestMultiInputCli {
+        #[command(flatten)]
+        input: MultiInputArgs,
+    }
+
+    #[test]
+    fn test_multi_input_args() {
+        let cli =
+            TestMultiInputCli::try_parse_from(["pingu", "--png", "a.png", "-p", "b.png", "--mmap"])
+                .unwrap();
+        let inputs: Vec<InputArgs> = cli.input.inputs().collect();
+
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0].png, PathBuf::from("a.png"));
+        assert_eq!(inputs[1].png, PathBuf::from("b.png"));
+        assert!(inputs.iter().all(|input| input.mmap));
+
+        assert!(TestMultiInputCli::try_parse_from(["pingu"]).is_err());
+    }
+
+    #[test]
+    fn test_skip_leading_bytes() {
+        let png = std::fs::read("download.png").unwrap();
+        let with_bom = [&UTF8_BOM[..], &png].concat();
+        let with_junk = [&b"\r\n  "[..], &png].concat();
+
+        let cli = TestInputCli::try_parse_from(["pingu", "--png", "a.png"]).unwrap();
+        assert_eq!(leading_bytes(&with_bom, None), 3);
+        assert_eq!(leading_bytes(&png, None), 0);
+        assert_eq!(cli.input.parse(&with_bom).unwrap().as_bytes(), png);
+        assert!(cli.input.parse(&with_junk).is_err());
+
+        let cli = TestInputCli::try_parse_from(["pingu", "--png", "a.png", "--skip-leading", "4"])
+            .unwrap();
+        assert_eq!(cli.input.parse(&with_junk).unwrap().as_bytes(), png);
+        assert_eq!(leading_bytes(b"ab", Some(4)), 2);
+    }
+
+    #[test]
+    fn test_parse_gzipped_input() {
+        let png = std::fs::read("download.png").unwrap();
+        let cli = TestInputCli::try_parse_from(["pingu", "--png", "a.png.gz"]).unwrap();
+
+        let gzipped = zlib::gzip_stored(&png);
+        assert_eq!(cli.input.parse(&gzipped).unwrap().as_bytes(), png);
+
+        let mut corrupted = gzipped.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        assert!(matches!(
+            cli.input.parse(&corrupted),
+            Err(PngError::Gzip(_))
+        ));
+
+        // Only one gzip layer is unpacked
+        assert!(matches!(
+            cli.input.parse(&zlib::gzip_stored(&gzipped)),
+            Err(PngError::InvalidHeader)
+        ));
+
+        // The limit applies to the unpacked size, not the gzip file
+        let cli = TestInputCli::try_parse_from([
+            "pingu",
+            "--png",
+            "a.png.gz",
+            "--max-input-size",
+            &(png.len() - 1).to_string(),
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.input.parse(&gzipped),
+            Err(PngError::Gzip(zlib::ZlibError::OutputTooLarge(_)))
+        ));
+    }
+
+    #[test]
+    fn test_read_limited() {
+        assert_eq!(read_limited(&b"12345"[..], 5).unwrap(), b"12345");
+
+        let err = read_limited(std::io::repeat(0), 1024).unwrap_err();
+        assert!(err.to_string().contains("larger than 1024 bytes"));
+    }
+
+    #[test]
+    fn test_limited_reader() {
+        let mut reader = LimitedReader::new(&b"12345"[..], 5);
+        let mut first = [0; 3];
+        reader.read_exact(&mut first).unwrap();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!((&first, rest.as_slice()), (b"123", &b"45"[..]));
+
+        // Chunk by chunk parsing stops with the limit error, not a truncated chunk
+        let png = std::fs::read("download.png").unwrap();
+        let mut reader = LimitedReader::new(png.as_slice(), 100);
+        let err = Png::for_each_chunk(&mut reader, |_, _| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("larger than 100 bytes"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_chunk_type_hex() {
+        let chunk_type = parse_chunk_type_hex("72755374").unwrap();
+        assert_eq!(chunk_type.to_string(), "ruSt");
+    }
+
+    #[test]
+    fn test_parse_chunk_type_hex_invalid() {
+        assert!(parse_chunk_type_hex("7275537").is_err());
+        assert!(parse_chunk_type_hex("7275537400").is_err());
+        assert!(parse_chunk_type_hex("zz755374").is_err());
+        assert!(parse_chunk_type_hex("31323334").is_err());
+        assert!(parse_chunk_type_hex("+2755374").is_err());
+    }
+
+    #[test]
+    fn test_chunk_type_args() {
+        let cli = TestCli::try_parse_from(["pingu", "--chunk-type-hex", "72755374"]).unwrap();
+        assert_eq!(cli.chunk_type.chunk_type().to_string(), "ruSt");
+
+        let cli = TestCli::try_parse_from(["pingu", "--chunk-type", "ruSt"]).unwrap();
+        assert_eq!(cli.chunk_type.chunk_type().to_string(), "ruSt");
+    }
+
+    #[test]
+    fn test_chunk_type_args_are_exclusive() {
+        let cli = TestCli::try_parse_from([
+            "pingu",
+            "--chunk-type",
+            "ruSt",
+            "--chunk-type-hex",
+            "72755374",
+        ]);
+        assert!(cli.is_err());
+
+        let cli = TestCli::try_parse_from(["pingu"]);
+        assert!(cli.is_err());
+    }
+
+    #[test]
+    fn test_input_args_mmap_matches_read() {
+        let png = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("download.png");
+        let png = png.to_str().unwrap();
+
+        let read = TestInputCli::try_parse_from(["pingu", "--png", png]).unwrap();
+        let mapped = TestInputCli::try_parse_from(["pingu", "--png", png, "--mmap"]).unwrap();
+
+        assert!(matches!(read.input.read().unwrap(), InputBytes::Read(_)));
+        assert_eq!(
+            &read.input.read().unwrap()[..],
+            &mapped.input.read().unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_parse_type_prefix() {
+        assert_eq!(parse_type_prefix("ms").unwrap(), "ms");
+        assert!(parse_type_prefix("m").is_err());
+        assert!(parse_type_prefix("msg").is_err());
+        assert!(parse_type_prefix("m1").is_err());
+    }
+
+    #[test]
+    fn test_decode_target_args() {
+        let cli = TestDecodeCli::try_parse_from(["pingu", "--type-prefix", "ms"]).unwrap();
+        assert_eq!(cli.target.type_prefix(), Some("ms"));
+        assert!(cli.target.chunk_type().is_none());
+
+        let cli = TestDecodeCli::try_parse_from(["pingu", "--chunk-type", "ruSt"]).unwrap();
+        assert_eq!(cli.target.chunk_type().unwrap().to_string(), "ruSt");
+
+        assert!(TestDecodeCli::try_parse_from(["pingu"]).is_err());
+        assert!(TestDecodeCli::try_parse_from([
+            "pingu",
+            "--chunk-type",
+            "ruSt",
+            "--type-prefix",
+            "ms"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_lsb_target_args() {
+        let cli = TestEncodeCli::try_parse_from(["pingu", "--lsb"]).unwrap();
+        assert!(cli.target.lsb());
+        assert!(cli.target.chunk_type().is_none());
+
+        let cli = TestDecodeCli::try_parse_from(["pingu", "--lsb"]).unwrap();
+        assert!(cli.target.lsb());
+
+        assert!(TestEncodeCli::try_parse_from(["pingu"]).is_err());
+        assert!(TestEncodeCli::try_parse_from(["pingu", "--lsb", "--chunk-type", "ruSt"]).is_err());
+        assert!(TestDecodeCli::try_parse_from(["pingu", "--lsb", "--type-prefix", "ms"]).is_err());
+    }
+
+    #[test]
+    fn test_expected_args() {
+        let path = std::env::temp_dir().join("pingu_expected_args.txt");
+        std::fs::write(&path, b"expected\n").unwrap();
+
+        let cli = TestExpectedCli::try_parse_from(["pingu", "--equals", "inline"]).unwrap();
+        assert_eq!(cli.expected.read().unwrap(), b"inline");
+
+        let cli =
+            TestExpectedCli::try_parse_from(["pingu", "--equals-file", path.to_str().unwrap()])
+                .unwrap();
+        assert_eq!(cli.expected.read().unwrap(), b"expected\n");
+
+        assert!(TestExpectedCli::try_parse_from(["pingu"]).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_message_args() {
+        let path = std::env::temp_dir().join("pingu_message_args.txt");
+        std::fs::write(&path, b"from a file\r\n").unwrap();
+
+        let cli = TestMessageCli::try_parse_from(["pingu", "--message", "inline"]).unwrap();
+        assert_eq!(cli.message.read().unwrap(), b"inline");
+
+        let cli =
+            TestMessageCli::try_parse_from(["pingu", "--message-file", path.to_str().unwrap()])
+                .unwrap();
+        assert_eq!(cli.message.read().unwrap(), b"from a file\r\n");
+
+        assert!(TestMessageCli::try_parse_from(["pingu"]).is_err());
+        assert!(TestMessageCli::try_parse_from([
+            "pingu",
+            "--message",
+            "inline",
+            "--message-file",
+            path.to_str().unwrap()
+        ])
+        .is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_input_args_empty_and_tiny_files() {
+        let dir = std::env::temp_dir();
+        for (name, bytes, len) in [
+            ("pingu_empty.png", &b""[..], 0),
+            ("pingu_tiny.png", &b"\x89PNG"[..], 4),
+        ] {
+            let path = dir.join(name);
+            std::fs::write(&path, bytes).unwrap();
+
+            let cli =
+                TestInputCli::try_parse_from(["pingu", "--png", path.to_str().unwrap()]).unwrap();
+            let data = cli.input.read().unwrap();
+            match cli.input.parse(&data) {
+                Err(PngError::Empty) => assert_eq!(len, 0),
+                Err(PngError::TooSmall(n)) => assert_eq!(n, len),
+                _ => panic!("expected a size error for {}", name),
+            }
+
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_input_args_no_signature() {
+        let png = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("download.png");
+        let png = png.to_str().unwrap();
+        let bytes = std::fs::read(png).unwrap();
+
+        let strict = TestInputCli::try_parse_from(["pingu", "--png", png]).unwrap();
+        let lenient =
+            TestInputCli::try_parse_from(["pingu", "--png", png, "--no-signature"]).unwrap();
+
+        assert!(strict.input.parse(&bytes).is_ok());
+        assert!(strict.input.parse(&bytes[8..]).is_err());
+        assert!(lenient.input.parse(&bytes[8..]).is_ok());
+    }
+
+    #[test]
+    fn test_input_format() {
+        let bytes = std::fs::read("download.png").unwrap();
+        let parse = |format: &str, bytes: &[u8]| {
+            let cli =
+                TestInputCli::try_parse_from(["pingu", "--png", "a.png", "--input-format", format])
+                    .unwrap();
+            cli.input.parse(bytes).map(|png| png.chunk_count())
+        };
+
+        assert_eq!(parse("png", &bytes).unwrap(), 4);
+        assert!(parse("png", &bytes[8..]).is_err());
+        assert_eq!(parse("chunks", &bytes[8..]).unwrap(), 4);
+        assert!(parse("chunks", &bytes).is_err());
+
+        let cli = TestInputCli::try_parse_from(["pingu", "--png", "a.png"]).unwrap();
+        assert_eq!(cli.input.input_format, InputFormat::Png);
+        assert!(
+            TestInputCli::try_parse_from(["pingu", "--png", "a.png", "--input-format", "gif"])
+                .is_err()
+        );
+
+        // --no-signature is shorthand for one format, so it can't be mixed with another
+        let err = TestInputCli::try_parse_from([
+            "pingu",
+            "--png",
+            "a.png",
+            "--no-signature",
+            "--input-format",
+            "png",
+        ])
+        .err()
+        .unwrap();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+}
@@ -0,0 +1,188 @@
+use thiserror::Error;
+
+use crate::{
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    ihdr::Ihdr,
+    png::{Png, PngError},
+};
+
+#[derive(Debug, Error)]
+pub enum BuilderError {
+    #[error("Invalid tEXt keyword: {0:?}")]
+    InvalidKeyword(String),
+    #[error("PNG has no IHDR chunk")]
+    MissingHeader,
+    #[error("IHDR must be the first chunk")]
+    HeaderNotFirst,
+    #[error("IEND must be the last chunk")]
+    EndNotLast,
+    #[error(transparent)]
+    PngError(#[from] PngError),
+}
+
+#[derive(Default)]
+pub struct PngBuilder {
+    header: Option<Ihdr>,
+    chunks: Vec<Chunk>,
+    strict: bool,
+    error: Option<BuilderError>,
+}
+
+impl PngBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_header(mut self, header: Ihdr) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    pub fn add_chunk(mut self, chunk: Chunk) -> Self {
+        self.chunks.push(chunk);
+        self
+    }
+
+    // tEXt data is a Latin-1 keyword of 1-79 bytes, a NUL, then the text
+    pub fn add_text(mut self, keyword: &str, text: &str) -> Self {
+        if keyword.is_empty() || keyword.len() > 79 || keyword.contains('\0') {
+            self.error
+                .get_or_insert(BuilderError::InvalidKeyword(keyword.to_string()));
+            return self;
+        }
+
+        let data: Vec<u8> = keyword
+            .bytes()
+            .chain(std::iter::once(0))
+            .chain(text.bytes())
+            .collect();
+        self.add_chunk(Chunk::new(chunk_type(b"tEXt"), data))
+    }
+
+    // Enforce IHDR-first and IEND-last at build time
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    // Prepends the header as IHDR and appends IEND if no chunk added one,
+    // then checks the result survives a serialize/parse round trip.
+    pub fn build(self) -> Result<Png, BuilderError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let mut chunks = Vec::with_capacity(self.chunks.len() + 2);
+        if let Some(header) = self.header {
+            chunks.push(Chunk::new(chunk_type(b"IHDR"), header.as_bytes()));
+        }
+        chunks.extend(self.chunks);
+        if !chunks.iter().any(|ch| ch.is_type("IEND")) {
+            chunks.push(Chunk::new(chunk_type(b"IEND"), Vec::new()));
+        }
+
+        if self.strict {
+            match chunks.iter().position(|ch| ch.is_type("IHDR")) {
+                None => return Err(BuilderError::MissingHeader),
+                Some(0) => {}
+                Some(_) => return Err(BuilderError::HeaderNotFirst),
+            }
+            if chunks.iter().rposition(|ch| ch.is_type("IEND")) != Some(chunks.len() - 1) {
+                return Err(BuilderError::EndNotLast);
+            }
+        }
+
+        let png = Png::from_chunks(chunks);
+        Png::try_from(png.as_bytes().as_ref())?;
+        Ok(png)
+    }
+}
+
+fn chunk_type(bytes: &[u8; 4]) -> ChunkType {
+    ChunkType::try_from(*bytes).expect("standard chunk types are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn testing_header() -> Ihdr {
+        Ihdr {
+            width: 1,
+            height: 1,
+            bit_depth: 8,
+            color_type: 0,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        }
+    }
+
+    fn chunk_types(png: &Png) -> Vec<String> {
+        png.iter_chunks()
+            .map(|ch| ch.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_build_minimal_png() {
+        let png = PngBuilder::new()
+            .with_header(testing_header())
+            .add_chunk(Chunk::new(
+                ChunkType::from_str("IDAT").unwrap(),
+                vec![120, 1, 1, 2, 0, 253, 255, 0, 0, 0, 2, 0, 1],
+            ))
+            .add_text("Comment", "built with pingu")
+            .strict(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(chunk_types(&png), vec!["IHDR", "IDAT", "tEXt", "IEND"]);
+        assert_eq!(
+            png.chunk_by_type("tEXt").unwrap().data(),
+            b"Comment\0built with pingu"
+        );
+
+        let reparsed = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        assert_eq!(reparsed.as_bytes(), png.as_bytes());
+        assert_eq!(
+            Ihdr::try_from(reparsed.chunk_by_type("IHDR").unwrap()).unwrap(),
+            testing_header()
+        );
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_keyword() {
+        let result = PngBuilder::new()
+            .with_header(testing_header())
+            .add_text("", "no keyword")
+            .build();
+
+        assert!(matches!(result, Err(BuilderError::InvalidKeyword(_))));
+    }
+
+    #[test]
+    fn test_strict_build_requires_header() {
+        let builder = || PngBuilder::new().add_text("Comment", "headless");
+
+        assert!(builder().build().is_ok());
+        assert!(matches!(
+            builder().strict(true).build(),
+            Err(BuilderError::MissingHeader)
+        ));
+    }
+
+    #[test]
+    fn test_strict_build_requires_iend_last() {
+        let result = PngBuilder::new()
+            .with_header(testing_header())
+            .add_chunk(Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]))
+            .add_text("Comment", "too late")
+            .strict(true)
+            .build();
+
+        assert!(matches!(result, Err(BuilderError::EndNotLast)));
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{fmt::Display, io::Write};
 
 use thiserror::Error;
 
@@ -9,13 +9,42 @@ pub enum ChunkError {
     #[error("Invalid chunk lenght: {0}")]
     InvalidLength(usize),
     #[error(transparent)]
-    ConversionError(#[from] Box<dyn std::error::Error>),
+    ConversionError(#[from] Box<dyn std::error::Error + Send + Sync>),
     #[error(transparent)]
     InvalidChunkType(#[from] ChunkTypeErr),
     #[error("Invalid CRC")]
     InvalidCrc,
+    #[error("Invalid hex chunk: {0}")]
+    InvalidHex(String),
+    #[error("Chunk length {0} does not fit in memory on this platform")]
+    LengthOverflow(u32),
 }
 
+// End of the data section (8 header bytes plus the declared length), checked
+// so that a huge length can't wrap around when usize is 32 bits wide
+fn data_end(length: u32) -> Result<usize, ChunkError> {
+    data_end_within(length, usize::MAX)
+}
+
+fn data_end_within(length: u32, max: usize) -> Result<usize, ChunkError> {
+    usize::try_from(length)
+        .ok()
+        .and_then(|length| length.checked_add(12))
+        .filter(|&total| total <= max)
+        .map(|total| total - 4)
+        .ok_or(ChunkError::LengthOverflow(length))
+}
+
+// Byte order of the CRC when serializing. The PNG spec requires big-endian;
+// little-endian only exists to interoperate with broken readers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CrcEndian {
+    #[default]
+    Big,
+    Little,
+}
+
+#[derive(PartialEq, Eq)]
 pub struct Chunk {
     length: u32,
     chunk_type: ChunkType,
@@ -41,33 +70,98 @@ impl Chunk {
         }
     }
 
-    fn length(&self) -> u32 {
+    // Parses a whole chunk (length, type, data and CRC) written as hex digits.
+    // Whitespace between bytes is ignored so copy-pasted dumps work.
+    pub fn from_hex(hex: &str) -> Result<Self, ChunkError> {
+        let digits: String = hex.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+        Chunk::try_from(&decode_hex(&digits)?[..])
+    }
+
+    pub fn length(&self) -> u32 {
         self.length
     }
 
-    fn crc(&self) -> u32 {
+    pub fn crc(&self) -> u32 {
         self.crc
     }
 
+    // Size in the file: the data plus the length, type and CRC fields
+    pub fn byte_len(&self) -> usize {
+        self.data.len() + 12
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
     pub fn chunk_type(&self) -> &ChunkType {
         &self.chunk_type
     }
 
+    // Call recompute afterwards, or the stored length and CRC go stale
+    pub fn data_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.data
+    }
+
+    pub fn recompute(&mut self) {
+        let mut bytes_to_checksum = vec![];
+        bytes_to_checksum.extend_from_slice(&self.chunk_type.bytes());
+        bytes_to_checksum.extend_from_slice(&self.data);
+
+        self.length = self.data.len() as u32;
+        self.crc = crc32fast::hash(bytes_to_checksum.as_ref());
+    }
+
+    pub fn is_type(&self, chunk_type: &str) -> bool {
+        self.chunk_type.bytes() == chunk_type.as_bytes()
+    }
+
     pub fn data_as_string(&self) -> Result<String, std::string::FromUtf8Error> {
         String::from_utf8(self.data.clone())
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
+        self.as_bytes_with_crc_endian(CrcEndian::Big)
+    }
+
+    pub fn as_bytes_with_crc_endian(&self, crc_endian: CrcEndian) -> Vec<u8> {
+        let crc = match crc_endian {
+            CrcEndian::Big => self.crc.to_be_bytes(),
+            CrcEndian::Little => self.crc.to_le_bytes(),
+        };
+
         let chunk_bytes: Vec<u8> = self.length.to_be_bytes()
         .iter()
         .chain(self.chunk_type.bytes().iter())
         .chain(self.data.iter())
-        .chain(self.crc.to_be_bytes().iter())
+        .chain(crc.iter())
         .copied()
         .collect();
 
         chunk_bytes
     }
+
+    // Parses a chunk whose stored CRC may be wrong, e.g. after hand-editing,
+    // and gives it the correct one. The flag says whether it needed fixing.
+    pub fn try_from_fixing_crc(value: &[u8]) -> Result<(Chunk, bool), ChunkError> {
+        match Chunk::try_from(value) {
+            // Length and type were already checked by the time the CRC is
+            Err(ChunkError::InvalidCrc) => {
+                let chunk_type = ChunkType::try_from(<[u8; 4]>::try_from(&value[4..8]).unwrap())?;
+                let data = value[8..value.len() - 4].to_vec();
+                Ok((Chunk::new(chunk_type, data), true))
+            }
+            result => result.map(|chunk| (chunk, false)),
+        }
+    }
+
+    // Same bytes as as_bytes, written straight to w without building a Vec
+    pub fn write_to<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        w.write_all(&self.length.to_be_bytes())?;
+        w.write_all(&self.chunk_type.bytes())?;
+        w.write_all(&self.data)?;
+        w.write_all(&self.crc.to_be_bytes())
+    }
 }
 
 impl TryFrom<&[u8]> for Chunk {
@@ -87,9 +181,16 @@ impl TryFrom<&[u8]> for Chunk {
             .try_into()
             .map_err(|e| ChunkError::ConversionError(Box::new(e)))?;
         let chunk_type = ChunkType::try_from(chunk_type_bytes)?;
-        let data = value[8..(8 + length as usize)].to_vec();
+
+        // The slice must hold exactly the declared data plus the CRC
+        let data_end = data_end(length)?;
+        if value.len() != data_end + 4 {
+            return Err(ChunkError::InvalidLength(value.len()));
+        }
+
+        let data = value[8..data_end].to_vec();
         let crc = u32::from_be_bytes(
-            value[8 + length as usize..]
+            value[data_end..]
                 .try_into()
                 .map_err(|e| ChunkError::ConversionError(Box::new(e)))?,
         );
@@ -111,16 +212,46 @@ impl TryFrom<&[u8]> for Chunk {
     }
 }
 
+// Decodes pairs of hex digits. Only 0-9, a-f and A-F count, so a sign or a
+// space inside a pair is an error rather than something from_str_radix allows.
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, ChunkError> {
+    let digits = hex.as_bytes();
+    if !digits.len().is_multiple_of(2) {
+        return Err(ChunkError::InvalidHex(format!(
+            "odd number of hex digits ({})",
+            digits.len()
+        )));
+    }
+
+    let digit = |byte: u8| char::from(byte).to_digit(16).map(|d| d as u8);
+    digits
+        .chunks(2)
+        .map(|pair| match (digit(pair[0]), digit(pair[1])) {
+            (Some(high), Some(low)) => Ok(high << 4 | low),
+            _ => Err(ChunkError::InvalidHex(format!(
+                "{:?} is not a hex byte",
+                String::from_utf8_lossy(pair)
+            ))),
+        })
+        .collect()
+}
+
+// Binary data like IHDR or IDAT is shown as hex instead of text. NUL stays
+// text since tEXt puts one between the keyword and the message.
 impl Display for Chunk {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Chunk Type: {}\nLength: {}\nData: {}\nCRC: {}",
-            self.chunk_type,
-            self.length,
-            self.data_as_string().unwrap(),
-            self.crc
-        )
+        write!(f, "Chunk Type: {}\nLength: {}\n", self.chunk_type, self.length)?;
+        let is_text = |c: char| !c.is_control() || matches!(c, '\0' | '\n' | '\r' | '\t');
+        match std::str::from_utf8(&self.data) {
+            Ok(text) if text.chars().all(is_text) => write!(f, "Data: {}", text)?,
+            _ => {
+                write!(f, "Data (hex): ")?;
+                for byte in &self.data {
+                    write!(f, "{:02x}", byte)?;
+                }
+            }
+        }
+        write!(f, "\nCRC: {}", self.crc)
     }
 }
 
@@ -165,12 +296,63 @@ mod tests {
         assert_eq!(chunk.length(), 42);
     }
 
+    #[test]
+    fn test_chunk_fixing_crc() {
+        let good = testing_chunk().as_bytes();
+        let (chunk, fixed) = Chunk::try_from_fixing_crc(&good).unwrap();
+        assert!(!fixed);
+        assert_eq!(chunk.as_bytes(), good);
+
+        let mut bad = good.clone();
+        let last = bad.len() - 1;
+        bad[last] ^= 0xff;
+        assert!(Chunk::try_from(bad.as_slice()).is_err());
+        let (chunk, fixed) = Chunk::try_from_fixing_crc(&bad).unwrap();
+        assert!(fixed);
+        assert_eq!(chunk.as_bytes(), good);
+
+        assert!(Chunk::try_from_fixing_crc(&good[..20]).is_err());
+    }
+
+    #[test]
+    fn test_chunk_write_to() {
+        let chunks = [
+            testing_chunk(),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0xff; 1000]),
+        ];
+
+        let mut written = Vec::new();
+        for chunk in &chunks {
+            chunk.write_to(&mut written).unwrap();
+        }
+        let expected: Vec<u8> = chunks.iter().flat_map(|ch| ch.as_bytes()).collect();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn test_chunk_byte_len() {
+        let chunk = testing_chunk();
+        assert_eq!(chunk.byte_len(), 54);
+        assert_eq!(chunk.byte_len(), chunk.as_bytes().len());
+    }
+
     #[test]
     fn test_chunk_type() {
         let chunk = testing_chunk();
         assert_eq!(chunk.chunk_type().to_string(), String::from("RuSt"));
     }
 
+    #[test]
+    fn test_chunk_is_type() {
+        let chunk = testing_chunk();
+        assert!(chunk.is_type("RuSt"));
+        assert!(!chunk.is_type("rust"));
+        assert!(!chunk.is_type("RUST"));
+        assert!(!chunk.is_type("RuS"));
+        assert!(!chunk.is_type("RuStX"));
+    }
+
     #[test]
     fn test_chunk_string() {
         let chunk = testing_chunk();
@@ -233,6 +415,113 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_chunk_from_bytes_with_oversized_length() {
+        let chunk_data: Vec<u8> = 1000u32
+            .to_be_bytes()
+            .iter()
+            .chain("RuSt".as_bytes().iter())
+            .chain([1, 2, 3, 4].iter())
+            .copied()
+            .collect();
+
+        assert!(matches!(
+            Chunk::try_from(chunk_data.as_ref()),
+            Err(ChunkError::InvalidLength(12))
+        ));
+    }
+
+    #[test]
+    fn test_data_end_guards_against_overflow() {
+        assert_eq!(data_end(42).unwrap(), 50);
+        assert_eq!(data_end_within(42, 54).unwrap(), 50);
+        // What a 32-bit target would see for the largest possible length
+        assert!(matches!(
+            data_end_within(u32::MAX, u32::MAX as usize),
+            Err(ChunkError::LengthOverflow(u32::MAX))
+        ));
+        assert!(matches!(
+            data_end_within(43, 54),
+            Err(ChunkError::LengthOverflow(43))
+        ));
+    }
+
+    #[test]
+    fn test_chunk_from_hex_str() {
+        let chunk = Chunk::from_hex("0000000049454e44ae426082").unwrap();
+        assert_eq!(chunk.length(), 0);
+        assert_eq!(chunk.chunk_type().to_string(), "IEND");
+        assert_eq!(chunk.crc(), 0xAE42_6082);
+
+        let chunk = Chunk::from_hex("00000002 52755374 6869 d2bc3f73").unwrap();
+        assert_eq!(chunk.chunk_type().to_string(), "RuSt");
+        assert_eq!(chunk.data(), b"hi");
+    }
+
+    #[test]
+    fn test_chunk_from_invalid_hex_str() {
+        assert!(matches!(
+            Chunk::from_hex("0000000049454e44ae42608"),
+            Err(ChunkError::InvalidHex(_))
+        ));
+        assert!(matches!(
+            Chunk::from_hex("0000000049454e44ae4260zz"),
+            Err(ChunkError::InvalidHex(_))
+        ));
+        assert!(matches!(
+            Chunk::from_hex("0000000049454e44ae426083"),
+            Err(ChunkError::InvalidCrc)
+        ));
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex("00fFa9").unwrap(), [0x00, 0xFF, 0xA9]);
+        assert_eq!(decode_hex("").unwrap(), []);
+        for bad in ["+f", "-1", " f", "f", "0x", "é0"] {
+            assert!(matches!(decode_hex(bad), Err(ChunkError::InvalidHex(_))), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_chunk_as_bytes_crc_endian() {
+        let chunk = testing_chunk();
+        let big = chunk.as_bytes_with_crc_endian(CrcEndian::Big);
+        let little = chunk.as_bytes_with_crc_endian(CrcEndian::Little);
+
+        assert_eq!(big, chunk.as_bytes());
+        assert_eq!(big[big.len() - 4..], 2882656334u32.to_be_bytes());
+        assert_eq!(little[little.len() - 4..], 2882656334u32.to_le_bytes());
+        assert_eq!(big[..big.len() - 4], little[..little.len() - 4]);
+    }
+
+    #[test]
+    fn test_chunk_eq() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        assert!(testing_chunk() == testing_chunk());
+        assert!(testing_chunk() != Chunk::new(chunk_type, b"other".to_vec()));
+
+        let lower = ChunkType::from_str("ruSt").unwrap();
+        let data = testing_chunk().data().to_vec();
+        assert!(testing_chunk() != Chunk::new(lower, data));
+    }
+
+    #[test]
+    fn test_chunk_recompute() {
+        let mut chunk = testing_chunk();
+        chunk.data_mut().extend_from_slice(b" And more.");
+        assert!(Chunk::try_from(chunk.as_bytes().as_slice()).is_err());
+
+        chunk.recompute();
+        assert_eq!(chunk.length(), 52);
+        let reparsed = Chunk::try_from(chunk.as_bytes().as_slice()).unwrap();
+        assert_eq!(reparsed.crc(), chunk.crc());
+        assert_eq!(
+            reparsed.data(),
+            b"This is where your secret message will be! And more."
+        );
+    }
+
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
@@ -253,4 +542,132 @@ mod tests {
 
         let _chunk_string = format!("{}", chunk);
     }
+
+    #[test]
+    fn test_display_binary_data() {
+        let chunk = Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0, 0, 0, 204, 0xff]);
+        let control = Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0, 2, 8]);
+
+        assert_eq!(
+            chunk.to_string(),
+            format!("Chunk Type: IHDR\nLength: 5\nData (hex): 000000ccff\nCRC: {}", chunk.crc())
+        );
+        assert!(control.to_string().contains("\nData (hex): 000208\n"));
+        assert!(testing_chunk()
+            .to_string()
+            .contains("\nData: This is where your secret message will be!\n"));
+    }
+
+    // Property tests over random chunk types and data. A seeded xorshift
+    // generator stands in for proptest; set PINGU_PROPERTY_SEED to replay a
+    // reported failure. Failing data is shrunk before it is reported.
+    const PROPERTY_CASES: usize = 256;
+    const PROPERTY_SEED: u64 = 0x5EED_C0FF_EE15_B00C;
+    const MAX_SHRINK_STEPS: usize = 1000;
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        // Any mix of cases, except the reserved (third) byte stays uppercase
+        fn chunk_type(&mut self) -> ChunkType {
+            let mut bytes = [0; 4];
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                let lowercase = i != 2 && self.below(2) == 1;
+                *byte = b'A' + self.below(26) as u8 + if lowercase { 32 } else { 0 };
+            }
+            ChunkType::try_from(bytes).unwrap()
+        }
+
+        // Mostly short data, with the boundaries around the 12-byte chunk
+        // overhead and a few larger sizes mixed in
+        fn data(&mut self) -> Vec<u8> {
+            const EDGE_LENGTHS: [u64; 8] = [0, 1, 4, 11, 12, 13, 255, 65_536];
+            let len = match self.below(4) {
+                0 => EDGE_LENGTHS[self.below(EDGE_LENGTHS.len() as u64) as usize],
+                _ => self.below(4096),
+            };
+            (0..len).map(|_| self.next() as u8).collect()
+        }
+    }
+
+    fn check_property(property: impl Fn(&mut Rng, ChunkType, &[u8]) -> Result<(), String>) {
+        let seed = std::env::var("PINGU_PROPERTY_SEED")
+            .ok()
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or(PROPERTY_SEED);
+        let mut rng = Rng(seed);
+
+        for case in 0..PROPERTY_CASES {
+            let chunk_type = rng.chunk_type();
+            let data = rng.data();
+            let Err(error) = property(&mut Rng(seed ^ case as u64), chunk_type, &data) else {
+                continue;
+            };
+
+            // Greedily keep whichever smaller input still fails
+            let fails = |data: &[u8]| property(&mut Rng(seed ^ case as u64), chunk_type, data);
+            let mut minimal = data;
+            for _ in 0..MAX_SHRINK_STEPS {
+                let half = minimal.len() / 2;
+                let candidates = [
+                    minimal[..half].to_vec(),
+                    minimal[half..].to_vec(),
+                    minimal[..minimal.len().saturating_sub(1)].to_vec(),
+                ];
+                match candidates
+                    .into_iter()
+                    .find(|c| c.len() < minimal.len() && fails(c).is_err())
+                {
+                    Some(smaller) => minimal = smaller,
+                    None => break,
+                }
+            }
+            panic!(
+                "case {} (seed {}) failed: {}\nminimal input: {} {:02x?}",
+                case, seed, error, chunk_type, minimal
+            );
+        }
+    }
+
+    #[test]
+    fn test_property_round_trip() {
+        check_property(|_, chunk_type, data| {
+            let chunk = Chunk::new(chunk_type, data.to_vec());
+            let bytes = chunk.as_bytes();
+            if bytes.len() != chunk.byte_len() {
+                return Err(format!("{} bytes, byte_len {}", bytes.len(), chunk.byte_len()));
+            }
+
+            let parsed = Chunk::try_from(bytes.as_slice()).map_err(|e| e.to_string())?;
+            if parsed != chunk || parsed.length() as usize != data.len() {
+                return Err("parsed chunk differs from the original".to_string());
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_property_bit_flip_is_rejected() {
+        check_property(|rng, chunk_type, data| {
+            let mut bytes = Chunk::new(chunk_type, data.to_vec()).as_bytes();
+            let bit = rng.below(bytes.len() as u64 * 8) as usize;
+            bytes[bit / 8] ^= 1 << (bit % 8);
+
+            match Chunk::try_from(bytes.as_slice()) {
+                Ok(_) => Err(format!("flipping bit {} went unnoticed", bit)),
+                Err(_) => Ok(()),
+            }
+        });
+    }
 }
@@ -1,7 +1,4 @@
-use std::{
-    fmt::Display,
-    str::{self, FromStr},
-};
+use std::{fmt::Display, str::FromStr};
 
 use thiserror::Error;
 
@@ -59,9 +56,13 @@ impl FromStr for ChunkType {
     }
 }
 
+// Both constructors only accept ASCII letters, so every byte is its own char
+// and the four bytes are echoed exactly, case included.
 impl Display for ChunkType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", str::from_utf8(&self.bytes).map_err(|_| std::fmt::Error)?)
+        self.bytes
+            .iter()
+            .try_for_each(|&byte| write!(f, "{}", byte as char))
     }
 }
 
@@ -90,6 +91,60 @@ impl ChunkType {
     pub fn is_valid(&self) -> bool {
         self.is_reserved_bit_valid()
     }
+
+    pub fn is_standard(&self) -> bool {
+        self.standard_description().is_some()
+    }
+
+    // Guidance for stripping chunks: critical chunks are always needed, and
+    // ancillary chunks that aren't safe to copy may describe the image data
+    pub fn removal_class(&self) -> &'static str {
+        match (self.is_critical(), self.is_safe_to_copy()) {
+            (true, _) => "required",
+            (false, false) => "optional-keep",
+            (false, true) => "optional-drop",
+        }
+    }
+
+    // Short human-readable name for chunk types defined by the PNG spec
+    pub fn description(&self) -> &'static str {
+        match self.standard_description() {
+            Some(description) => description,
+            None if self.is_public() => "Unknown public chunk",
+            None => "Unknown private chunk",
+        }
+    }
+
+    fn standard_description(&self) -> Option<&'static str> {
+        let description = match &self.bytes {
+            b"IHDR" => "Image header",
+            b"PLTE" => "Palette",
+            b"IDAT" => "Image data",
+            b"IEND" => "Image trailer",
+            b"acTL" => "Animation control",
+            b"cHRM" => "Primary chromaticities",
+            b"cICP" => "Coding-independent code points",
+            b"cLLi" => "Content light level",
+            b"eXIf" => "Exif metadata",
+            b"fcTL" => "Frame control",
+            b"fdAT" => "Frame data",
+            b"gAMA" => "Image gamma",
+            b"hIST" => "Palette histogram",
+            b"iCCP" => "Embedded ICC profile",
+            b"iTXt" => "International text",
+            b"mDCv" => "Mastering display color volume",
+            b"pHYs" => "Physical pixel dimensions",
+            b"sBIT" => "Significant bits",
+            b"sPLT" => "Suggested palette",
+            b"sRGB" => "Standard RGB color space",
+            b"tEXt" => "Text",
+            b"tIME" => "Last modification time",
+            b"tRNS" => "Transparency",
+            b"zTXt" => "Compressed text",
+            _ => return None,
+        };
+        Some(description)
+    }
 }
 
 #[cfg(test)]
@@ -180,6 +235,96 @@ mod tests {
         assert_eq!(&chunk.to_string(), "RuSt");
     }
 
+    // Every upper/lower case pattern of a few base types
+    fn case_variants(base: &str) -> Vec<String> {
+        (0..16u8)
+            .map(|mask| {
+                base.chars()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        if mask & (1 << i) != 0 {
+                            c.to_ascii_uppercase()
+                        } else {
+                            c.to_ascii_lowercase()
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn test_chunk_type_string_round_trip_all_cases() {
+        for base in ["rust", "iend", "text", "zzaa"] {
+            for s in case_variants(base) {
+                let chunk_type = ChunkType::from_str(&s).unwrap();
+                assert_eq!(chunk_type.to_string(), s);
+                assert_eq!(chunk_type.bytes(), s.as_bytes());
+                assert_eq!(ChunkType::from_str(&chunk_type.to_string()).unwrap(), chunk_type);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_chunk_type_string_round_trip_every_letter() {
+        for letter in (b'a'..=b'z').chain(b'A'..=b'Z') {
+            for position in 0..4 {
+                let mut bytes = *b"RuSt";
+                bytes[position] = letter;
+                let s = std::str::from_utf8(&bytes).unwrap();
+                assert_eq!(ChunkType::from_str(s).unwrap().to_string(), s);
+                assert_eq!(ChunkType::try_from(bytes).unwrap().to_string(), s);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_chunk_type_case_variants_are_distinct() {
+        let variants = case_variants("rust");
+        for (i, a) in variants.iter().enumerate() {
+            for b in &variants[i + 1..] {
+                assert_ne!(
+                    ChunkType::from_str(a).unwrap(),
+                    ChunkType::from_str(b).unwrap()
+                );
+            }
+        }
+
+        // Multi-byte characters can make a 4-byte string that isn't 4 letters
+        assert!(ChunkType::from_str("ab\u{e9}").is_err());
+        assert!(ChunkType::from_str("\u{1F980}").is_err());
+    }
+
+    #[test]
+    pub fn test_chunk_type_description() {
+        let describe = |s| ChunkType::from_str(s).unwrap().description();
+        assert_eq!(describe("gAMA"), "Image gamma");
+        assert_eq!(describe("sRGB"), "Standard RGB color space");
+        assert_eq!(describe("pHYs"), "Physical pixel dimensions");
+        assert_eq!(describe("IEND"), "Image trailer");
+        assert_eq!(describe("GAMA"), "Unknown public chunk");
+        assert_eq!(describe("ruSt"), "Unknown private chunk");
+    }
+
+    #[test]
+    pub fn test_chunk_type_removal_class() {
+        let classify = |s| ChunkType::from_str(s).unwrap().removal_class();
+        assert_eq!(classify("IHDR"), "required");
+        assert_eq!(classify("IDAT"), "required");
+        assert_eq!(classify("gAMA"), "optional-keep");
+        assert_eq!(classify("cHRM"), "optional-keep");
+        assert_eq!(classify("tEXt"), "optional-drop");
+        assert_eq!(classify("ruSt"), "optional-drop");
+    }
+
+    #[test]
+    pub fn test_chunk_type_is_standard() {
+        assert!(ChunkType::from_str("IDAT").unwrap().is_standard());
+        assert!(ChunkType::from_str("tEXt").unwrap().is_standard());
+        assert!(!ChunkType::from_str("ruSt").unwrap().is_standard());
+        assert!(!ChunkType::from_str("TEXT").unwrap().is_standard());
+    }
+
     #[test]
     pub fn test_chunk_type_trait_impls() {
         let chunk_type_1: ChunkType = TryFrom::try_from([82, 117, 83, 116]).unwrap();
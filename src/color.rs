@@ -0,0 +1,203 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ColorError {
+    #[error("{chunk_type} must be {expected} bytes, found {found}")]
+    InvalidLength {
+        chunk_type: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Unknown sRGB rendering intent: {0}")]
+    UnknownIntent(u8),
+}
+
+fn check_length(chunk_type: &'static str, data: &[u8], expected: usize) -> Result<(), ColorError> {
+    if data.len() != expected {
+        return Err(ColorError::InvalidLength {
+            chunk_type,
+            expected,
+            found: data.len(),
+        });
+    }
+    Ok(())
+}
+
+fn read_u32(data: &[u8], index: usize) -> u32 {
+    let at = 4 * index;
+    u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+// gAMA stores the image gamma times 100000
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Gamma(pub u32);
+
+impl Gamma {
+    pub fn value(&self) -> f64 {
+        self.0 as f64 / 100_000.0
+    }
+}
+
+impl TryFrom<&[u8]> for Gamma {
+    type Error = ColorError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        check_length("gAMA", value, 4)?;
+        Ok(Gamma(read_u32(value, 0)))
+    }
+}
+
+impl Display for Gamma {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.5}", self.value())
+    }
+}
+
+// cHRM: x and y of the white point and the three primaries, each times 100000
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Chromaticities {
+    pub white: (u32, u32),
+    pub red: (u32, u32),
+    pub green: (u32, u32),
+    pub blue: (u32, u32),
+}
+
+impl TryFrom<&[u8]> for Chromaticities {
+    type Error = ColorError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        check_length("cHRM", value, 32)?;
+        let point = |i: usize| (read_u32(value, 2 * i), read_u32(value, 2 * i + 1));
+        Ok(Chromaticities {
+            white: point(0),
+            red: point(1),
+            green: point(2),
+            blue: point(3),
+        })
+    }
+}
+
+impl Display for Chromaticities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let points = [
+            ("white", self.white),
+            ("red", self.red),
+            ("green", self.green),
+            ("blue", self.blue),
+        ];
+        for (i, (name, (x, y))) in points.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(
+                f,
+                "{} ({:.5}, {:.5})",
+                name,
+                *x as f64 / 100_000.0,
+                *y as f64 / 100_000.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// sRGB holds a single byte: how to map colors outside the display's gamut
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RenderingIntent {
+    Perceptual,
+    RelativeColorimetric,
+    Saturation,
+    AbsoluteColorimetric,
+}
+
+impl TryFrom<&[u8]> for RenderingIntent {
+    type Error = ColorError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        check_length("sRGB", value, 1)?;
+        match value[0] {
+            0 => Ok(RenderingIntent::Perceptual),
+            1 => Ok(RenderingIntent::RelativeColorimetric),
+            2 => Ok(RenderingIntent::Saturation),
+            3 => Ok(RenderingIntent::AbsoluteColorimetric),
+            other => Err(ColorError::UnknownIntent(other)),
+        }
+    }
+}
+
+impl Display for RenderingIntent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RenderingIntent::Perceptual => "perceptual",
+            RenderingIntent::RelativeColorimetric => "relative colorimetric",
+            RenderingIntent::Saturation => "saturation",
+            RenderingIntent::AbsoluteColorimetric => "absolute colorimetric",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The sRGB primaries and D65 white point, as the spec recommends
+    // writing them alongside an sRGB chunk
+    fn srgb_chrm_bytes() -> Vec<u8> {
+        [31270, 32900, 64000, 33000, 30000, 60000, 15000, 6000]
+            .iter()
+            .flat_map(|v: &u32| v.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_gamma() {
+        let gamma = Gamma::try_from(&[0, 0, 177, 143][..]).unwrap();
+
+        assert_eq!(gamma, Gamma(45455));
+        assert!((gamma.value() - 0.45455).abs() < 1e-9);
+        assert_eq!(gamma.to_string(), "0.45455");
+    }
+
+    #[test]
+    fn test_chromaticities() {
+        let chrm = Chromaticities::try_from(srgb_chrm_bytes().as_slice()).unwrap();
+
+        assert_eq!(chrm.white, (31270, 32900));
+        assert_eq!(chrm.blue, (15000, 6000));
+        assert_eq!(
+            chrm.to_string(),
+            "white (0.31270, 0.32900), red (0.64000, 0.33000), \
+             green (0.30000, 0.60000), blue (0.15000, 0.06000)"
+        );
+    }
+
+    #[test]
+    fn test_rendering_intent() {
+        let intent = |byte: u8| RenderingIntent::try_from(&[byte][..]);
+
+        assert_eq!(intent(0), Ok(RenderingIntent::Perceptual));
+        assert_eq!(intent(3), Ok(RenderingIntent::AbsoluteColorimetric));
+        assert_eq!(intent(1).unwrap().to_string(), "relative colorimetric");
+        assert_eq!(intent(4), Err(ColorError::UnknownIntent(4)));
+    }
+
+    #[test]
+    fn test_invalid_lengths() {
+        assert_eq!(
+            Gamma::try_from(&[0, 1][..]),
+            Err(ColorError::InvalidLength {
+                chunk_type: "gAMA",
+                expected: 4,
+                found: 2
+            })
+        );
+        assert!(Chromaticities::try_from(&srgb_chrm_bytes()[..31]).is_err());
+        assert_eq!(
+            RenderingIntent::try_from(&[][..]).unwrap_err().to_string(),
+            "sRGB must be 1 bytes, found 0"
+        );
+    }
+}
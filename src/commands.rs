@@ -0,0 +1,2015 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fmt::Write,
+    io::Write as _,
+    path::Path,
+    time::{Duration, Instant, SystemTime},
+};
+
+use pingu::{
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    color::Gamma,
+    handler::HandlerRegistry,
+    ihdr::Ihdr,
+    time::Time,
+    png::{Png, PngError},
+    zlib::{self, ZlibError},
+};
+use thiserror::Error;
+
+use crate::{
+    args::{self, ChunkNaming, HashAlgo, Newlines},
+    json::Value,
+    zip::{ZipError, ZipWriter},
+};
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error(
+        "Chunk {chunk_type} is not valid UTF-8 (first invalid byte at offset {offset}); \
+         use --format hex or --output <FILE> to get the raw bytes"
+    )]
+    InvalidUtf8 { chunk_type: String, offset: usize },
+    #[error("Output directory {0} does not exist; pass --create-dirs to create it")]
+    MissingOutputDir(String),
+    #[error(
+        "PNG already has a {0} chunk; pass --allow-duplicate to add another \
+         or --replace to overwrite it"
+    )]
+    DuplicateChunk(String),
+    #[error(
+        "{0} is a critical chunk with a fixed layout; carrying a message in it \
+         would corrupt the image (pass --force to do it anyway)"
+    )]
+    ReservedChunkType(String),
+    #[error("Chunk {chunk_type} does not hold valid JSON: {source}")]
+    InvalidJson {
+        chunk_type: String,
+        source: crate::json::JsonError,
+    },
+    #[error("Verification failed: {0}")]
+    VerificationFailed(String),
+    #[error("Chunk {chunk_type} does not match the expected message\n{diff}")]
+    Mismatch { chunk_type: String, diff: String },
+    #[error("No {SIGNATURE_CHUNK} chunk found; sign the file first")]
+    MissingSignature,
+    #[error("Found {0} {SIGNATURE_CHUNK} chunks; a signed file has exactly one")]
+    MultipleSignatures(usize),
+    #[error("{SIGNATURE_CHUNK} chunk must hold a 64-byte signature, found {0} bytes")]
+    InvalidSignatureLength(usize),
+    #[error(
+        "Signature does not match: the file was changed after signing, \
+         or signed with another key"
+    )]
+    SignatureMismatch,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+// First max bytes of data for printing, 0 meaning no limit. A cut through a
+// UTF-8 sequence moves back to the start of that character so the shown
+// part of a text message still decodes.
+pub fn truncate_output(data: &[u8], max: usize) -> &[u8] {
+    if max == 0 || data.len() <= max {
+        return data;
+    }
+
+    let shown = &data[..max];
+    match std::str::from_utf8(shown) {
+        Err(e) if e.error_len().is_none() => &shown[..e.valid_up_to()],
+        _ => shown,
+    }
+}
+
+// Lossy decoding swaps invalid bytes for U+FFFD instead of failing
+pub fn decode_text(chunk_type: &str, data: &[u8], lossy: bool) -> Result<String, CommandError> {
+    if lossy {
+        return Ok(String::from_utf8_lossy(data).into_owned());
+    }
+    String::from_utf8(data.to_vec()).map_err(|e| CommandError::InvalidUtf8 {
+        chunk_type: chunk_type.to_string(),
+        offset: e.utf8_error().valid_up_to(),
+    })
+}
+
+pub fn pretty_json(chunk_type: &str, message: &str) -> Result<String, CommandError> {
+    crate::json::parse(message)
+        .map(|value| value.to_pretty())
+        .map_err(|source| CommandError::InvalidJson {
+            chunk_type: chunk_type.to_string(),
+            source,
+        })
+}
+
+// Header lines for decode --with-meta, from the chunk's length and CRC
+pub fn chunk_meta(chunk_type: &str, (length, crc): (u32, u32)) -> String {
+    format!("type: {}\nlength: {}\ncrc: {:08x}\n", chunk_type, length, crc)
+}
+
+pub fn decoded_json(chunk_type: &str, message: &str, meta: Option<(u32, u32)>) -> Value {
+    let mut fields = vec![
+        ("type".to_string(), Value::from(chunk_type)),
+        ("message".to_string(), Value::from(message)),
+    ];
+    if let Some((length, crc)) = meta {
+        fields.push(("length".to_string(), Value::from(length as u64)));
+        fields.push(("crc".to_string(), Value::from(format!("{:08x}", crc))));
+    }
+    Value::Object(fields)
+}
+
+// One line of print --jsonl, written as soon as the chunk is read
+pub fn chunk_json_line(index: usize, offset: usize, chunk: &Chunk) -> String {
+    let fields = vec![
+        ("index".to_string(), Value::from(index as u64)),
+        ("type".to_string(), Value::from(chunk.chunk_type().to_string())),
+        ("offset".to_string(), Value::from(offset as u64)),
+        ("length".to_string(), Value::from(chunk.length() as u64)),
+        ("crc".to_string(), Value::from(format!("{:08x}", chunk.crc()))),
+    ];
+    format!("{}\n", Value::Object(fields))
+}
+
+// What to write for a PNG, after global --gzip-output
+pub fn png_output(bytes: &[u8], gzip: bool) -> Cow<'_, [u8]> {
+    if gzip {
+        Cow::Owned(zlib::gzip_stored(bytes))
+    } else {
+        Cow::Borrowed(bytes)
+    }
+}
+
+pub fn write_output(path: &Path, bytes: &[u8], create_dirs: bool) -> Result<(), CommandError> {
+    if args::is_stdio(path) {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(bytes)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if create_dirs {
+            std::fs::create_dir_all(parent)?;
+        } else if !parent.is_dir() {
+            return Err(CommandError::MissingOutputDir(parent.display().to_string()));
+        }
+    }
+
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+// Modification time to carry over to the output, when asked for
+pub fn input_mtime(path: &Path, preserve: bool) -> Result<Option<SystemTime>, CommandError> {
+    if !preserve {
+        return Ok(None);
+    }
+    if args::is_stdio(path) {
+        return Err(CommandError::Io(std::io::Error::other(
+            "--preserve-mtime needs a --png file, not stdin",
+        )));
+    }
+    Ok(Some(std::fs::metadata(path)?.modified()?))
+}
+
+pub fn restore_mtime(path: &Path, mtime: Option<SystemTime>) -> Result<(), CommandError> {
+    match mtime {
+        Some(mtime) if !args::is_stdio(path) => {
+            std::fs::File::options()
+                .write(true)
+                .open(path)?
+                .set_modified(mtime)?;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+// Re-parses encoded output the way a standard decoder would and checks the
+// message comes back intact. A chunk type of None means the message is in LSBs.
+pub fn verify_encoded(
+    bytes: &[u8],
+    chunk_type: Option<&str>,
+    message: &[u8],
+) -> Result<(), CommandError> {
+    let failed = CommandError::VerificationFailed;
+    let png = Png::try_from(bytes).map_err(|e| failed(e.to_string()))?;
+
+    match chunk_type {
+        Some(chunk_type) => {
+            if !png
+                .iter_chunks()
+                .any(|ch| ch.is_type(chunk_type) && ch.data() == message)
+            {
+                return Err(failed(format!("no {} chunk holds the message", chunk_type)));
+            }
+        }
+        None => {
+            let decoded = pingu::lsb::extract(&png).map_err(|e| failed(e.to_string()))?;
+            if decoded != message {
+                return Err(failed("LSB message does not match".to_string()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Messages bigger than this need confirming before they are embedded
+pub const LARGE_MESSAGE_BYTES: usize = 1024 * 1024;
+
+pub fn message_size_report(chunk: &Chunk) -> String {
+    format!(
+        "message: {} bytes, {} chunk: {} bytes",
+        chunk.data().len(),
+        chunk.chunk_type(),
+        chunk.byte_len()
+    )
+}
+
+pub fn size_warning(chunk: &Chunk, threshold: usize, raw: bool) -> Option<String> {
+    (chunk.data().len() > threshold).then(|| {
+        format!(
+            "warning: the message is larger than {}; the PNG will grow by {}",
+            format_size(threshold as u64, raw),
+            format_size(chunk.byte_len() as u64, raw)
+        )
+    })
+}
+
+// Asks on the terminal; without one there is nobody to answer, so the
+// caller has to pass --yes instead
+pub fn confirm(question: &str) -> Result<bool, CommandError> {
+    use std::io::IsTerminal;
+
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(false);
+    }
+    eprint!("{} [y/N] ", question);
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+pub fn check_duplicate(png: &Png, chunk_type: &str, allow: bool) -> Result<(), CommandError> {
+    if !allow && png.chunk_by_type(chunk_type).is_some() {
+        return Err(CommandError::DuplicateChunk(chunk_type.to_string()));
+    }
+    Ok(())
+}
+
+// Critical chunks whose contents the decoder interprets
+const RESERVED_TYPES: [&str; 4] = ["IHDR", "PLTE", "IDAT", "IEND"];
+
+pub fn check_reserved(chunk_type: &str, force: bool) -> Result<(), CommandError> {
+    if !force && RESERVED_TYPES.contains(&chunk_type) {
+        return Err(CommandError::ReservedChunkType(chunk_type.to_string()));
+    }
+    Ok(())
+}
+
+// Expands the backslash escapes accepted by --delimiter; anything else is literal
+pub fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('0') => out.push('\0'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+pub fn join_messages(chunks: &[&Chunk], delimiter: &str) -> Vec<u8> {
+    let messages: Vec<&[u8]> = chunks.iter().map(|ch| ch.data()).collect();
+    messages.join(unescape(delimiter).as_bytes())
+}
+
+// Payloads embedded with --preserve-name start with this marker, a big-endian
+// u16 name length and the UTF-8 file name
+const NAMED_PAYLOAD_MAGIC: &[u8; 4] = b"pgFN";
+
+pub fn wrap_named_payload(name: &str, data: &[u8]) -> Vec<u8> {
+    let name = &name.as_bytes()[..name.len().min(u16::MAX as usize)];
+    let mut payload = Vec::with_capacity(6 + name.len() + data.len());
+    payload.extend_from_slice(NAMED_PAYLOAD_MAGIC);
+    payload.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    payload.extend_from_slice(name);
+    payload.extend_from_slice(data);
+    payload
+}
+
+// Returns the embedded name and the payload after it. Names that aren't a
+// plain file name (empty, a path, "." or "..") are treated as absent.
+pub fn unwrap_named_payload(data: &[u8]) -> Option<(&str, &[u8])> {
+    let rest = data.strip_prefix(NAMED_PAYLOAD_MAGIC)?;
+    let len = u16::from_be_bytes(rest.get(..2)?.try_into().unwrap()) as usize;
+    let name = std::str::from_utf8(rest.get(2..2 + len)?).ok()?;
+
+    let is_plain = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', '\0']);
+    is_plain.then(|| (name, &rest[2 + len..]))
+}
+
+pub fn assert_message(chunk_type: &str, actual: &[u8], expected: &[u8]) -> Result<(), CommandError> {
+    if actual == expected {
+        return Ok(());
+    }
+
+    Err(CommandError::Mismatch {
+        chunk_type: chunk_type.to_string(),
+        diff: line_diff(
+            &String::from_utf8_lossy(expected),
+            &String::from_utf8_lossy(actual),
+        )
+        .trim_end()
+        .to_string(),
+    })
+}
+
+// Unified-style line diff from expected to actual, built on the longest
+// common subsequence of lines. Messages are small, so O(n*m) is fine.
+pub fn line_diff(expected: &str, actual: &str) -> String {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::from("--- expected\n+++ actual\n");
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            writeln!(diff, " {}", a[i]).unwrap();
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            writeln!(diff, "-{}", a[i]).unwrap();
+            i += 1;
+        } else {
+            writeln!(diff, "+{}", b[j]).unwrap();
+            j += 1;
+        }
+    }
+    diff
+}
+
+// XORs with the key repeated over the data; applying it twice gives the
+// original back. Anyone with the output can recover the key, so this only
+// keeps the message from showing up in a hex dump.
+pub fn xor_mask(data: &[u8], key: &[u8]) -> Vec<u8> {
+    data.iter()
+        .zip(key.iter().cycle())
+        .map(|(byte, k)| byte ^ k)
+        .collect()
+}
+
+pub fn normalize_newlines(text: &[u8], newlines: Newlines) -> Vec<u8> {
+    let ending: &[u8] = match newlines {
+        Newlines::None => return text.to_vec(),
+        Newlines::Lf => b"\n",
+        Newlines::Crlf => b"\r\n",
+    };
+
+    let mut normalized = Vec::with_capacity(text.len());
+    let mut bytes = text.iter().peekable();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            b'\r' => {
+                bytes.next_if_eq(&&b'\n');
+                normalized.extend_from_slice(ending);
+            }
+            b'\n' => normalized.extend_from_slice(ending),
+            _ => normalized.push(byte),
+        }
+    }
+    normalized
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn pngcheck_report(name: &str, png: &Png, file_len: usize) -> String {
+    pngcheck_report_with(name, png, file_len, &HandlerRegistry::standard())
+}
+
+pub fn pngcheck_report_with(
+    name: &str,
+    png: &Png,
+    file_len: usize,
+    handlers: &HandlerRegistry,
+) -> String {
+    let mut report = String::new();
+    let mut chunk_count = 0;
+    let mut ihdr = None;
+
+    writeln!(report, "File: {} ({} bytes)", name, file_len).unwrap();
+    for (offset, chunk) in png.chunks_with_offsets() {
+        chunk_count += 1;
+        write!(
+            report,
+            "  chunk {} at offset 0x{:05x}, length {}",
+            chunk.chunk_type(),
+            offset,
+            chunk.length()
+        )
+        .unwrap();
+
+        match pngcheck_interpretation(chunk, handlers) {
+            Some(PngcheckLine::Inline(text)) => writeln!(report, ": {}", text).unwrap(),
+            Some(PngcheckLine::Indented(text)) => {
+                writeln!(report).unwrap();
+                writeln!(report, "    {}", text).unwrap();
+            }
+            None => writeln!(report).unwrap(),
+        }
+
+        if ihdr.is_none() {
+            ihdr = Ihdr::try_from(chunk).ok();
+        }
+    }
+
+    write!(
+        report,
+        "No errors detected in {} ({} chunks",
+        name, chunk_count
+    )
+    .unwrap();
+    if let Some(ihdr) = ihdr {
+        let row_bytes = (ihdr.width as u64 * ihdr.bits_per_pixel() as u64).div_ceil(8) + 1;
+        let raw_len = row_bytes * ihdr.height as u64;
+        if raw_len > 0 {
+            let ratio = 100.0 * (1.0 - file_len as f64 / raw_len as f64);
+            write!(report, ", {:.1}% compression", ratio).unwrap();
+        }
+    }
+    writeln!(report, ").").unwrap();
+
+    report
+}
+
+pub fn chunk_descriptions(png: &Png, raw_sizes: bool, preview: Option<usize>) -> String {
+    let mut listing = String::new();
+
+    for (index, chunk) in png.iter_chunks().enumerate() {
+        write!(
+            listing,
+            "{:>3}  {}  {:>10}  {:<13}  {}",
+            index,
+            chunk.chunk_type(),
+            format_size(chunk.length() as u64, raw_sizes),
+            chunk.chunk_type().removal_class(),
+            chunk.chunk_type().description()
+        )
+        .unwrap();
+        if looks_compressed(chunk) {
+            listing.push_str("  compressed: likely");
+        }
+        if let Some(len) = preview.filter(|_| chunk.length() > 0) {
+            write!(listing, "  {}", data_preview(chunk.data(), len)).unwrap();
+        }
+        listing.push('\n');
+    }
+
+    listing
+}
+
+// The first len bytes, quoted if they are printable ASCII and as hex otherwise
+pub fn data_preview(data: &[u8], len: usize) -> String {
+    let shown = &data[..len.min(data.len())];
+    let more = if shown.len() < data.len() { "..." } else { "" };
+    if shown.iter().all(|&b| b == b' ' || b.is_ascii_graphic()) {
+        format!("\"{}\"{}", String::from_utf8_lossy(shown), more)
+    } else {
+        format!("{}{}", to_hex(shown), more)
+    }
+}
+
+// zTXt puts a keyword and a method byte ahead of the zlib stream; everything
+// else is checked from the start of the data
+pub fn looks_compressed(chunk: &Chunk) -> bool {
+    let data = chunk.data();
+    let stream = match data.iter().position(|&b| b == 0) {
+        Some(end) if chunk.is_type("zTXt") => data.get(end + 2..).unwrap_or_default(),
+        _ => data,
+    };
+    zlib::has_zlib_header(stream)
+}
+
+// Same output as printing the whole PNG, restricted to the given types
+pub fn filter_chunks(png: &Png, chunk_types: &[ChunkType]) -> String {
+    png.iter_chunks()
+        .filter(|ch| chunk_types.iter().any(|t| ch.is_type(&t.to_string())))
+        .map(|ch| ch.to_string())
+        .collect()
+}
+
+// What an encode did to the file, given the chunk count and size from before
+// and the index of the chunk it added or changed
+pub fn change_summary(
+    (chunks_before, len_before): (usize, usize),
+    png: &Png,
+    len_after: usize,
+    index: Option<usize>,
+) -> String {
+    let signed = |after: usize, before: usize| after as i64 - before as i64;
+    let mut summary = String::new();
+
+    let chunks_after = png.chunk_count();
+    writeln!(
+        summary,
+        "chunks: {} -> {} ({:+})",
+        chunks_before,
+        chunks_after,
+        signed(chunks_after, chunks_before)
+    )
+    .unwrap();
+    writeln!(
+        summary,
+        "size: {} -> {} bytes ({:+})",
+        len_before,
+        len_after,
+        signed(len_after, len_before)
+    )
+    .unwrap();
+
+    // The offset is in the written file, which has a signature even when the
+    // input was a bare chunk stream
+    let written = index.and_then(|i| {
+        let offset = Png::STANDARD_HEADER.len()
+            + png.iter_chunks().take(i).map(|ch| ch.length() as usize + 12).sum::<usize>();
+        png.iter_chunks().nth(i).map(|chunk| (offset, chunk))
+    });
+    match written {
+        Some((offset, chunk)) => writeln!(
+            summary,
+            "{} chunk: {} at index {}, offset 0x{:05x}, {} data bytes",
+            if chunks_after > chunks_before { "new" } else { "changed" },
+            chunk.chunk_type(),
+            index.unwrap(),
+            offset,
+            chunk.length()
+        )
+        .unwrap(),
+        None if len_after == len_before && chunks_after == chunks_before => {
+            writeln!(summary, "no chunk changed").unwrap()
+        }
+        None => writeln!(summary, "image data rewritten").unwrap(),
+    }
+
+    summary
+}
+
+pub fn chunk_layout(png: &Png, highlight: Option<usize>, raw_sizes: bool) -> String {
+    let mut layout = String::new();
+
+    for (index, chunk) in png.iter_chunks().enumerate() {
+        let marker = if highlight == Some(index) { ">" } else { " " };
+        write!(
+            layout,
+            "{} {:>3}  {}  {:>10}",
+            marker,
+            index,
+            chunk.chunk_type(),
+            format_size(chunk.length() as u64, raw_sizes)
+        )
+        .unwrap();
+        if highlight == Some(index) {
+            write!(layout, "  (new)").unwrap();
+        }
+        writeln!(layout).unwrap();
+    }
+
+    layout
+}
+
+// Index names keep the chunk's place in the file; hash names depend only on
+// the data, so archives from many files can share one deduplicated store
+pub fn chunk_entry_name(index: usize, chunk: &Chunk, naming: ChunkNaming) -> String {
+    match naming {
+        ChunkNaming::Index => format!("{:04}-{}.bin", index, chunk.chunk_type()),
+        ChunkNaming::Hash => format!("{}.chunk", file_hash(chunk.data(), HashAlgo::default())),
+    }
+}
+
+// Bundles every chunk of the type into a zip, one entry per chunk. Returns the
+// number of chunks alongside the archive; with hash naming a chunk whose data
+// is already in the archive isn't stored again.
+pub fn extract_all_zip(
+    png: &Png,
+    chunk_type: &str,
+    naming: ChunkNaming,
+) -> Result<(usize, Vec<u8>), ZipError> {
+    let mut zip = ZipWriter::new();
+    let mut names = HashSet::new();
+    let mut count = 0;
+
+    for (index, chunk) in png.iter_chunks().enumerate() {
+        if chunk.is_type(chunk_type) {
+            let name = chunk_entry_name(index, chunk, naming);
+            if names.insert(name.clone()) {
+                zip.add_entry(&name, chunk.data())?;
+            }
+            count += 1;
+        }
+    }
+
+    Ok((count, zip.finish()?))
+}
+
+// The chunk remove --auto targets: never critical, never a type from the spec
+pub fn auto_removal_type(png: &Png) -> Option<ChunkType> {
+    png.iter_chunks()
+        .map(|ch| *ch.chunk_type())
+        .find(|ty| !ty.is_critical() && !ty.is_standard())
+}
+
+// With --count only the number of removed chunks is printed, for scripts
+pub fn removal_report(removed: &[Chunk], count: bool) -> String {
+    if count {
+        return format!("{}\n", removed.len());
+    }
+
+    removed.iter().map(|chunk| format!("{}\n", chunk)).collect()
+}
+
+// Raw data of the removed chunks, back to back, for remove --print-data
+pub fn removed_data(removed: &[Chunk]) -> Vec<u8> {
+    removed.iter().flat_map(|chunk| chunk.data()).copied().collect()
+}
+
+pub struct BenchStats {
+    pub iterations: usize,
+    pub mean: Duration,
+    pub median: Duration,
+    pub mb_per_sec: f64,
+}
+
+// Times a parse + serialize round trip of the input, after a short warm-up
+pub fn bench(
+    bytes: &[u8],
+    iterations: usize,
+    parse: impl Fn(&[u8]) -> Result<Png, PngError>,
+) -> Result<BenchStats, PngError> {
+    for _ in 0..(iterations / 10).clamp(1, 10) {
+        std::hint::black_box(parse(bytes)?.as_bytes());
+    }
+
+    let mut times = Vec::with_capacity(iterations);
+    for _ in 0..iterations.max(1) {
+        let start = Instant::now();
+        std::hint::black_box(parse(bytes)?.as_bytes());
+        times.push(start.elapsed());
+    }
+    times.sort();
+
+    let mean = times.iter().sum::<Duration>() / times.len() as u32;
+    // Guard against a zero reading from a coarse clock on tiny inputs
+    let mb_per_sec = bytes.len() as f64 / 1e6 / mean.as_secs_f64().max(1e-9);
+
+    Ok(BenchStats {
+        iterations: times.len(),
+        mean,
+        median: times[times.len() / 2],
+        mb_per_sec,
+    })
+}
+
+pub fn bench_report(stats: &BenchStats) -> String {
+    format!(
+        "iterations: {}\nmean: {:?}\nmedian: {:?}\nthroughput: {:.2} MB/s\n",
+        stats.iterations, stats.mean, stats.median, stats.mb_per_sec
+    )
+}
+
+// Spells out the meaning of each case bit of a chunk type and whether it is,
+// or is one case change away from, a type defined by the PNG spec
+pub fn check_type_report(chunk_type: &ChunkType) -> String {
+    let mut report = String::new();
+
+    if chunk_type.is_valid() {
+        writeln!(report, "{}: well-formed", chunk_type).unwrap();
+    } else {
+        writeln!(
+            report,
+            "{}: invalid, the reserved third letter must be uppercase",
+            chunk_type
+        )
+        .unwrap();
+    }
+
+    let bits = [
+        (chunk_type.is_critical(), "critical", "ancillary"),
+        (chunk_type.is_public(), "public", "private"),
+        (chunk_type.is_safe_to_copy(), "safe to copy", "unsafe to copy"),
+    ];
+    for (set, yes, no) in bits {
+        writeln!(report, "  {}", if set { yes } else { no }).unwrap();
+    }
+
+    if chunk_type.is_standard() {
+        writeln!(report, "  standard type: {}", chunk_type.description()).unwrap();
+        return report;
+    }
+
+    let same_letters = (1..16u8).find_map(|mask| {
+        let mut bytes = chunk_type.bytes();
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            if mask & (1 << i) != 0 {
+                *byte ^= 0x20;
+            }
+        }
+        ChunkType::try_from(bytes).ok().filter(|t| t.is_standard())
+    });
+    match same_letters {
+        Some(standard) => writeln!(
+            report,
+            "  differs only in case from standard type {} ({})",
+            standard,
+            standard.description()
+        )
+        .unwrap(),
+        None => writeln!(report, "  not a standard type").unwrap(),
+    }
+
+    report
+}
+
+// Chunks whose type isn't in the PNG spec, i.e. where hidden data usually is
+pub fn hidden_report(png: &Png, raw_sizes: bool) -> String {
+    let mut report = String::new();
+
+    for chunk in png.iter_chunks().filter(|ch| !ch.chunk_type().is_standard()) {
+        writeln!(
+            report,
+            "{}  {:>10}  {}",
+            chunk.chunk_type(),
+            format_size(chunk.length() as u64, raw_sizes),
+            preview(chunk.data(), 32)
+        )
+        .unwrap();
+    }
+
+    report
+}
+
+fn printable(b: u8) -> char {
+    if b.is_ascii_graphic() || b == b' ' {
+        b as char
+    } else {
+        '.'
+    }
+}
+
+// Printable ASCII rendering of the first bytes of data, '.' for the rest
+fn preview(data: &[u8], max_len: usize) -> String {
+    let mut text: String = data
+        .iter()
+        .take(max_len)
+        .map(|&b| printable(b))
+        .collect();
+    if data.len() > max_len {
+        text.push_str("...");
+    }
+    text
+}
+
+// The image stream is one zlib stream that may be split across many IDATs.
+// Inflating stops past limit bytes, as a few kilobytes can expand to gigabytes.
+pub fn idat_stream(png: &Png, decompress: bool, limit: usize) -> Result<Vec<u8>, ZlibError> {
+    let data = png.image_data();
+    if decompress {
+        return zlib::decompress_with_limit(&data, limit);
+    }
+    Ok(data)
+}
+
+pub fn trailing_report(png: &Png, raw_sizes: bool) -> String {
+    let trailing = png.trailing_bytes();
+    let mut report = String::new();
+
+    writeln!(
+        report,
+        "Trailing bytes after IEND: {}",
+        format_size(trailing.len() as u64, raw_sizes)
+    )
+    .unwrap();
+    if !trailing.is_empty() {
+        writeln!(report, "Looks like: {}", guess_file_type(trailing)).unwrap();
+    }
+    report.push_str(&hex_dump(trailing));
+    report
+}
+
+// Start and end of every occurrence of needle, without overlaps
+pub fn find_bytes(data: &[u8], needle: &[u8]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while let Some(at) = data
+        .get(start..)
+        .and_then(|rest| rest.windows(needle.len()).position(|w| w == needle))
+    {
+        matches.push((start + at, start + at + needle.len()));
+        start += at + needle.len();
+    }
+    matches
+}
+
+// One line per match: chunk index and type, offset in the chunk data, and
+// the match in brackets with up to context bytes either side
+pub fn grep_report(
+    png: &Png,
+    find: impl Fn(&[u8]) -> Vec<(usize, usize)>,
+    context: usize,
+) -> (String, usize) {
+    let mut report = String::new();
+    let mut count = 0;
+    for (index, chunk) in png.iter_chunks().enumerate() {
+        let data = chunk.data();
+        for (start, end) in find(data) {
+            let before = start.saturating_sub(context);
+            let after = end.saturating_add(context).min(data.len());
+            writeln!(
+                report,
+                "{:>3}  {}  +{}: {}{}[{}]{}{}",
+                index,
+                chunk.chunk_type(),
+                start,
+                if before > 0 { "..." } else { "" },
+                data[before..start].escape_ascii(),
+                data[start..end].escape_ascii(),
+                data[end..after].escape_ascii(),
+                if after < data.len() { "..." } else { "" }
+            )
+            .unwrap();
+            count += 1;
+        }
+    }
+    (report, count)
+}
+
+// Chunks with at least one match, for grep --count
+pub fn grep_count(png: &Png, find: impl Fn(&[u8]) -> Vec<(usize, usize)>) -> usize {
+    png.iter_chunks()
+        .filter(|chunk| !find(chunk.data()).is_empty())
+        .count()
+}
+
+pub fn crc_fix_report(png: &Png, fixed: &[usize]) -> String {
+    let mut report = String::new();
+    for (index, chunk) in png.iter_chunks().enumerate() {
+        if fixed.contains(&index) {
+            writeln!(report, "fixed CRC of {} chunk at index {}", chunk.chunk_type(), index)
+                .unwrap();
+        }
+    }
+    writeln!(report, "Corrected {} of {} CRCs", fixed.len(), png.chunk_count()).unwrap();
+    report
+}
+
+// Names the format of data appended to a PNG from its magic bytes
+pub fn guess_file_type(data: &[u8]) -> &'static str {
+    const MAGIC: [(&[u8], &str); 11] = [
+        (b"PK\x03\x04", "ZIP archive"),
+        (b"PK\x05\x06", "ZIP archive (empty)"),
+        (b"\x89PNG\r\n\x1a\n", "PNG image"),
+        (b"\xff\xd8\xff", "JPEG image"),
+        (b"GIF8", "GIF image"),
+        (b"%PDF", "PDF document"),
+        (b"\x1f\x8b", "gzip data"),
+        (b"BZh", "bzip2 data"),
+        (b"7z\xbc\xaf\x27\x1c", "7-Zip archive"),
+        (b"Rar!\x1a\x07", "RAR archive"),
+        (b"\x7fELF", "ELF executable"),
+    ];
+
+    if let Some((_, name)) = MAGIC.iter().find(|(magic, _)| data.starts_with(magic)) {
+        return name;
+    }
+    match std::str::from_utf8(data) {
+        Ok(text) if text.chars().all(|c| !c.is_control() || c.is_whitespace()) => "text",
+        _ => "unknown data",
+    }
+}
+
+// Sizes from 1 KiB up get a binary unit with one decimal, like ls -h; smaller
+// ones, and everything when raw is set, stay plain byte counts
+pub fn format_size(bytes: u64, raw: bool) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+    if raw || bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+// Digest of the whole file as lowercase hex
+pub fn file_hash(bytes: &[u8], algo: HashAlgo) -> String {
+    match algo {
+        #[cfg(feature = "sha256")]
+        HashAlgo::Sha256 => to_hex(&pingu::sha256::sha256(bytes)),
+        HashAlgo::Crc32 => format!("{:08x}", crc32fast::hash(bytes)),
+    }
+}
+
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+
+    for (line, row) in bytes.chunks(16).enumerate() {
+        write!(dump, "{:08x}:", line * 16).unwrap();
+        for byte in row {
+            write!(dump, " {:02x}", byte).unwrap();
+        }
+        let ascii: String = row.iter().map(|&b| printable(b)).collect();
+        writeln!(
+            dump,
+            "{:width$}  {}",
+            "",
+            ascii,
+            width = (16 - row.len()) * 3
+        )
+        .unwrap();
+    }
+
+    dump
+}
+
+enum PngcheckLine {
+    Inline(String),
+    Indented(String),
+}
+
+// Types whose summary fits on the chunk line; everything else goes through
+// the handler registry and gets a line of its own
+fn pngcheck_interpretation(chunk: &Chunk, handlers: &HandlerRegistry) -> Option<PngcheckLine> {
+    let data = chunk.data();
+    match &chunk.chunk_type().bytes() {
+        b"tIME" => Time::try_from(data)
+            .ok()
+            .map(|time| PngcheckLine::Inline(time.to_string())),
+        b"gAMA" => Some(match Gamma::try_from(data) {
+            Ok(gamma) => PngcheckLine::Inline(gamma.to_string()),
+            Err(e) => PngcheckLine::Indented(format!("invalid gAMA: {}", e)),
+        }),
+        b"pHYs" if data.len() == 9 => {
+            let x = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+            let y = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+            let unit = if data[8] == 1 {
+                "pixels/meter"
+            } else {
+                "pixels/unit"
+            };
+            Some(PngcheckLine::Inline(format!("{}x{} {}", x, y, unit)))
+        }
+        _ => handlers.describe(chunk).map(PngcheckLine::Indented),
+    }
+}
+
+// Private, and unsafe to copy so editors drop it along with any changes
+pub const SIGNATURE_CHUNK: &str = "siGN";
+
+// What sign covers: the whole file as written to disk except the siGN chunk,
+// so the 8-byte PNG signature and any bytes after IEND are included. A bare
+// chunk stream is signed as if it had the standard PNG signature.
+pub fn signed_bytes(png: &Png) -> Vec<u8> {
+    let chunks = png
+        .iter_chunks()
+        .filter(|chunk| !chunk.is_type(SIGNATURE_CHUNK))
+        .flat_map(|chunk| chunk.as_bytes());
+    png.signature()
+        .iter()
+        .copied()
+        .chain(chunks)
+        .chain(png.trailing_bytes().iter().copied())
+        .collect()
+}
+
+// Replaces any earlier signature with one placed just before IEND
+pub fn sign_png(png: &mut Png, secret: &[u8; 32]) {
+    png.remove_chunks(SIGNATURE_CHUNK);
+    let signature = pingu::ed25519::sign(secret, &signed_bytes(png));
+    let chunk_type = ChunkType::try_from(*b"siGN").expect("siGN is a valid chunk type");
+    png.insert_chunk_before_iend(Chunk::new(chunk_type, signature.to_vec()));
+}
+
+pub fn verify_png(png: &Png, public: &[u8; 32]) -> Result<(), CommandError> {
+    let signatures: Vec<&Chunk> = png
+        .iter_chunks()
+        .filter(|chunk| chunk.is_type(SIGNATURE_CHUNK))
+        .collect();
+    let chunk = match signatures[..] {
+        [] => return Err(CommandError::MissingSignature),
+        [chunk] => chunk,
+        _ => return Err(CommandError::MultipleSignatures(signatures.len())),
+    };
+    let signature: &[u8; 64] = chunk
+        .data()
+        .try_into()
+        .map_err(|_| CommandError::InvalidSignatureLength(chunk.data().len()))?;
+
+    if !pingu::ed25519::verify(public, &signed_bytes(png), signature) {
+        return Err(CommandError::SignatureMismatch);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pingu::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            Chunk::new(
+                ChunkType::from_str("IHDR").unwrap(),
+                vec![0, 0, 0, 2, 0, 0, 0, 2, 8, 2, 0, 0, 0],
+            ),
+            Chunk::new(ChunkType::from_str("gAMA").unwrap(), vec![0, 0, 177, 143]),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hidden".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ])
+    }
+
+    #[test]
+    fn test_xor_mask_round_trip() {
+        let message = b"a message longer than its key";
+        let key = b"k3y";
+
+        let masked = xor_mask(message, key);
+        assert_eq!(masked.len(), message.len());
+        assert_ne!(&masked[..], &message[..]);
+        assert_eq!(masked[0], b'a' ^ b'k');
+        assert_eq!(masked[3], b'e' ^ b'k');
+        assert_eq!(xor_mask(&masked, key), message);
+
+        // The chunk CRC covers the masked bytes, not the plain message
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), masked.clone());
+        let plain = Chunk::new(ChunkType::from_str("ruSt").unwrap(), message.to_vec());
+        assert_ne!(chunk.crc(), plain.crc());
+        assert_eq!(xor_mask(chunk.data(), key), message);
+    }
+
+    #[test]
+    fn test_filter_chunks() {
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"Comment\0hi".to_vec(),
+        ));
+        let types = |names: &[&str]| -> Vec<ChunkType> {
+            names.iter().map(|n| ChunkType::from_str(n).unwrap()).collect()
+        };
+
+        let printed = filter_chunks(&png, &types(&["ruSt", "tEXt"]));
+        assert!(printed.starts_with("Chunk Type: ruSt\n"));
+        assert!(printed.contains("Chunk Type: tEXt\n"));
+        assert!(!printed.contains("IHDR"));
+        assert!(!printed.contains("gAMA"));
+
+        assert_eq!(filter_chunks(&png, &types(&["IDAT"])), "");
+        // IHDR is binary, so its data comes out as hex rather than panicking
+        let ihdr = filter_chunks(&png, &types(&["IHDR"]));
+        assert!(ihdr.starts_with("Chunk Type: IHDR\nLength: 13\nData (hex): 00000002"));
+        assert_eq!(
+            filter_chunks(&png, &types(&["ruSt"])),
+            png.chunk_by_type("ruSt").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_pngcheck_report() {
+        let png = testing_png();
+        let report = pngcheck_report("test.png", &png, png.as_bytes().len());
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines[0], "File: test.png (79 bytes)");
+        assert_eq!(lines[1], "  chunk IHDR at offset 0x00008, length 13");
+        assert_eq!(lines[2], "    2 x 2 image, 24-bit RGB, non-interlaced");
+        assert_eq!(
+            lines[3],
+            "  chunk gAMA at offset 0x00021, length 4: 0.45455"
+        );
+        assert_eq!(lines[4], "  chunk ruSt at offset 0x00031, length 6");
+        assert_eq!(lines[5], "  chunk IEND at offset 0x00043, length 0");
+        assert!(lines[6].starts_with("No errors detected in test.png (4 chunks"));
+    }
+
+    #[test]
+    fn test_pngcheck_report_without_signature() {
+        let stream = testing_png().as_bytes()[8..].to_vec();
+        let png = Png::try_from_chunk_stream(&stream).unwrap();
+        let report = pngcheck_report("test.chunks", &png, stream.len());
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines[1], "  chunk IHDR at offset 0x00000, length 13");
+        assert_eq!(lines[5], "  chunk IEND at offset 0x0003b, length 0");
+
+        // The written file gets a signature, so its offsets stay 8 bytes on
+        let mut png = png;
+        let index = png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("teSt").unwrap(),
+            b"new".to_vec(),
+        ));
+        let summary = change_summary((4, stream.len()), &png, png.as_bytes().len(), Some(index));
+        assert!(summary.ends_with("new chunk: teSt at index 3, offset 0x00043, 3 data bytes\n"));
+    }
+
+    #[test]
+    fn test_pngcheck_shows_splt() {
+        let mut png = testing_png();
+        let splt = [&b"web safe\0\x08"[..], &[255, 0, 0, 255, 0, 10]].concat();
+        png.insert_chunk_before_iend(Chunk::new(ChunkType::from_str("sPLT").unwrap(), splt));
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("sPLT").unwrap(),
+            b"broken\0\x09".to_vec(),
+        ));
+        let report = pngcheck_report("test.png", &png, png.as_bytes().len());
+
+        assert!(report.contains("    suggested palette \"web safe\", 8-bit samples, 1 entries\n"));
+        assert!(report.contains("    invalid sPLT: sPLT sample depth must be 8 or 16, found 9\n"));
+    }
+
+    #[test]
+    fn test_pngcheck_shows_color_chunks() {
+        let mut png = testing_png();
+        let chrm: Vec<u8> = [31270u32, 32900, 64000, 33000, 30000, 60000, 15000, 6000]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+        png.insert_chunk_before_iend(Chunk::new(ChunkType::from_str("cHRM").unwrap(), chrm));
+        png.insert_chunk_before_iend(Chunk::new(ChunkType::from_str("sRGB").unwrap(), vec![0]));
+        png.insert_chunk_before_iend(Chunk::new(ChunkType::from_str("gAMA").unwrap(), vec![1]));
+        let report = pngcheck_report("test.png", &png, png.as_bytes().len());
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines[3], "  chunk gAMA at offset 0x00021, length 4: 0.45455");
+        assert_eq!(
+            lines[6],
+            "    white (0.31270, 0.32900), red (0.64000, 0.33000), \
+             green (0.30000, 0.60000), blue (0.15000, 0.06000)"
+        );
+        assert_eq!(lines[8], "    rendering intent = perceptual");
+        assert_eq!(lines[10], "    invalid gAMA: gAMA must be 4 bytes, found 1");
+    }
+
+    #[test]
+    fn test_pngcheck_uses_registered_handlers() {
+        let mut handlers = HandlerRegistry::standard();
+        handlers.register(ChunkType::from_str("ruSt").unwrap(), |data: &[u8]| {
+            Some(format!("{} bytes of rust", data.len()))
+        });
+        let png = testing_png();
+        let report = pngcheck_report_with("test.png", &png, png.as_bytes().len(), &handlers);
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines[4], "  chunk ruSt at offset 0x00031, length 6");
+        assert_eq!(lines[5], "    6 bytes of rust");
+    }
+
+    #[test]
+    fn test_pngcheck_shows_time() {
+        let mut png = testing_png();
+        let time = Time {
+            year: 2024,
+            month: 2,
+            day: 29,
+            hour: 12,
+            minute: 34,
+            second: 56,
+        };
+        png.replace_chunk(time.to_chunk());
+        let report = pngcheck_report("test.png", &png, png.as_bytes().len());
+
+        assert!(report.contains("chunk tIME at offset 0x00043, length 7: 2024-02-29 12:34:56 UTC"));
+    }
+
+    #[test]
+    fn test_chunk_descriptions() {
+        let listing = chunk_descriptions(&testing_png(), false, None);
+        let lines: Vec<&str> = listing.lines().collect();
+
+        assert_eq!(lines[0], "  0  IHDR          13  required       Image header");
+        assert_eq!(lines[1], "  1  gAMA           4  optional-keep  Image gamma");
+        assert_eq!(
+            lines[2],
+            "  2  ruSt           6  optional-drop  Unknown private chunk"
+        );
+        assert_eq!(lines[3], "  3  IEND           0  required       Image trailer");
+    }
+
+    #[test]
+    fn test_chunk_descriptions_preview() {
+        let listing = chunk_descriptions(&testing_png(), false, Some(4));
+        let lines: Vec<&str> = listing.lines().collect();
+
+        assert!(lines[0].ends_with("Image header  00000002..."));
+        assert!(lines[2].ends_with("Unknown private chunk  \"hidd\"..."));
+        assert!(lines[3].ends_with("Image trailer"));
+
+        assert_eq!(data_preview(b"hidden", 6), "\"hidden\"");
+        assert_eq!(data_preview(b"hi\0", 8), "686900");
+        assert_eq!(data_preview(b"caf\xc3\xa9", 5), "636166c3a9");
+        assert_eq!(data_preview(b"hidden", 0), "\"\"...");
+    }
+
+    #[test]
+    fn test_chunk_json_lines() {
+        let png = testing_png();
+        let lines: Vec<String> = png
+            .chunks_with_offsets()
+            .enumerate()
+            .map(|(index, (offset, chunk))| chunk_json_line(index, offset, chunk))
+            .collect();
+
+        assert_eq!(lines.len(), 4);
+        for (index, line) in lines.iter().enumerate() {
+            assert_eq!(line.matches('\n').count(), 1);
+            let value = crate::json::parse(line).unwrap();
+            assert_eq!(value.get("index").and_then(Value::as_u64), Some(index as u64));
+        }
+        let rust = crate::json::parse(&lines[2]).unwrap();
+        assert_eq!(rust.get("type").and_then(Value::as_str), Some("ruSt"));
+        assert_eq!(rust.get("offset").and_then(Value::as_u64), Some(49));
+        assert_eq!(rust.get("length").and_then(Value::as_u64), Some(6));
+        assert_eq!(rust.get("crc").and_then(Value::as_str).map(str::len), Some(8));
+    }
+
+    #[test]
+    fn test_looks_compressed() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let zlib_payload = zlib::compress_stored(b"payload");
+        assert!(looks_compressed(&Chunk::new(chunk_type, zlib_payload.clone())));
+        assert!(!looks_compressed(&Chunk::new(chunk_type, b"payload".to_vec())));
+        assert!(!looks_compressed(&Chunk::new(chunk_type, vec![])));
+
+        let ztxt = [&b"Comment\0\0"[..], &zlib_payload].concat();
+        assert!(looks_compressed(&Chunk::new("zTXt".parse().unwrap(), ztxt)));
+
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(chunk_type, zlib_payload));
+        let listing = chunk_descriptions(&png, false, None);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert!(!lines[2].contains("compressed"));
+        assert_eq!(
+            lines[3],
+            "  3  ruSt          18  optional-drop  Unknown private chunk  compressed: likely"
+        );
+    }
+
+    #[test]
+    fn test_change_summary() {
+        let mut png = testing_png();
+        let before = (png.chunk_count(), png.as_bytes().len());
+        let index = png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("teSt").unwrap(),
+            b"new message".to_vec(),
+        ));
+        let after = png.as_bytes().len();
+
+        assert_eq!(
+            change_summary(before, &png, after, Some(index)),
+            "chunks: 4 -> 5 (+1)\n\
+             size: 79 -> 102 bytes (+23)\n\
+             new chunk: teSt at index 3, offset 0x00043, 11 data bytes\n"
+        );
+
+        let unchanged = testing_png();
+        let len = unchanged.as_bytes().len();
+        assert!(change_summary((4, len), &unchanged, len, None).ends_with("no chunk changed\n"));
+    }
+
+    #[test]
+    fn test_chunk_layout_highlights_new_chunk() {
+        let mut png = testing_png();
+        let index = png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("teSt").unwrap(),
+            b"new message".to_vec(),
+        ));
+
+        let layout = chunk_layout(&png, Some(index), false);
+        let lines: Vec<&str> = layout.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[3], ">   3  teSt          11  (new)");
+        assert_eq!(lines[4], "    4  IEND           0");
+        assert_eq!(lines.iter().filter(|l| l.starts_with('>')).count(), 1);
+    }
+
+    #[test]
+    fn test_extract_all_zip() {
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            vec![0, 159, 146, 150],
+        ));
+
+        let (count, archive) = extract_all_zip(&png, "ruSt", ChunkNaming::Index).unwrap();
+        let entries = crate::zip::tests::read_entries(&archive);
+
+        assert_eq!(count, 2);
+        assert_eq!(
+            entries,
+            vec![
+                ("0002-ruSt.bin".to_string(), b"hidden".to_vec()),
+                ("0003-ruSt.bin".to_string(), vec![0, 159, 146, 150]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_all_zip_hash_names() {
+        let shared = || Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"shared".to_vec());
+        let mut first = testing_png();
+        first.insert_chunk_before_iend(shared());
+        first.insert_chunk_before_iend(shared());
+        let mut second = Png::try_from(&std::fs::read("download.png").unwrap()[..]).unwrap();
+        second.insert_chunk_before_iend(shared());
+
+        let (count, archive) = extract_all_zip(&first, "ruSt", ChunkNaming::Hash).unwrap();
+        let first_entries = crate::zip::tests::read_entries(&archive);
+        let (_, archive) = extract_all_zip(&second, "ruSt", ChunkNaming::Hash).unwrap();
+        let second_entries = crate::zip::tests::read_entries(&archive);
+
+        // "hidden" plus one copy of the repeated chunk
+        assert_eq!(count, 3);
+        assert_eq!(first_entries.len(), 2);
+        assert_eq!(second_entries.len(), 1);
+        assert_eq!(first_entries[1], second_entries[0]);
+        assert_eq!(
+            second_entries[0].0,
+            format!("{}.chunk", file_hash(b"shared", HashAlgo::default()))
+        );
+        assert_ne!(first_entries[0].0, first_entries[1].0);
+    }
+
+    #[test]
+    fn test_write_output_missing_dirs() {
+        let root = std::env::temp_dir().join("pingu_write_output");
+        let _ = std::fs::remove_dir_all(&root);
+        let path = root.join("nested/dir/out.png");
+
+        assert!(matches!(
+            write_output(&path, b"data", false),
+            Err(CommandError::MissingOutputDir(_))
+        ));
+        assert!(!root.exists());
+
+        write_output(&path, b"data", true).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"data");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_preserve_mtime_across_rewrite() {
+        let path = std::env::temp_dir().join("pingu_preserve_mtime.png");
+        std::fs::write(&path, b"before").unwrap();
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        restore_mtime(&path, Some(old)).unwrap();
+
+        let mtime = input_mtime(&path, true).unwrap();
+        write_output(&path, b"after", false).unwrap();
+        assert_ne!(std::fs::metadata(&path).unwrap().modified().unwrap(), old);
+        restore_mtime(&path, mtime).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"after");
+        assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), old);
+        assert_eq!(input_mtime(&path, false).unwrap(), None);
+        assert!(input_mtime(Path::new("-"), true).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_verify_encoded() {
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("meSg").unwrap(),
+            b"secret".to_vec(),
+        ));
+        let mut bytes = png.as_bytes();
+        verify_encoded(&bytes, Some("meSg"), b"secret").unwrap();
+
+        assert!(matches!(
+            verify_encoded(&bytes, Some("meSg"), b"other"),
+            Err(CommandError::VerificationFailed(_))
+        ));
+
+        // Flip a bit in the message data so its CRC no longer matches
+        let at = bytes.windows(6).position(|w| w == b"secret").unwrap();
+        bytes[at] ^= 1;
+        assert!(matches!(
+            verify_encoded(&bytes, Some("meSg"), b"secret"),
+            Err(CommandError::VerificationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_size_warning() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let small = Chunk::new(chunk_type, vec![b'a'; 100]);
+        let large = Chunk::new(chunk_type, vec![b'a'; 101]);
+
+        assert_eq!(
+            message_size_report(&small),
+            "message: 100 bytes, ruSt chunk: 112 bytes"
+        );
+        assert_eq!(size_warning(&small, 100, true), None);
+        assert_eq!(
+            size_warning(&large, 100, true).unwrap(),
+            "warning: the message is larger than 100; the PNG will grow by 113"
+        );
+        assert!(size_warning(&large, LARGE_MESSAGE_BYTES, false).is_none());
+    }
+
+    #[test]
+    fn test_check_reserved() {
+        assert!(matches!(
+            check_reserved("IHDR", false),
+            Err(CommandError::ReservedChunkType(_))
+        ));
+        assert!(check_reserved("IEND", false).is_err());
+        check_reserved("IHDR", true).unwrap();
+        check_reserved("ruSt", false).unwrap();
+        check_reserved("tEXt", false).unwrap();
+    }
+
+    #[test]
+    fn test_check_duplicate() {
+        let png = testing_png();
+        assert!(matches!(
+            check_duplicate(&png, "ruSt", false),
+            Err(CommandError::DuplicateChunk(_))
+        ));
+        check_duplicate(&png, "ruSt", true).unwrap();
+        check_duplicate(&png, "neWw", false).unwrap();
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("\\n"), "\n");
+        assert_eq!(unescape(" | \\t\\0"), " | \t\0");
+        assert_eq!(unescape("a\\\\b\\q\\"), "a\\b\\q\\");
+    }
+
+    #[test]
+    fn test_join_messages() {
+        let first = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"first".to_vec());
+        let second = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"second".to_vec());
+
+        assert_eq!(
+            join_messages(&[&first, &second], "\\t--\\t"),
+            b"first\t--\tsecond"
+        );
+        assert_eq!(join_messages(&[&first], "\\t--\\t"), b"first");
+    }
+
+    #[test]
+    fn test_named_payload_round_trip() {
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("fiLe").unwrap(),
+            wrap_named_payload("report.pdf", b"%PDF-1.7 bytes"),
+        ));
+
+        let png = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        let data = png.chunk_by_type("fiLe").unwrap().data();
+        assert_eq!(
+            unwrap_named_payload(data),
+            Some(("report.pdf", &b"%PDF-1.7 bytes"[..]))
+        );
+    }
+
+    #[test]
+    fn test_named_payload_rejects_bad_names() {
+        assert_eq!(unwrap_named_payload(b"plain message"), None);
+        assert_eq!(unwrap_named_payload(b"pgFN\x00"), None);
+        assert_eq!(unwrap_named_payload(b"pgFN\x00\x09short"), None);
+        for name in ["", "..", "../etc/passwd", "a\\b"] {
+            assert_eq!(unwrap_named_payload(&wrap_named_payload(name, b"x")), None);
+        }
+    }
+
+    #[test]
+    fn test_assert_message_match() {
+        assert_message("ruSt", b"hidden", b"hidden").unwrap();
+    }
+
+    #[test]
+    fn test_assert_message_mismatch() {
+        let err = assert_message("ruSt", b"one\ntwo\nthree", b"one\n2\nthree").unwrap_err();
+        let CommandError::Mismatch { chunk_type, diff } = err else {
+            panic!("expected a mismatch");
+        };
+
+        assert_eq!(chunk_type, "ruSt");
+        assert_eq!(diff, "--- expected\n+++ actual\n one\n-2\n+two\n three");
+    }
+
+    #[test]
+    fn test_line_diff_added_and_removed() {
+        assert_eq!(line_diff("a\nb", "a"), "--- expected\n+++ actual\n a\n-b\n");
+        assert_eq!(line_diff("", "new"), "--- expected\n+++ actual\n+new\n");
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode_text("ruSt", b"hello", false).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_pretty_json() {
+        assert_eq!(
+            pretty_json("ruSt", r#"{"name":"pingu","tags":["a",1],"nested":{}}"#).unwrap(),
+            "{\n  \"name\": \"pingu\",\n  \"tags\": [\n    \"a\",\n    1\n  ],\n  \"nested\": {}\n}"
+        );
+
+        let err = pretty_json("ruSt", "{\"name\": }").unwrap_err();
+        assert!(matches!(err, CommandError::InvalidJson { .. }));
+        assert!(err.to_string().contains("line 1, column 10"));
+    }
+
+    #[test]
+    fn test_decode_with_meta() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hello".to_vec());
+        let meta = (chunk.length(), chunk.crc());
+        let crc = format!("{:08x}", chunk.crc());
+
+        assert_eq!(
+            chunk_meta("ruSt", meta),
+            format!("type: ruSt\nlength: 5\ncrc: {}\n", crc)
+        );
+        assert_eq!(
+            decoded_json("ruSt", "hello", Some(meta)).to_string(),
+            format!(
+                r#"{{"type":"ruSt","message":"hello","length":5,"crc":"{}"}}"#,
+                crc
+            )
+        );
+        assert_eq!(
+            decoded_json("ruSt", "hello", None).to_string(),
+            r#"{"type":"ruSt","message":"hello"}"#
+        );
+    }
+
+    #[test]
+    fn test_decode_text_reports_invalid_offset() {
+        let err = decode_text("ruSt", b"hello \xff world", false).unwrap_err();
+
+        assert!(matches!(err, CommandError::InvalidUtf8 { offset: 6, .. }));
+        let message = err.to_string();
+        assert!(message.contains("ruSt"));
+        assert!(message.contains("offset 6"));
+        assert!(message.contains("--format hex"));
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        let large = vec![b'a'; 100_000];
+        assert_eq!(truncate_output(&large, 65536).len(), 65536);
+        assert_eq!(truncate_output(&large, 0).len(), 100_000);
+        assert_eq!(truncate_output(b"short", 10), b"short");
+
+        // The cut lands inside the two-byte \u{e9}
+        let text = "caf\u{e9} au lait".as_bytes();
+        assert_eq!(truncate_output(text, 4), b"caf");
+        assert_eq!(truncate_output(text, 5), "caf\u{e9}".as_bytes());
+
+        // Binary data isn't text, so it is cut exactly
+        assert_eq!(truncate_output(&[0xff, 0xfe, 0xc3, 0xa9], 3), [0xff, 0xfe, 0xc3]);
+    }
+
+    #[test]
+    fn test_decode_text_lossy() {
+        let data = b"caf\xc3\xa9 \xff\xfe ok";
+
+        assert!(decode_text("ruSt", data, false).is_err());
+        assert_eq!(
+            decode_text("ruSt", data, true).unwrap(),
+            "caf\u{e9} \u{fffd}\u{fffd} ok"
+        );
+        assert_eq!(decode_text("ruSt", b"valid", true).unwrap(), "valid");
+    }
+
+    #[test]
+    fn test_normalize_newlines() {
+        let text = b"one\r\ntwo\nthree\rfour";
+
+        assert_eq!(
+            normalize_newlines(text, Newlines::Lf),
+            b"one\ntwo\nthree\nfour"
+        );
+        assert_eq!(
+            normalize_newlines(text, Newlines::Crlf),
+            b"one\r\ntwo\r\nthree\r\nfour"
+        );
+        assert_eq!(normalize_newlines(text, Newlines::None), text);
+    }
+
+    #[test]
+    fn test_to_hex() {
+        assert_eq!(to_hex(&[0x00, 0x7f, 0xff, 0x10]), "007fff10");
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let dump = hex_dump(b"PK\x03\x04hello, trailing world");
+        let lines: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "00000000: 50 4b 03 04 68 65 6c 6c 6f 2c 20 74 72 61 69 6c  PK..hello, trail"
+        );
+        assert!(lines[1].starts_with("00000010: 69 6e 67 20 77 6f 72 6c 64"));
+        assert!(lines[1].ends_with("  ing world"));
+    }
+
+    #[test]
+    fn test_auto_removal_type() {
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("CrIt").unwrap(),
+            b"critical".to_vec(),
+        ));
+
+        let chunk_type = auto_removal_type(&png).unwrap();
+        assert_eq!(chunk_type.to_string(), "ruSt");
+
+        png.remove_chunk(&chunk_type.to_string()).unwrap();
+        let remaining: Vec<String> = png
+            .iter_chunks()
+            .map(|ch| ch.chunk_type().to_string())
+            .collect();
+        assert_eq!(remaining, ["IHDR", "gAMA", "CrIt", "IEND"]);
+
+        // Standard and critical chunks are never picked
+        assert!(auto_removal_type(&png).is_none());
+    }
+
+    #[test]
+    fn test_removal_report_count() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            b"again".to_vec(),
+        ));
+
+        let removed = png.remove_chunks("ruSt");
+        assert_eq!(removal_report(&removed, true), "2\n");
+        assert!(removal_report(&removed, false).contains("Data: again"));
+        assert_eq!(removal_report(&[], true), "0\n");
+    }
+
+    #[test]
+    fn test_removed_data_is_binary_safe() {
+        let payload = vec![0x89, 0x00, 0xff, 0xfe, 0x80];
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("biNy").unwrap(),
+            payload.clone(),
+        ));
+
+        let removed = vec![png.remove_chunk("biNy").unwrap()];
+        assert_eq!(removed_data(&removed), payload);
+        assert!(png.chunk_by_type("biNy").is_none());
+
+        png.append_chunk(Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0xff]));
+        let removed = png.remove_chunks("ruSt");
+        assert_eq!(removed_data(&removed), b"hidden\xff");
+    }
+
+    #[test]
+    fn test_bench() {
+        let bytes = testing_png().as_bytes();
+        let stats = bench(&bytes, 5, |b| Png::try_from(b)).unwrap();
+
+        assert_eq!(stats.iterations, 5);
+        assert!(stats.mb_per_sec > 0.0);
+        assert!(bench_report(&stats).contains("MB/s"));
+        assert!(bench(&bytes[..20], 5, |b| Png::try_from(b)).is_err());
+    }
+
+    #[test]
+    fn test_hidden_report() {
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"Comment\0public".to_vec(),
+        ));
+
+        assert_eq!(hidden_report(&png, false), "ruSt           6  hidden\n");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0, false), "0");
+        assert_eq!(format_size(1023, false), "1023");
+        assert_eq!(format_size(1024, false), "1.0 KiB");
+        assert_eq!(format_size(1536, false), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024, false), "5.0 MiB");
+        assert_eq!(format_size(3 << 30, false), "3.0 GiB");
+        assert_eq!(format_size(2048 << 30, false), "2048.0 GiB");
+        assert_eq!(format_size(1536, true), "1536");
+    }
+
+    #[test]
+    fn test_chunk_descriptions_sizes() {
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            vec![b'a'; 3000],
+        ));
+
+        let human = chunk_descriptions(&png, false, None);
+        assert_eq!(
+            human.lines().nth(3).unwrap(),
+            "  3  ruSt     2.9 KiB  optional-drop  Unknown private chunk"
+        );
+        let raw = chunk_descriptions(&png, true, None);
+        assert_eq!(
+            raw.lines().nth(3).unwrap(),
+            "  3  ruSt        3000  optional-drop  Unknown private chunk"
+        );
+    }
+
+    #[test]
+    fn test_check_type_report() {
+        let report = |s| check_type_report(&ChunkType::from_str(s).unwrap());
+
+        assert_eq!(
+            report("ruSt"),
+            "ruSt: well-formed\n  ancillary\n  private\n  safe to copy\n  not a standard type\n"
+        );
+        assert_eq!(
+            report("tEXt"),
+            "tEXt: well-formed\n  ancillary\n  public\n  safe to copy\n  standard type: Text\n"
+        );
+        assert!(report("TEXt").ends_with(
+            "  differs only in case from standard type tEXt (Text)\n"
+        ));
+        assert!(report("Rust").starts_with("Rust: invalid"));
+        assert!(ChunkType::from_str("ru5t").is_err());
+    }
+
+    #[test]
+    fn test_file_hash_of_fixture() {
+        let bytes = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/download.png")).unwrap();
+
+        assert_eq!(file_hash(&bytes, HashAlgo::Crc32), "9f009b89");
+        #[cfg(feature = "sha256")]
+        assert_eq!(
+            file_hash(&bytes, HashAlgo::Sha256),
+            "698852cfbff4c67bdc3fa3d140cb6062bbbc84a093fb594fe3cb13dd151524a1"
+        );
+    }
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(preview(b"ab\x00\xffcd", 32), "ab..cd");
+        assert_eq!(preview(b"abcdef", 4), "abcd...");
+    }
+
+    #[test]
+    fn test_idat_stream_concatenates_in_order() {
+        let stream = zlib::compress_stored(b"row data split across several IDAT chunks");
+        let idat = |range: std::ops::Range<usize>| {
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), stream[range].to_vec())
+        };
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            idat(0..10),
+            idat(10..11),
+            idat(11..stream.len()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ]);
+
+        assert_eq!(idat_stream(&png, false, 0).unwrap(), stream);
+        assert_eq!(
+            idat_stream(&png, true, 41).unwrap(),
+            b"row data split across several IDAT chunks"
+        );
+        assert_eq!(
+            idat_stream(&png, true, 40),
+            Err(ZlibError::OutputTooLarge(40))
+        );
+    }
+
+    #[test]
+    fn test_trailing_report() {
+        let mut bytes = testing_png().as_bytes();
+        bytes.extend_from_slice(b"extra");
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+
+        let report = trailing_report(&png, false);
+        assert!(report.starts_with("Trailing bytes after IEND: 5\nLooks like: text\n"));
+        assert!(report.contains("65 78 74 72 61"));
+    }
+
+    #[test]
+    fn test_find_bytes() {
+        assert_eq!(find_bytes(b"aaaa", b"aa"), vec![(0, 2), (2, 4)]);
+        assert_eq!(find_bytes(b"a secret", b"secret"), vec![(2, 8)]);
+        assert!(find_bytes(b"short", b"longer than data").is_empty());
+    }
+
+    #[test]
+    fn test_grep_report() {
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"Comment\0the secret is under the mat".to_vec(),
+        ));
+
+        let (report, count) = grep_report(&png, |data| find_bytes(data, b"secret"), 6);
+        assert_eq!(count, 1);
+        assert_eq!(report, "  3  tEXt  +12: ...t\\x00the [secret] is un...\n");
+
+        let (_, count) = grep_report(&png, |data| find_bytes(data, b"nowhere"), 6);
+        assert_eq!(count, 0);
+
+        let regex = crate::regex::Regex::new("^[A-Za-z]+").unwrap();
+        let (report, count) = grep_report(&png, |data| regex.find_all(data), 0);
+        assert_eq!(count, 2);
+        assert_eq!(report, "  2  ruSt  +0: [hidden]\n  3  tEXt  +0: [Comment]...\n");
+    }
+
+    #[test]
+    fn test_grep_count() {
+        let mut png = testing_png();
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"Comment\0a secret, and another secret".to_vec(),
+        ));
+        png.insert_chunk_before_iend(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            b"top secret".to_vec(),
+        ));
+        let find = |data: &[u8]| find_bytes(data, b"secret");
+
+        // Three matches, but only two chunks hold them
+        assert_eq!(grep_report(&png, find, 0).1, 3);
+        assert_eq!(grep_count(&png, find), 2);
+        assert_eq!(grep_count(&png, |data| find_bytes(data, b"nowhere")), 0);
+    }
+
+    fn signing_keys() -> ([u8; 32], [u8; 32]) {
+        (
+            crate::pem::private_key(crate::pem::tests::PRIVATE_PEM).unwrap(),
+            crate::pem::public_key(crate::pem::tests::PUBLIC_PEM).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_sign_and_verify_png() {
+        let (secret, public) = signing_keys();
+        let mut png = testing_png();
+        sign_png(&mut png, &secret);
+
+        let types: Vec<String> = png.iter_chunks().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(types, ["IHDR", "gAMA", "ruSt", "siGN", "IEND"]);
+        assert_eq!(signed_bytes(&png), signed_bytes(&testing_png()));
+
+        // The signature survives writing the file out and reading it back
+        let reread = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        assert!(verify_png(&reread, &public).is_ok());
+
+        // Signing again replaces the signature rather than adding another
+        sign_png(&mut png, &secret);
+        assert_eq!(png.remove_chunks(SIGNATURE_CHUNK).len(), 1);
+        assert!(matches!(
+            verify_png(&png, &public),
+            Err(CommandError::MissingSignature)
+        ));
+    }
+
+    #[test]
+    fn test_verify_tampered_png() {
+        let (secret, public) = signing_keys();
+        let mut png = testing_png();
+        sign_png(&mut png, &secret);
+
+        let mut tampered = png.as_bytes();
+        // The ruSt chunk's data ("hidden") starts at offset 57
+        tampered[57] = b'H';
+        let (tampered, _) = Png::try_from_fixing_crcs(&tampered).unwrap();
+        assert!(matches!(
+            verify_png(&tampered, &public),
+            Err(CommandError::SignatureMismatch)
+        ));
+
+        let mut extra = png.as_bytes();
+        let mut chunk = png.chunk_by_type("ruSt").unwrap().as_bytes();
+        extra.splice(33..33, chunk.drain(..));
+        let extra = Png::try_from(extra.as_slice()).unwrap();
+        assert!(matches!(
+            verify_png(&extra, &public),
+            Err(CommandError::SignatureMismatch)
+        ));
+
+        let other_key = pingu::ed25519::public_key(&[1; 32]);
+        assert!(matches!(
+            verify_png(&png, &other_key),
+            Err(CommandError::SignatureMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_verify_covers_signature_and_trailing_bytes() {
+        let (secret, public) = signing_keys();
+        let mut png = testing_png();
+        sign_png(&mut png, &secret);
+
+        let mut appended = png.as_bytes();
+        appended.extend_from_slice(b"appended after IEND");
+        let appended = Png::try_from(appended.as_slice()).unwrap();
+        assert!(matches!(
+            verify_png(&appended, &public),
+            Err(CommandError::SignatureMismatch)
+        ));
+
+        let mut relabeled = png.as_bytes();
+        relabeled[1] = b'M';
+        let relabeled = Png::try_from_any_signature(&relabeled).unwrap();
+        assert!(matches!(
+            verify_png(&relabeled, &public),
+            Err(CommandError::SignatureMismatch)
+        ));
+
+        // Bytes that were already there when signing are covered, not rejected
+        let mut with_trailing = testing_png().as_bytes();
+        with_trailing.extend_from_slice(b"trailing");
+        let mut png = Png::try_from(with_trailing.as_slice()).unwrap();
+        sign_png(&mut png, &secret);
+        let reread = Png::try_from(png.as_bytes().as_slice()).unwrap();
+        assert_eq!(reread.trailing_bytes(), b"trailing");
+        assert!(verify_png(&reread, &public).is_ok());
+    }
+
+    #[test]
+    fn test_crc_fix_report() {
+        assert_eq!(
+            crc_fix_report(&testing_png(), &[1, 2]),
+            "fixed CRC of gAMA chunk at index 1\n\
+             fixed CRC of ruSt chunk at index 2\n\
+             Corrected 2 of 4 CRCs\n"
+        );
+    }
+
+    #[test]
+    fn test_carve_appended_zip() {
+        let mut zip = ZipWriter::new();
+        zip.add_entry("secret.txt", b"hidden after the end").unwrap();
+        let zip = zip.finish().unwrap();
+        let mut bytes = testing_png().as_bytes();
+        bytes.extend_from_slice(&zip);
+
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+
+        assert_eq!(png.trailing_bytes(), zip);
+        assert_eq!(guess_file_type(png.trailing_bytes()), "ZIP archive");
+    }
+
+    #[test]
+    fn test_guess_file_type() {
+        assert_eq!(guess_file_type(&testing_png().as_bytes()), "PNG image");
+        assert_eq!(guess_file_type(b"\xff\xd8\xff\xe0rest"), "JPEG image");
+        assert_eq!(guess_file_type(b"just a note\n"), "text");
+        assert_eq!(guess_file_type(b"\x00\x01\x02"), "unknown data");
+    }
+}
@@ -0,0 +1,335 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EccError {
+    #[error(
+        "Error-correction parity must be between {MIN_PARITY} and {MAX_PARITY} bytes, found {0}"
+    )]
+    InvalidParity(usize),
+    #[error("Error-correction header is damaged beyond repair")]
+    DamagedHeader,
+    #[error("Block {0} has more damaged bytes than its parity can correct")]
+    TooManyErrors(usize),
+    #[error("Recovered message is {0} bytes shorter than its stored length")]
+    Truncated(usize),
+}
+
+pub const MIN_PARITY: usize = 2;
+pub const MAX_PARITY: usize = 128;
+// A Reed-Solomon codeword over GF(256) is at most 255 bytes, parity included
+const BLOCK_LEN: usize = 255;
+const HEADER_COPIES: usize = 3;
+
+// Exponent and logarithm tables for GF(256) with the polynomial 0x11d and
+// generator 2. exp is doubled so products of two logs need no reduction.
+struct Tables {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+const GF: Tables = build_tables();
+
+const fn build_tables() -> Tables {
+    let mut exp = [0; 512];
+    let mut log = [0; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11d;
+        }
+        i += 1;
+    }
+    while i < 512 {
+        exp[i] = exp[i - 255];
+        i += 1;
+    }
+    Tables { exp, log }
+}
+
+fn mul(x: u8, y: u8) -> u8 {
+    if x == 0 || y == 0 {
+        return 0;
+    }
+    GF.exp[GF.log[x as usize] as usize + GF.log[y as usize] as usize]
+}
+
+fn div(x: u8, y: u8) -> u8 {
+    if x == 0 {
+        return 0;
+    }
+    GF.exp[(GF.log[x as usize] as usize + 255 - GF.log[y as usize] as usize) % 255]
+}
+
+fn inverse(x: u8) -> u8 {
+    GF.exp[255 - GF.log[x as usize] as usize]
+}
+
+// 2 to the given power, which may be negative
+fn alpha_pow(power: i64) -> u8 {
+    GF.exp[power.rem_euclid(255) as usize]
+}
+
+// Polynomials are stored highest degree first
+fn poly_scale(p: &[u8], x: u8) -> Vec<u8> {
+    p.iter().map(|&c| mul(c, x)).collect()
+}
+
+fn poly_add(p: &[u8], q: &[u8]) -> Vec<u8> {
+    let len = p.len().max(q.len());
+    let mut sum = vec![0; len];
+    for (i, &c) in p.iter().enumerate() {
+        sum[i + len - p.len()] = c;
+    }
+    for (i, &c) in q.iter().enumerate() {
+        sum[i + len - q.len()] ^= c;
+    }
+    sum
+}
+
+fn poly_mul(p: &[u8], q: &[u8]) -> Vec<u8> {
+    let mut product = vec![0; p.len() + q.len() - 1];
+    for (i, &a) in p.iter().enumerate() {
+        for (j, &b) in q.iter().enumerate() {
+            product[i + j] ^= mul(a, b);
+        }
+    }
+    product
+}
+
+fn poly_eval(p: &[u8], x: u8) -> u8 {
+    p.iter().fold(0, |y, &c| mul(y, x) ^ c)
+}
+
+// Remainder of dividing by a monic divisor
+fn poly_rem(dividend: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut out = dividend.to_vec();
+    for i in 0..dividend.len().saturating_sub(divisor.len() - 1) {
+        let coef = out[i];
+        if coef != 0 {
+            for (j, &d) in divisor.iter().enumerate().skip(1) {
+                out[i + j] ^= mul(d, coef);
+            }
+        }
+    }
+    out.split_off(out.len() - (divisor.len() - 1))
+}
+
+fn generator(parity: usize) -> Vec<u8> {
+    (0..parity).fold(vec![1], |g, i| poly_mul(&g, &[1, alpha_pow(i as i64)]))
+}
+
+fn encode_block(data: &[u8], generator: &[u8]) -> Vec<u8> {
+    let parity = generator.len() - 1;
+    let mut block = data.to_vec();
+    block.resize(data.len() + parity, 0);
+    let remainder = poly_rem(&block, generator);
+    block[data.len()..].copy_from_slice(&remainder);
+    block
+}
+
+// A leading zero keeps the indices the same as in the textbook algorithms
+fn syndromes(block: &[u8], parity: usize) -> Vec<u8> {
+    std::iter::once(0)
+        .chain((0..parity).map(|i| poly_eval(block, alpha_pow(i as i64))))
+        .collect()
+}
+
+// Berlekamp-Massey
+fn error_locator(synd: &[u8], parity: usize) -> Option<Vec<u8>> {
+    let mut locator = vec![1];
+    let mut old = vec![1];
+    for i in 0..parity {
+        let k = i + 1;
+        let mut delta = synd[k];
+        for j in 1..locator.len() {
+            delta ^= mul(locator[locator.len() - 1 - j], synd[k - j]);
+        }
+        old.push(0);
+        if delta != 0 {
+            if old.len() > locator.len() {
+                let new = poly_scale(&old, delta);
+                old = poly_scale(&locator, inverse(delta));
+                locator = new;
+            }
+            locator = poly_add(&locator, &poly_scale(&old, delta));
+        }
+    }
+
+    let start = locator.iter().position(|&c| c != 0)?;
+    let locator = locator.split_off(start);
+    (2 * (locator.len() - 1) <= parity).then_some(locator)
+}
+
+// Chien search: positions in the block whose locator root is present
+fn error_positions(locator: &[u8], block_len: usize) -> Option<Vec<usize>> {
+    let reversed: Vec<u8> = locator.iter().rev().copied().collect();
+    let positions: Vec<usize> = (0..block_len)
+        .filter(|&i| poly_eval(&reversed, alpha_pow(i as i64)) == 0)
+        .map(|i| block_len - 1 - i)
+        .collect();
+    (positions.len() == locator.len() - 1).then_some(positions)
+}
+
+// Forney: the value to XOR into each erroneous byte
+fn correct(block: &mut [u8], synd: &[u8], positions: &[usize]) -> Option<()> {
+    let coef_positions: Vec<usize> = positions.iter().map(|&p| block.len() - 1 - p).collect();
+    let locator = coef_positions.iter().fold(vec![1], |loc, &i| {
+        poly_mul(&loc, &poly_add(&[1], &[alpha_pow(i as i64), 0]))
+    });
+
+    let reversed_synd: Vec<u8> = synd.iter().rev().copied().collect();
+    let mut modulus = vec![0; locator.len() + 1];
+    modulus[0] = 1;
+    let mut evaluator = poly_rem(&poly_mul(&reversed_synd, &locator), &modulus);
+    evaluator.reverse();
+
+    let roots: Vec<u8> = coef_positions
+        .iter()
+        .map(|&i| alpha_pow(-((BLOCK_LEN - i) as i64)))
+        .collect();
+    for (i, &root) in roots.iter().enumerate() {
+        let root_inv = inverse(root);
+        let derivative = roots
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .fold(1, |acc, (_, &other)| mul(acc, 1 ^ mul(root_inv, other)));
+        if derivative == 0 {
+            return None;
+        }
+        let reversed_eval: Vec<u8> = evaluator.iter().rev().copied().collect();
+        let y = mul(root, poly_eval(&reversed_eval, root_inv));
+        block[positions[i]] ^= div(y, derivative);
+    }
+    Some(())
+}
+
+fn decode_block(block: &mut [u8], parity: usize) -> Option<()> {
+    let synd = syndromes(block, parity);
+    if synd.iter().all(|&s| s == 0) {
+        return Some(());
+    }
+    let locator = error_locator(&synd, parity)?;
+    let positions = error_positions(&locator, block.len())?;
+    correct(block, &synd, &positions)?;
+    syndromes(block, parity)
+        .iter()
+        .all(|&s| s == 0)
+        .then_some(())
+}
+
+// Reed-Solomon protects the data in blocks of up to 255 bytes, each ending
+// in parity bytes; a block can repair up to parity / 2 damaged bytes. The
+// parity count is written three times up front, followed by the blocks of
+// the data's length (4 bytes, big-endian) and the data itself.
+pub fn encode(data: &[u8], parity: usize) -> Result<Vec<u8>, EccError> {
+    if !(MIN_PARITY..=MAX_PARITY).contains(&parity) {
+        return Err(EccError::InvalidParity(parity));
+    }
+    let generator = generator(parity);
+    let stream = [&(data.len() as u32).to_be_bytes()[..], data].concat();
+
+    let mut out = vec![parity as u8; HEADER_COPIES];
+    for block in stream.chunks(BLOCK_LEN - parity) {
+        out.extend(encode_block(block, &generator));
+    }
+    Ok(out)
+}
+
+pub fn decode(encoded: &[u8]) -> Result<Vec<u8>, EccError> {
+    let header = encoded
+        .get(..HEADER_COPIES)
+        .ok_or(EccError::DamagedHeader)?;
+    let parity = match header {
+        [a, b, _] | [a, _, b] | [_, a, b] if a == b => *a as usize,
+        _ => return Err(EccError::DamagedHeader),
+    };
+    if !(MIN_PARITY..=MAX_PARITY).contains(&parity) {
+        return Err(EccError::DamagedHeader);
+    }
+
+    let mut stream = Vec::with_capacity(encoded.len());
+    for (index, block) in encoded[HEADER_COPIES..].chunks(BLOCK_LEN).enumerate() {
+        if block.len() <= parity {
+            return Err(EccError::TooManyErrors(index));
+        }
+        let mut block = block.to_vec();
+        decode_block(&mut block, parity).ok_or(EccError::TooManyErrors(index))?;
+        stream.extend_from_slice(&block[..block.len() - parity]);
+    }
+
+    let len = stream.get(..4).ok_or(EccError::Truncated(4))?;
+    let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+    let data = &stream[4..];
+    if data.len() < len {
+        return Err(EccError::Truncated(len - data.len()));
+    }
+    Ok(data[..len].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> Vec<u8> {
+        (0..600u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    // Flips bytes spread evenly over each block, at most max_per_block of them
+    fn damage(encoded: &mut [u8], max_per_block: usize) {
+        for block in encoded[HEADER_COPIES..].chunks_mut(BLOCK_LEN) {
+            let step = block.len() / max_per_block;
+            for i in 0..max_per_block {
+                block[i * step + 3] ^= 0x5a;
+            }
+        }
+    }
+
+    #[test]
+    fn test_gf_arithmetic() {
+        assert_eq!(mul(2, 0x80), 0x1d);
+        for x in 1..=255u8 {
+            assert_eq!(mul(x, inverse(x)), 1);
+            assert_eq!(div(mul(x, 7), 7), x);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_without_damage() {
+        let encoded = encode(&message(), 32).unwrap();
+
+        // 604 bytes of stream in 223-byte pieces: three blocks plus the header
+        assert_eq!(encoded.len(), 3 + 604 + 3 * 32);
+        assert_eq!(decode(&encoded).unwrap(), message());
+        assert_eq!(decode(&encode(b"", 2).unwrap()).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_recovers_damaged_bytes() {
+        let mut encoded = encode(&message(), 32).unwrap();
+        damage(&mut encoded, 16);
+        encoded[0] = 0;
+
+        assert_eq!(decode(&encoded).unwrap(), message());
+    }
+
+    #[test]
+    fn test_too_much_damage() {
+        let mut encoded = encode(&message(), 8).unwrap();
+        damage(&mut encoded, 20);
+
+        assert!(decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(encode(b"x", 1), Err(EccError::InvalidParity(1)));
+        assert_eq!(encode(b"x", 200), Err(EccError::InvalidParity(200)));
+        assert_eq!(decode(&[4, 5, 6, 0]), Err(EccError::DamagedHeader));
+        assert_eq!(decode(&[4]), Err(EccError::DamagedHeader));
+    }
+}
@@ -0,0 +1,839 @@
+// Ed25519 signatures as specified in RFC 8032, and the SHA-512 they hash
+// with. This is a small implementation of our own that has not been audited,
+// so don't trust it with keys that protect anything valuable.
+//
+// Scalar multiplication and the reduction of scalars mod L don't branch on
+// their inputs, so signing takes the same time whatever the key and nonce, and
+// the secret scalar and nonce are wiped once signing is done. Decoding and
+// verification only see public values and make no such effort.
+
+#[rustfmt::skip]
+const SHA512_K: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+#[rustfmt::skip]
+const SHA512_INITIAL_STATE: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+fn sha512(data: &[u8]) -> [u8; 64] {
+    let mut state = SHA512_INITIAL_STATE;
+
+    // Message, a 1 bit, zeros up to 112 mod 128, then the 128-bit bit length
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 128 != 112 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((data.len() as u128) * 8).to_be_bytes());
+
+    for block in padded.chunks_exact(128) {
+        sha512_compress(&mut state, block);
+    }
+
+    let mut digest = [0; 64];
+    for (out, word) in digest.chunks_exact_mut(8).zip(state) {
+        out.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn sha512_compress(state: &mut [u64; 8], block: &[u8]) {
+    let mut w = [0u64; 80];
+    for (i, word) in block.chunks_exact(8).enumerate() {
+        w[i] = u64::from_be_bytes(word.try_into().unwrap());
+    }
+    for i in 16..80 {
+        let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+        let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in 0..80 {
+        let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(SHA512_K[i])
+            .wrapping_add(w[i]);
+        let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+// The curve constant d = -121665 / 121666 and a square root of -1, both mod p
+const D: [u8; 32] = [
+    0xa3, 0x78, 0x59, 0x13, 0xca, 0x4d, 0xeb, 0x75, 0xab, 0xd8, 0x41, 0x41, 0x4d, 0x0a, 0x70, 0x00,
+    0x98, 0xe8, 0x79, 0x77, 0x79, 0x40, 0xc7, 0x8c, 0x73, 0xfe, 0x6f, 0x2b, 0xee, 0x6c, 0x03, 0x52,
+];
+const SQRT_M1: [u8; 32] = [
+    0xb0, 0xa0, 0x0e, 0x4a, 0x27, 0x1b, 0xee, 0xc4, 0x78, 0xe4, 0x2f, 0xad, 0x06, 0x18, 0x43, 0x2f,
+    0xa7, 0xd7, 0xfb, 0x3d, 0x99, 0x00, 0x4d, 0x2b, 0x0b, 0xdf, 0xc1, 0x4f, 0x80, 0x24, 0x83, 0x2b,
+];
+
+const MASK: u64 = (1 << 51) - 1;
+
+// An element of GF(p), p = 2^255 - 19, as five 51-bit limbs, lowest first.
+// Limbs may run a little over 51 bits between operations.
+#[derive(Clone, Copy, Debug)]
+struct Fe([u64; 5]);
+
+impl PartialEq for Fe {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+// Little-endian exponent 2^256 - 1 with its lowest and highest bytes replaced
+fn exponent(low: u8, high: u8) -> [u8; 32] {
+    let mut exponent = [0xff; 32];
+    exponent[0] = low;
+    exponent[31] = high;
+    exponent
+}
+
+impl Fe {
+    const ZERO: Fe = Fe([0; 5]);
+    const ONE: Fe = Fe([1, 0, 0, 0, 0]);
+
+    // The top bit is ignored; it carries the sign of x in encoded points
+    fn from_bytes(bytes: &[u8; 32]) -> Fe {
+        let load = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        Fe([
+            load(0) & MASK,
+            (load(6) >> 3) & MASK,
+            (load(12) >> 6) & MASK,
+            (load(19) >> 1) & MASK,
+            (load(24) >> 12) & MASK,
+        ])
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut limbs = Fe::carry(self.0).0;
+        // The value is now below 2p; q is 1 exactly when it is at least p
+        let mut q = (limbs[0] + 19) >> 51;
+        for limb in &limbs[1..] {
+            q = (limb + q) >> 51;
+        }
+        limbs[0] += 19 * q;
+        for i in 0..4 {
+            limbs[i + 1] += limbs[i] >> 51;
+            limbs[i] &= MASK;
+        }
+        limbs[4] &= MASK;
+
+        let mut bytes = [0; 32];
+        let mut bits: u128 = 0;
+        let mut bit_count = 0;
+        let mut at = 0;
+        for limb in limbs {
+            bits |= (limb as u128) << bit_count;
+            bit_count += 51;
+            while bit_count >= 8 {
+                bytes[at] = bits as u8;
+                bits >>= 8;
+                bit_count -= 8;
+                at += 1;
+            }
+        }
+        bytes[at] = bits as u8;
+        bytes
+    }
+
+    // Moves each limb's overflow into the next; 2^255 wraps around as 19
+    fn carry(mut limbs: [u64; 5]) -> Fe {
+        for i in 0..4 {
+            limbs[i + 1] += limbs[i] >> 51;
+            limbs[i] &= MASK;
+        }
+        limbs[0] += 19 * (limbs[4] >> 51);
+        limbs[4] &= MASK;
+        limbs[1] += limbs[0] >> 51;
+        limbs[0] &= MASK;
+        Fe(limbs)
+    }
+
+    fn add(self, other: Fe) -> Fe {
+        let mut sum = self.0;
+        for (limb, add) in sum.iter_mut().zip(other.0) {
+            *limb += add;
+        }
+        Fe::carry(sum)
+    }
+
+    // Adds 16p first so no limb goes below zero
+    fn sub(self, other: Fe) -> Fe {
+        let mut difference = self.0;
+        for (i, (limb, sub)) in difference.iter_mut().zip(other.0).enumerate() {
+            let p_limb = if i == 0 { MASK - 18 } else { MASK };
+            *limb = *limb + 16 * p_limb - sub;
+        }
+        Fe::carry(difference)
+    }
+
+    fn neg(self) -> Fe {
+        Fe::ZERO.sub(self)
+    }
+
+    fn mul(self, other: Fe) -> Fe {
+        let [a0, a1, a2, a3, a4] = self.0.map(u128::from);
+        let [b0, b1, b2, b3, b4] = other.0.map(u128::from);
+        let (b1_19, b2_19, b3_19, b4_19) = (b1 * 19, b2 * 19, b3 * 19, b4 * 19);
+
+        let c0 = a0 * b0 + a4 * b1_19 + a3 * b2_19 + a2 * b3_19 + a1 * b4_19;
+        let mut c1 = a1 * b0 + a0 * b1 + a4 * b2_19 + a3 * b3_19 + a2 * b4_19;
+        let mut c2 = a2 * b0 + a1 * b1 + a0 * b2 + a4 * b3_19 + a3 * b4_19;
+        let mut c3 = a3 * b0 + a2 * b1 + a1 * b2 + a0 * b3 + a4 * b4_19;
+        let mut c4 = a4 * b0 + a3 * b1 + a2 * b2 + a1 * b3 + a0 * b4;
+
+        c1 += c0 >> 51;
+        c2 += c1 >> 51;
+        c3 += c2 >> 51;
+        c4 += c3 >> 51;
+        let mask = MASK as u128;
+        Fe::carry([
+            (c0 & mask) as u64 + 19 * (c4 >> 51) as u64,
+            (c1 & mask) as u64,
+            (c2 & mask) as u64,
+            (c3 & mask) as u64,
+            (c4 & mask) as u64,
+        ])
+    }
+
+    fn square(self) -> Fe {
+        self.mul(self)
+    }
+
+    // Square-and-multiply over a little-endian exponent
+    fn pow(self, exponent: &[u8; 32]) -> Fe {
+        (0..256).rev().fold(Fe::ONE, |result, bit| {
+            let result = result.square();
+            if exponent[bit / 8] >> (bit % 8) & 1 == 1 {
+                result.mul(self)
+            } else {
+                result
+            }
+        })
+    }
+
+    // x^(p - 2)
+    fn invert(self) -> Fe {
+        self.pow(&exponent(0xeb, 0x7f))
+    }
+
+    fn is_negative(self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    // Swaps a and b when swap is 1 and leaves them when it is 0, with the same
+    // instructions either way. black_box keeps the optimizer from turning the
+    // mask back into a branch.
+    fn conditional_swap(a: &mut Fe, b: &mut Fe, swap: u64) {
+        let mask = std::hint::black_box(swap.wrapping_neg());
+        for (a, b) in a.0.iter_mut().zip(b.0.iter_mut()) {
+            let flip = mask & (*a ^ *b);
+            *a ^= flip;
+            *b ^= flip;
+        }
+    }
+}
+
+// A curve point in extended coordinates: x = X/Z, y = Y/Z and xy = T/Z
+#[derive(Clone, Copy, Debug)]
+struct Point {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+    t: Fe,
+}
+
+impl Point {
+    const IDENTITY: Point = Point {
+        x: Fe::ZERO,
+        y: Fe::ONE,
+        z: Fe::ONE,
+        t: Fe::ZERO,
+    };
+
+    fn base() -> Point {
+        let mut encoded = [0x66; 32];
+        encoded[0] = 0x58;
+        Point::decode(&encoded).expect("the base point is on the curve")
+    }
+
+    // The unified formula from RFC 8032, so it also doubles
+    fn add(&self, other: &Point) -> Point {
+        let d = Fe::from_bytes(&D);
+        let a = self.y.sub(self.x).mul(other.y.sub(other.x));
+        let b = self.y.add(self.x).mul(other.y.add(other.x));
+        let c = self.t.mul(d.add(d)).mul(other.t);
+        let d = self.z.add(self.z).mul(other.z);
+        let (e, f, g, h) = (b.sub(a), d.sub(c), d.add(c), b.add(a));
+        Point {
+            x: e.mul(f),
+            y: g.mul(h),
+            z: f.mul(g),
+            t: e.mul(h),
+        }
+    }
+
+    fn neg(&self) -> Point {
+        Point {
+            x: self.x.neg(),
+            t: self.t.neg(),
+            ..*self
+        }
+    }
+
+    fn conditional_swap(a: &mut Point, b: &mut Point, swap: u64) {
+        Fe::conditional_swap(&mut a.x, &mut b.x, swap);
+        Fe::conditional_swap(&mut a.y, &mut b.y, swap);
+        Fe::conditional_swap(&mut a.z, &mut b.z, swap);
+        Fe::conditional_swap(&mut a.t, &mut b.t, swap);
+    }
+
+    // Montgomery ladder over a little-endian scalar, keeping r1 = r0 + self.
+    // Every bit costs one addition and one doubling, and the bit only picks
+    // which point is which through conditional swaps, so neither the timing
+    // nor the memory accesses depend on the scalar.
+    fn mul(&self, scalar: &[u8; 32]) -> Point {
+        let (mut r0, mut r1) = (Point::IDENTITY, *self);
+        for bit in (0..256).rev() {
+            let swap = u64::from(scalar[bit / 8] >> (bit % 8) & 1);
+            Point::conditional_swap(&mut r0, &mut r1, swap);
+            r1 = r0.add(&r1);
+            r0 = r0.add(&r0);
+            Point::conditional_swap(&mut r0, &mut r1, swap);
+        }
+        r0
+    }
+
+    // Points in the small subgroup of order 8 cancel out once multiplied by
+    // the cofactor, leaving the identity, where x is zero
+    fn has_small_order(&self) -> bool {
+        let mut point = *self;
+        for _ in 0..3 {
+            point = point.add(&point);
+        }
+        point.x == Fe::ZERO
+    }
+
+    // y, with the sign of x in the top bit
+    fn encode(&self) -> [u8; 32] {
+        let z_inverse = self.z.invert();
+        let mut encoded = self.y.mul(z_inverse).to_bytes();
+        if self.x.mul(z_inverse).is_negative() {
+            encoded[31] |= 0x80;
+        }
+        encoded
+    }
+
+    // Recovers x from x^2 = (y^2 - 1) / (d y^2 + 1) as in RFC 8032 5.1.3
+    fn decode(encoded: &[u8; 32]) -> Option<Point> {
+        let negative = encoded[31] & 0x80 != 0;
+        let mut y_bytes = *encoded;
+        y_bytes[31] &= 0x7f;
+        let y = Fe::from_bytes(&y_bytes);
+        if y.to_bytes() != y_bytes {
+            return None;
+        }
+
+        let y2 = y.square();
+        let u = y2.sub(Fe::ONE);
+        let v = Fe::from_bytes(&D).mul(y2).add(Fe::ONE);
+        let v3 = v.square().mul(v);
+        let v7 = v3.square().mul(v);
+        let mut x = u.mul(v3).mul(u.mul(v7).pow(&exponent(0xfd, 0x0f)));
+
+        let vx2 = v.mul(x.square());
+        if vx2 == u.neg() {
+            x = x.mul(Fe::from_bytes(&SQRT_M1));
+        } else if vx2 != u {
+            return None;
+        }
+        if x == Fe::ZERO && negative {
+            return None;
+        }
+        if x.is_negative() != negative {
+            x = x.neg();
+        }
+
+        Some(Point {
+            x,
+            y,
+            z: Fe::ONE,
+            t: x.mul(y),
+        })
+    }
+}
+
+// The order of the base point, 2^252 + 27742317777372353535851937790883648493,
+// as little-endian 64-bit limbs
+const L: [u64; 4] = [
+    0x5812631a5cf5d3ed,
+    0x14def9dea2f79cd6,
+    0,
+    0x1000000000000000,
+];
+
+fn below_l(limbs: &[u64; 4]) -> bool {
+    for (limb, l) in limbs.iter().zip(L).rev() {
+        if *limb != l {
+            return *limb < l;
+        }
+    }
+    false
+}
+
+fn scalar_limbs(bytes: &[u8; 32]) -> [u64; 4] {
+    let mut limbs = [0; 4];
+    for (limb, word) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+        *limb = u64::from_le_bytes(word.try_into().unwrap());
+    }
+    limbs
+}
+
+// A little-endian number of up to 64 bytes mod L, one bit at a time
+fn reduce(bytes: &[u8]) -> [u8; 32] {
+    let mut remainder = [0u64; 4];
+    for &byte in bytes.iter().rev() {
+        for bit in (0..8).rev() {
+            // The remainder stays below L < 2^253, so doubling can't overflow
+            let mut carry = u64::from(byte >> bit & 1);
+            for limb in remainder.iter_mut() {
+                let next = *limb >> 63;
+                *limb = *limb << 1 | carry;
+                carry = next;
+            }
+            // Subtract L unless that goes below zero, picking the result with
+            // a mask since the nonce and the signature's s are secret
+            let mut difference = remainder;
+            let mut borrow = 0;
+            for (limb, l) in difference.iter_mut().zip(L) {
+                let (value, under) = limb.overflowing_sub(l);
+                let (value, under_again) = value.overflowing_sub(borrow);
+                *limb = value;
+                borrow = u64::from(under | under_again);
+            }
+            let keep = std::hint::black_box(borrow.wrapping_neg());
+            for (limb, difference) in remainder.iter_mut().zip(difference) {
+                *limb = *limb & keep | difference & !keep;
+            }
+        }
+    }
+
+    let mut reduced = [0; 32];
+    for (out, limb) in reduced.chunks_exact_mut(8).zip(remainder) {
+        out.copy_from_slice(&limb.to_le_bytes());
+    }
+    reduced
+}
+
+// (a * b + c) mod L
+fn mul_add(a: &[u8; 32], b: &[u8; 32], c: &[u8; 32]) -> [u8; 32] {
+    let (a, b) = (scalar_limbs(a), scalar_limbs(b));
+    let mut wide = [0u64; 8];
+    wide[..4].copy_from_slice(&scalar_limbs(c));
+    for (i, &a) in a.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, &b) in b.iter().enumerate() {
+            let sum = wide[i + j] as u128 + a as u128 * b as u128 + carry;
+            wide[i + j] = sum as u64;
+            carry = sum >> 64;
+        }
+        for limb in &mut wide[i + 4..] {
+            let sum = *limb as u128 + carry;
+            *limb = sum as u64;
+            carry = sum >> 64;
+        }
+    }
+
+    let bytes: Vec<u8> = wide.iter().flat_map(|limb| limb.to_le_bytes()).collect();
+    reduce(&bytes)
+}
+
+// Overwrites bytes with zeros in a way the compiler can't drop as a dead store
+fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+// Key material that is wiped when it goes out of scope
+struct Secret<const N: usize>([u8; N]);
+
+impl<const N: usize> Drop for Secret<N> {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+// The secret scalar, clamped as RFC 8032 requires, and the nonce prefix
+fn expand(secret: &[u8; 32]) -> (Secret<32>, Secret<32>) {
+    let hash = Secret(sha512(secret));
+    let mut scalar = Secret([0; 32]);
+    let mut prefix = Secret([0; 32]);
+    scalar.0.copy_from_slice(&hash.0[..32]);
+    prefix.0.copy_from_slice(&hash.0[32..]);
+    scalar.0[0] &= 248;
+    scalar.0[31] &= 127;
+    scalar.0[31] |= 64;
+    (scalar, prefix)
+}
+
+pub fn public_key(secret: &[u8; 32]) -> [u8; 32] {
+    let (scalar, _) = expand(secret);
+    Point::base().mul(&scalar.0).encode()
+}
+
+pub fn sign(secret: &[u8; 32], message: &[u8]) -> [u8; 64] {
+    let (scalar, prefix) = expand(secret);
+    let public = Point::base().mul(&scalar.0).encode();
+
+    let mut prefixed = [&prefix.0[..], message].concat();
+    let nonce = Secret(reduce(&Secret(sha512(&prefixed)).0));
+    wipe(&mut prefixed);
+    let r = Point::base().mul(&nonce.0).encode();
+    let challenge = reduce(&sha512(&[&r[..], &public, message].concat()));
+    let s = mul_add(&challenge, &scalar.0, &nonce.0);
+
+    let mut signature = [0; 64];
+    signature[..32].copy_from_slice(&r);
+    signature[32..].copy_from_slice(&s);
+    signature
+}
+
+// Checks [s]B = R + [k]A. Besides what RFC 8032 requires (canonical
+// encodings that decompress, and s below L), A and R of small order are
+// rejected, since with those a signature says nothing about the key.
+pub fn verify(public: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    let r: [u8; 32] = signature[..32].try_into().unwrap();
+    let s: [u8; 32] = signature[32..].try_into().unwrap();
+    let (Some(a), Some(r_point)) = (Point::decode(public), Point::decode(&r)) else {
+        return false;
+    };
+    if a.has_small_order() || r_point.has_small_order() || !below_l(&scalar_limbs(&s)) {
+        return false;
+    }
+
+    let challenge = reduce(&sha512(&[&r[..], public, message].concat()));
+    Point::base().mul(&s).add(&a.neg().mul(&challenge)).encode() == r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // Tests 1 to 3 from RFC 8032 section 7.1
+    const VECTORS: [(&str, &str, &str, &str); 3] = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+             5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac\
+             18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+        ),
+    ];
+
+    // Test 1024 from RFC 8032 section 7.1, with a 1023-byte message
+    const LONG_VECTOR: (&str, &str, &str, &str) = (
+        "f5e5767cf153319517630f226876b86c8160cc583bc013744c6bf255f5cc0ee5",
+        "278117fc144c72340f67d0f2316e8386ceffbf2b2428c9c51fef7c597f1d426e",
+        "08b8b2b733424243760fe426a4b54908632110a66c2f6591eabd3345e3e4eb98\
+         fa6e264bf09efe12ee50f8f54e9f77b1e355f6c50544e23fb1433ddf73be84d8\
+         79de7c0046dc4996d9e773f4bc9efe5738829adb26c81b37c93a1b270b20329d\
+         658675fc6ea534e0810a4432826bf58c941efb65d57a338bbd2e26640f89ffbc\
+         1a858efcb8550ee3a5e1998bd177e93a7363c344fe6b199ee5d02e82d522c4fe\
+         ba15452f80288a821a579116ec6dad2b3b310da903401aa62100ab5d1a36553e\
+         06203b33890cc9b832f79ef80560ccb9a39ce767967ed628c6ad573cb116dbef\
+         efd75499da96bd68a8a97b928a8bbc103b6621fcde2beca1231d206be6cd9ec7\
+         aff6f6c94fcd7204ed3455c68c83f4a41da4af2b74ef5c53f1d8ac70bdcb7ed1\
+         85ce81bd84359d44254d95629e9855a94a7c1958d1f8ada5d0532ed8a5aa3fb2\
+         d17ba70eb6248e594e1a2297acbbb39d502f1a8c6eb6f1ce22b3de1a1f40cc24\
+         554119a831a9aad6079cad88425de6bde1a9187ebb6092cf67bf2b13fd65f270\
+         88d78b7e883c8759d2c4f5c65adb7553878ad575f9fad878e80a0c9ba63bcbcc\
+         2732e69485bbc9c90bfbd62481d9089beccf80cfe2df16a2cf65bd92dd597b07\
+         07e0917af48bbb75fed413d238f5555a7a569d80c3414a8d0859dc65a46128ba\
+         b27af87a71314f318c782b23ebfe808b82b0ce26401d2e22f04d83d1255dc51a\
+         ddd3b75a2b1ae0784504df543af8969be3ea7082ff7fc9888c144da2af58429e\
+         c96031dbcad3dad9af0dcbaaaf268cb8fcffead94f3c7ca495e056a9b47acdb7\
+         51fb73e666c6c655ade8297297d07ad1ba5e43f1bca32301651339e22904cc8c\
+         42f58c30c04aafdb038dda0847dd988dcda6f3bfd15c4b4c4525004aa06eeff8\
+         ca61783aacec57fb3d1f92b0fe2fd1a85f6724517b65e614ad6808d6f6ee34df\
+         f7310fdc82aebfd904b01e1dc54b2927094b2db68d6f903b68401adebf5a7e08\
+         d78ff4ef5d63653a65040cf9bfd4aca7984a74d37145986780fc0b16ac451649\
+         de6188a7dbdf191f64b5fc5e2ab47b57f7f7276cd419c17a3ca8e1b939ae49e4\
+         88acba6b965610b5480109c8b17b80e1b7b750dfc7598d5d5011fd2dcc5600a3\
+         2ef5b52a1ecc820e308aa342721aac0943bf6686b64b2579376504ccc493d97e\
+         6aed3fb0f9cd71a43dd497f01f17c0e2cb3797aa2a2f256656168e6c496afc5f\
+         b93246f6b1116398a346f1a641f3b041e989f7914f90cc2c7fff357876e506b5\
+         0d334ba77c225bc307ba537152f3f1610e4eafe595f6d9d90d11faa933a15ef1\
+         369546868a7f3a45a96768d40fd9d03412c091c6315cf4fde7cb68606937380d\
+         b2eaaa707b4c4185c32eddcdd306705e4dc1ffc872eeee475a64dfac86aba41c\
+         0618983f8741c5ef68d3a101e8a3b8cac60c905c15fc910840b94c00a0b9d0",
+        "0aab4c900501b3e24d7cdf4663326a3a87df5e4843b2cbdb67cbf6e460fec350\
+         aa5371b1508f9f4528ecea23c436d94b5e8fcd4f681e30a6ac00a9704a188a03",
+    );
+
+    #[test]
+    fn test_sha512_known_vectors() {
+        assert_eq!(
+            hex(&sha512(b"")),
+            "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce\
+             47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"
+        );
+        assert_eq!(
+            hex(&sha512(b"abc")),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+        // 112 bytes, so the length spills into a second block
+        assert_eq!(
+            hex(&sha512(
+                b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmn\
+                  hijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu"
+            )),
+            "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018\
+             501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"
+        );
+    }
+
+    #[test]
+    fn test_field_constants() {
+        let d = Fe::from_bytes(&D);
+        assert_eq!(
+            d.mul(Fe([121666, 0, 0, 0, 0])),
+            Fe([121665, 0, 0, 0, 0]).neg()
+        );
+        assert_eq!(Fe::from_bytes(&SQRT_M1).square(), Fe::ONE.neg());
+        assert_eq!(d.mul(d.invert()), Fe::ONE);
+    }
+
+    #[test]
+    fn test_rfc8032_vectors() {
+        for (secret, public, message, signature) in VECTORS.into_iter().chain([LONG_VECTOR]) {
+            let secret = unhex(secret).try_into().unwrap();
+            let message = unhex(message);
+
+            assert_eq!(hex(&public_key(&secret)), public);
+            assert_eq!(hex(&sign(&secret, &message)), signature);
+            assert!(verify(
+                &unhex(public).try_into().unwrap(),
+                &message,
+                &unhex(signature).try_into().unwrap()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_scalar_multiplication() {
+        let base = Point::base();
+        let scalar = |n: u8| {
+            let mut bytes = [0; 32];
+            bytes[0] = n;
+            bytes
+        };
+        let mut l = [0; 32];
+        for (out, limb) in l.chunks_exact_mut(8).zip(L) {
+            out.copy_from_slice(&limb.to_le_bytes());
+        }
+
+        assert_eq!(base.mul(&scalar(0)).encode(), Point::IDENTITY.encode());
+        assert_eq!(base.mul(&scalar(1)).encode(), base.encode());
+        assert_eq!(
+            base.mul(&scalar(3)).encode(),
+            base.add(&base).add(&base).encode()
+        );
+        assert_eq!(base.mul(&l).encode(), Point::IDENTITY.encode());
+
+        let (mut a, mut b) = (Fe::ONE, Fe::ZERO);
+        Fe::conditional_swap(&mut a, &mut b, 0);
+        assert_eq!((a, b), (Fe::ONE, Fe::ZERO));
+        Fe::conditional_swap(&mut a, &mut b, 1);
+        assert_eq!((a, b), (Fe::ZERO, Fe::ONE));
+    }
+
+    #[test]
+    fn test_verify_rejects_changes() {
+        let secret = [7; 32];
+        let public = public_key(&secret);
+        let signature = sign(&secret, b"pingu");
+
+        assert!(verify(&public, b"pingu", &signature));
+        assert!(!verify(&public, b"pingo", &signature));
+        assert!(!verify(&public_key(&[8; 32]), b"pingu", &signature));
+
+        let mut tampered = signature;
+        tampered[40] ^= 1;
+        assert!(!verify(&public, b"pingu", &tampered));
+        // An s of L or more is rejected before any curve arithmetic
+        let mut malleated = signature;
+        malleated[63] |= 0xf0;
+        assert!(!verify(&public, b"pingu", &malleated));
+    }
+
+    // Encodings of public values that verification must not accept
+    fn y_encoding(y: u8) -> [u8; 32] {
+        let mut encoded = [0; 32];
+        encoded[0] = y;
+        encoded
+    }
+
+    // p = 2^255 - 19 with its lowest byte replaced, so 0xec is p - 1 and
+    // 0xee is p + 1, which Fe::from_bytes would otherwise read as 1
+    fn near_p(low: u8) -> [u8; 32] {
+        let mut encoded = [0xff; 32];
+        encoded[0] = low;
+        encoded[31] = 0x7f;
+        encoded
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_encodings() {
+        // No x satisfies the curve equation for y = 2
+        assert!(Point::decode(&y_encoding(2)).is_none());
+        assert!(Point::decode(&near_p(0xee)).is_none());
+        // x = 0 has no negative form
+        let mut negative_zero = y_encoding(1);
+        negative_zero[31] |= 0x80;
+        assert!(Point::decode(&negative_zero).is_none());
+        assert!(Point::decode(&y_encoding(1)).is_some());
+    }
+
+    #[test]
+    fn test_small_order_points() {
+        // The identity, y = -1 of order 2, and y = 0 of order 4
+        for encoded in [y_encoding(1), near_p(0xec), y_encoding(0)] {
+            assert!(Point::decode(&encoded).unwrap().has_small_order());
+        }
+        assert!(!Point::base().has_small_order());
+        assert!(!Point::base()
+            .add(&Point::decode(&y_encoding(0)).unwrap())
+            .has_small_order());
+    }
+
+    #[test]
+    fn test_verify_rejects_non_canonical_s() {
+        let secret = [7; 32];
+        let public = public_key(&secret);
+        let signature = sign(&secret, b"pingu");
+
+        // s + L passes the same equation, so only the range check stops it
+        let mut s = scalar_limbs(&signature[32..].try_into().unwrap());
+        let mut carry = 0;
+        for (limb, l) in s.iter_mut().zip(L) {
+            let (sum, over) = limb.overflowing_add(l);
+            let (sum, over_again) = sum.overflowing_add(carry);
+            *limb = sum;
+            carry = u64::from(over | over_again);
+        }
+        let mut malleated = signature;
+        for (out, limb) in malleated[32..].chunks_exact_mut(8).zip(s) {
+            out.copy_from_slice(&limb.to_le_bytes());
+        }
+        assert!(!verify(&public, b"pingu", &malleated));
+    }
+
+    #[test]
+    fn test_verify_rejects_bad_public_keys() {
+        let signature = sign(&[7; 32], b"pingu");
+        for public in [y_encoding(2), near_p(0xee), y_encoding(1), y_encoding(0)] {
+            assert!(!verify(&public, b"pingu", &signature));
+        }
+
+        // With the identity as the key, R = [s]B satisfies the equation for
+        // any message at all
+        let s = [9; 32];
+        let mut forged = [0; 64];
+        forged[..32].copy_from_slice(&Point::base().mul(&s).encode());
+        forged[32..].copy_from_slice(&reduce(&s));
+        assert!(!verify(&y_encoding(1), b"anything", &forged));
+    }
+
+    #[test]
+    fn test_verify_rejects_bad_r() {
+        let secret = [7; 32];
+        let (scalar, _) = expand(&secret);
+        let public = public_key(&secret);
+
+        // R as the identity with s = k * a makes the equation hold without
+        // the nonce, and the identity written as p + 1 must not sneak past
+        for r in [y_encoding(1), near_p(0xee)] {
+            let challenge = reduce(&sha512(&[&r[..], &public, b"pingu"].concat()));
+            let mut signature = [0; 64];
+            signature[..32].copy_from_slice(&r);
+            signature[32..].copy_from_slice(&mul_add(&challenge, &scalar.0, &[0; 32]));
+            assert!(!verify(&public, b"pingu", &signature));
+        }
+
+        let mut signature = sign(&secret, b"pingu");
+        signature[..32].copy_from_slice(&y_encoding(2));
+        assert!(!verify(&public, b"pingu", &signature));
+    }
+}
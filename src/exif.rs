@@ -0,0 +1,391 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ExifError {
+    #[error("Invalid EXIF byte order marker")]
+    InvalidByteOrder,
+    #[error("EXIF data ends unexpectedly at offset {0}")]
+    Truncated(usize),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExifValue {
+    Ascii(String),
+    Integers(Vec<i64>),
+    Rationals(Vec<(i64, i64)>),
+    // Types without a useful text form, e.g. UNDEFINED blobs
+    Other { field_type: u16, count: u32 },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExifTag {
+    pub tag: u16,
+    pub value: ExifValue,
+}
+
+// Tags from IFD0 and the Exif sub-IFD, in file order
+#[derive(Debug, PartialEq, Clone)]
+pub struct Exif {
+    tags: Vec<ExifTag>,
+}
+
+const EXIF_IFD_POINTER: u16 = 0x8769;
+
+#[allow(dead_code)]
+impl Exif {
+    pub fn tags(&self) -> &[ExifTag] {
+        &self.tags
+    }
+
+    pub fn get(&self, tag: u16) -> Option<&ExifValue> {
+        self.tags.iter().find(|t| t.tag == tag).map(|t| &t.value)
+    }
+
+    pub fn orientation(&self) -> Option<u16> {
+        match self.get(0x0112)? {
+            ExifValue::Integers(values) => values.first().map(|&v| v as u16),
+            _ => None,
+        }
+    }
+}
+
+// Parses the TIFF structure that makes up the eXIf chunk data
+impl TryFrom<&[u8]> for Exif {
+    type Error = ExifError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let reader = match value.get(..4) {
+            Some(b"II*\0") => Reader {
+                data: value,
+                little_endian: true,
+            },
+            Some(b"MM\0*") => Reader {
+                data: value,
+                little_endian: false,
+            },
+            _ => return Err(ExifError::InvalidByteOrder),
+        };
+
+        let mut tags = Vec::new();
+        let ifd0 = reader.u32(4)? as usize;
+        reader.read_ifd(ifd0, &mut tags)?;
+
+        let exif_ifd = tags.iter().find_map(|t| match (&t.tag, &t.value) {
+            (&EXIF_IFD_POINTER, ExifValue::Integers(values)) => values.first().copied(),
+            _ => None,
+        });
+        if let Some(offset) = exif_ifd {
+            reader.read_ifd(offset as usize, &mut tags)?;
+        }
+        tags.retain(|t| t.tag != EXIF_IFD_POINTER);
+
+        Ok(Exif { tags })
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], ExifError> {
+        self.data
+            .get(offset..offset + N)
+            .map(|b| b.try_into().unwrap())
+            .ok_or(ExifError::Truncated(offset))
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, ExifError> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, ExifError> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn read_ifd(&self, offset: usize, tags: &mut Vec<ExifTag>) -> Result<(), ExifError> {
+        let count = self.u16(offset)? as usize;
+        for i in 0..count {
+            let entry = offset + 2 + i * 12;
+            let tag = self.u16(entry)?;
+            let field_type = self.u16(entry + 2)?;
+            let count = self.u32(entry + 4)?;
+            let value = self.read_value(entry + 8, field_type, count)?;
+            tags.push(ExifTag { tag, value });
+        }
+        Ok(())
+    }
+
+    fn read_value(&self, at: usize, field_type: u16, count: u32) -> Result<ExifValue, ExifError> {
+        let size = match field_type {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 => 4,
+            5 | 10 => 8,
+            _ => return Ok(ExifValue::Other { field_type, count }),
+        };
+        // Values that fit in four bytes are stored in the entry itself
+        let total = size * count as usize;
+        let start = if total <= 4 {
+            at
+        } else {
+            self.u32(at)? as usize
+        };
+        if self.data.len() < start.saturating_add(total) {
+            return Err(ExifError::Truncated(start));
+        }
+
+        let items = (0..count as usize).map(|i| start + i * size);
+        let value = match field_type {
+            2 => {
+                let text = &self.data[start..start + total];
+                let text = text.split(|&b| b == 0).next().unwrap_or_default();
+                ExifValue::Ascii(String::from_utf8_lossy(text).into_owned())
+            }
+            1 => ExifValue::Integers(items.map(|i| self.data[i] as i64).collect()),
+            6 => ExifValue::Integers(items.map(|i| self.data[i] as i8 as i64).collect()),
+            3 => ExifValue::Integers(
+                items
+                    .map(|i| self.u16(i).map(|v| v as i64))
+                    .collect::<Result<_, _>>()?,
+            ),
+            8 => ExifValue::Integers(
+                items
+                    .map(|i| self.u16(i).map(|v| v as i16 as i64))
+                    .collect::<Result<_, _>>()?,
+            ),
+            4 => ExifValue::Integers(
+                items
+                    .map(|i| self.u32(i).map(|v| v as i64))
+                    .collect::<Result<_, _>>()?,
+            ),
+            9 => ExifValue::Integers(
+                items
+                    .map(|i| self.u32(i).map(|v| v as i32 as i64))
+                    .collect::<Result<_, _>>()?,
+            ),
+            5 => ExifValue::Rationals(
+                items
+                    .map(|i| Ok((self.u32(i)? as i64, self.u32(i + 4)? as i64)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            10 => ExifValue::Rationals(
+                items
+                    .map(|i| Ok((self.u32(i)? as i32 as i64, self.u32(i + 4)? as i32 as i64)))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => ExifValue::Other { field_type, count },
+        };
+        Ok(value)
+    }
+}
+
+impl ExifTag {
+    // Names of the common tags; anything else is shown by number
+    pub fn name(&self) -> Option<&'static str> {
+        let name = match self.tag {
+            0x010E => "ImageDescription",
+            0x010F => "Make",
+            0x0110 => "Model",
+            0x0112 => "Orientation",
+            0x011A => "XResolution",
+            0x011B => "YResolution",
+            0x0128 => "ResolutionUnit",
+            0x0131 => "Software",
+            0x0132 => "DateTime",
+            0x013B => "Artist",
+            0x8298 => "Copyright",
+            0x829A => "ExposureTime",
+            0x829D => "FNumber",
+            0x8827 => "ISOSpeedRatings",
+            0x9003 => "DateTimeOriginal",
+            0x9004 => "DateTimeDigitized",
+            0x920A => "FocalLength",
+            0xA002 => "PixelXDimension",
+            0xA003 => "PixelYDimension",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+fn orientation_name(orientation: i64) -> Option<&'static str> {
+    let name = match orientation {
+        1 => "normal",
+        2 => "mirrored horizontally",
+        3 => "rotated 180",
+        4 => "mirrored vertically",
+        5 => "mirrored horizontally, rotated 270 CW",
+        6 => "rotated 90 CW",
+        7 => "mirrored horizontally, rotated 90 CW",
+        8 => "rotated 270 CW",
+        _ => return None,
+    };
+    Some(name)
+}
+
+impl Display for ExifValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExifValue::Ascii(text) => write!(f, "{}", text),
+            ExifValue::Integers(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "{}", values.join(", "))
+            }
+            ExifValue::Rationals(values) => {
+                let values: Vec<String> =
+                    values.iter().map(|(n, d)| format!("{}/{}", n, d)).collect();
+                write!(f, "{}", values.join(", "))
+            }
+            ExifValue::Other { field_type, count } => {
+                write!(f, "<{} values of type {}>", count, field_type)
+            }
+        }
+    }
+}
+
+impl Display for ExifTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}: {}", name, self.value)?,
+            None => write!(f, "0x{:04X}: {}", self.tag, self.value)?,
+        }
+        if let (0x0112, ExifValue::Integers(values)) = (self.tag, &self.value) {
+            if let Some(name) = values.first().and_then(|&v| orientation_name(v)) {
+                write!(f, " ({})", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small EXIF block: orientation, make and date in IFD0, plus an Exif
+    // sub-IFD holding DateTimeOriginal and an exposure time
+    fn exif_fixture(little_endian: bool) -> Vec<u8> {
+        let u16b = |v: u16| {
+            if little_endian {
+                v.to_le_bytes()
+            } else {
+                v.to_be_bytes()
+            }
+        };
+        let u32b = |v: u32| {
+            if little_endian {
+                v.to_le_bytes()
+            } else {
+                v.to_be_bytes()
+            }
+        };
+        let entry = |tag: u16, field_type: u16, count: u32, value: [u8; 4]| {
+            [&u16b(tag)[..], &u16b(field_type), &u32b(count), &value].concat()
+        };
+
+        let date = b"2024:04:13 10:20:30\0";
+        // Header 8 + IFD0 (2 + 4 * 12 + 4) = 62, then the sub-IFD
+        let sub_ifd = 62u32;
+        let sub_ifd_len = 2 + 2 * 12 + 4;
+        let make_at = sub_ifd + sub_ifd_len;
+        let date_at = make_at + 6;
+        let exposure_at = date_at + date.len() as u32;
+
+        let mut orientation = [0; 4];
+        orientation[..2].copy_from_slice(&u16b(6));
+
+        let mut data = if little_endian {
+            b"II*\0".to_vec()
+        } else {
+            b"MM\0*".to_vec()
+        };
+        data.extend_from_slice(&u32b(8));
+        data.extend_from_slice(&u16b(4));
+        data.extend(entry(0x010F, 2, 6, u32b(make_at)));
+        data.extend(entry(0x0112, 3, 1, orientation));
+        data.extend(entry(0x0132, 2, date.len() as u32, u32b(date_at)));
+        data.extend(entry(EXIF_IFD_POINTER, 4, 1, u32b(sub_ifd)));
+        data.extend_from_slice(&u32b(0));
+
+        data.extend_from_slice(&u16b(2));
+        data.extend(entry(0x829A, 5, 1, u32b(exposure_at)));
+        data.extend(entry(0x9003, 2, date.len() as u32, u32b(date_at)));
+        data.extend_from_slice(&u32b(0));
+
+        data.extend_from_slice(b"Pingu\0");
+        data.extend_from_slice(date);
+        data.extend_from_slice(&u32b(1));
+        data.extend_from_slice(&u32b(250));
+        data
+    }
+
+    #[test]
+    fn test_parse_exif_both_byte_orders() {
+        for little_endian in [true, false] {
+            let exif = Exif::try_from(exif_fixture(little_endian).as_slice()).unwrap();
+
+            assert_eq!(exif.orientation(), Some(6));
+            assert_eq!(
+                exif.get(0x010F),
+                Some(&ExifValue::Ascii("Pingu".to_string()))
+            );
+            assert_eq!(
+                exif.get(0x829A),
+                Some(&ExifValue::Rationals(vec![(1, 250)]))
+            );
+            assert_eq!(
+                exif.get(0x9003),
+                Some(&ExifValue::Ascii("2024:04:13 10:20:30".to_string()))
+            );
+            assert_eq!(exif.get(EXIF_IFD_POINTER), None);
+            assert_eq!(exif.tags().len(), 5);
+        }
+    }
+
+    #[test]
+    fn test_exif_tag_display() {
+        let exif = Exif::try_from(exif_fixture(true).as_slice()).unwrap();
+        let lines: Vec<String> = exif.tags().iter().map(|t| t.to_string()).collect();
+
+        assert_eq!(lines[0], "Make: Pingu");
+        assert_eq!(lines[1], "Orientation: 6 (rotated 90 CW)");
+        assert_eq!(lines[3], "ExposureTime: 1/250");
+
+        let unknown = ExifTag {
+            tag: 0xC4A5,
+            value: ExifValue::Other {
+                field_type: 7,
+                count: 3,
+            },
+        };
+        assert_eq!(unknown.to_string(), "0xC4A5: <3 values of type 7>");
+    }
+
+    #[test]
+    fn test_invalid_exif() {
+        assert_eq!(
+            Exif::try_from(&b"XX*\0"[..]),
+            Err(ExifError::InvalidByteOrder)
+        );
+
+        let data = exif_fixture(true);
+        assert!(matches!(
+            Exif::try_from(&data[..40]),
+            Err(ExifError::Truncated(_))
+        ));
+    }
+}
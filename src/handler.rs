@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use crate::{
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    color::{Chromaticities, RenderingIntent},
+    ihdr::Ihdr,
+    splt::SuggestedPalette,
+};
+
+// Turns a chunk's data into a readable one-line summary. None means there is
+// nothing worth showing, e.g. data too short to interpret.
+pub trait ChunkHandler {
+    fn describe(&self, data: &[u8]) -> Option<String>;
+}
+
+impl<F: Fn(&[u8]) -> Option<String>> ChunkHandler for F {
+    fn describe(&self, data: &[u8]) -> Option<String> {
+        self(data)
+    }
+}
+
+// Handlers by chunk type. standard() covers the public chunks pingu knows;
+// library users register their own for private types on top.
+#[derive(Default)]
+pub struct HandlerRegistry {
+    handlers: HashMap<[u8; 4], Box<dyn ChunkHandler>>,
+}
+
+impl HandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn standard() -> Self {
+        let mut registry = Self::new();
+        let mut add = |name: &[u8; 4], handler: fn(&[u8]) -> Option<String>| {
+            let chunk_type = ChunkType::try_from(*name).expect("standard chunk types are valid");
+            registry.register(chunk_type, handler);
+        };
+
+        add(b"IHDR", |data| {
+            Ihdr::try_from(data).ok().map(|ihdr| {
+                if ihdr.has_valid_depth() {
+                    ihdr.to_string()
+                } else {
+                    format!("{} (invalid bit depth for color type)", ihdr)
+                }
+            })
+        });
+        add(b"sPLT", |data| {
+            Some(match SuggestedPalette::try_from(data) {
+                Ok(palette) => format!("suggested palette {}", palette),
+                Err(e) => format!("invalid sPLT: {}", e),
+            })
+        });
+        add(b"cHRM", |data| {
+            Some(match Chromaticities::try_from(data) {
+                Ok(chrm) => chrm.to_string(),
+                Err(e) => format!("invalid cHRM: {}", e),
+            })
+        });
+        add(b"sRGB", |data| {
+            Some(match RenderingIntent::try_from(data) {
+                Ok(intent) => format!("rendering intent = {}", intent),
+                Err(e) => format!("invalid sRGB: {}", e),
+            })
+        });
+        add(b"tEXt", |data| {
+            let keyword_len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+            Some(format!(
+                "keyword: {}",
+                String::from_utf8_lossy(&data[..keyword_len])
+            ))
+        });
+
+        registry
+    }
+
+    // Returns the handler this one replaces, if any
+    pub fn register(
+        &mut self,
+        chunk_type: ChunkType,
+        handler: impl ChunkHandler + 'static,
+    ) -> Option<Box<dyn ChunkHandler>> {
+        self.handlers.insert(chunk_type.bytes(), Box::new(handler))
+    }
+
+    pub fn describe(&self, chunk: &Chunk) -> Option<String> {
+        self.handlers
+            .get(&chunk.chunk_type().bytes())
+            .and_then(|handler| handler.describe(chunk.data()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, rc::Rc, str::FromStr};
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    struct Version {
+        calls: Rc<Cell<usize>>,
+    }
+
+    impl ChunkHandler for Version {
+        fn describe(&self, data: &[u8]) -> Option<String> {
+            self.calls.set(self.calls.get() + 1);
+            let [major, minor] = data else {
+                return None;
+            };
+            Some(format!("format version {}.{}", major, minor))
+        }
+    }
+
+    #[test]
+    fn test_custom_handler() {
+        let calls = Rc::new(Cell::new(0));
+        let mut registry = HandlerRegistry::standard();
+        let previous = registry.register(
+            ChunkType::from_str("veRs").unwrap(),
+            Version {
+                calls: Rc::clone(&calls),
+            },
+        );
+        assert!(previous.is_none());
+
+        assert_eq!(
+            registry.describe(&chunk("veRs", &[2, 1])).as_deref(),
+            Some("format version 2.1")
+        );
+        assert_eq!(registry.describe(&chunk("veRs", &[2])), None);
+        assert_eq!(registry.describe(&chunk("ruSt", &[2, 1])), None);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_register_replaces_standard_handler() {
+        let mut registry = HandlerRegistry::standard();
+        let text = chunk("tEXt", b"Title\0pingu");
+        assert_eq!(registry.describe(&text).as_deref(), Some("keyword: Title"));
+
+        let previous = registry.register(ChunkType::from_str("tEXt").unwrap(), |data: &[u8]| {
+            Some(String::from_utf8_lossy(data).replace('\0', " = "))
+        });
+        assert!(previous.is_some());
+        assert_eq!(registry.describe(&text).as_deref(), Some("Title = pingu"));
+    }
+
+    #[test]
+    fn test_standard_handlers() {
+        let registry = HandlerRegistry::standard();
+
+        assert_eq!(
+            registry.describe(&chunk("sRGB", &[0])).as_deref(),
+            Some("rendering intent = perceptual")
+        );
+        assert_eq!(
+            registry.describe(&chunk("sRGB", &[])).as_deref(),
+            Some("invalid sRGB: sRGB must be 1 bytes, found 0")
+        );
+        assert_eq!(registry.describe(&chunk("IHDR", &[0; 3])), None);
+        assert!(HandlerRegistry::new()
+            .describe(&chunk("sRGB", &[0]))
+            .is_none());
+    }
+}
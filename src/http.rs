@@ -0,0 +1,306 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use thiserror::Error;
+
+use crate::args;
+
+#[derive(Debug, Error)]
+pub enum HttpError {
+    #[error("Cannot fetch {0}: only http:// URLs are supported, there is no TLS support")]
+    UnsupportedScheme(String),
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("{url} returned HTTP {status} {reason}")]
+    Status {
+        url: String,
+        status: u16,
+        reason: String,
+    },
+    #[error("Gave up on {0} after {MAX_REDIRECTS} redirects")]
+    TooManyRedirects(String),
+    #[error("Malformed HTTP response from {url}: {reason}")]
+    Malformed { url: String, reason: String },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+const MAX_REDIRECTS: usize = 5;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Url<'a> {
+    host: &'a str,
+    port: u16,
+    path: &'a str,
+}
+
+impl<'a> Url<'a> {
+    fn parse(url: &'a str) -> Result<Self, HttpError> {
+        if url.starts_with("https://") {
+            return Err(HttpError::UnsupportedScheme(url.to_string()));
+        }
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| HttpError::InvalidUrl(url.to_string()))?;
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| HttpError::InvalidUrl(url.to_string()))?,
+            ),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(HttpError::InvalidUrl(url.to_string()));
+        }
+        Ok(Url { host, port, path })
+    }
+}
+
+struct Response {
+    status: u16,
+    reason: String,
+    location: Option<String>,
+    body: Vec<u8>,
+}
+
+// GETs the URL, following redirects, and returns the body of the final
+// 200 response. Bodies over limit bytes are refused like oversized stdin.
+pub fn fetch(url: &str, limit: u64) -> Result<Vec<u8>, HttpError> {
+    let mut url = url.to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        let response = get(&url, limit)?;
+        match response.status {
+            200 => return Ok(response.body),
+            301 | 302 | 303 | 307 | 308 => {
+                let location = response.location.ok_or_else(|| HttpError::Malformed {
+                    url: url.clone(),
+                    reason: "redirect without a Location header".to_string(),
+                })?;
+                url = resolve(&url, &location);
+            }
+            status => {
+                return Err(HttpError::Status {
+                    url,
+                    status,
+                    reason: response.reason,
+                })
+            }
+        }
+    }
+    Err(HttpError::TooManyRedirects(url))
+}
+
+// Location may be absolute, host-relative or relative to the current path
+fn resolve(base: &str, location: &str) -> String {
+    if location.contains("://") {
+        return location.to_string();
+    }
+    let after_scheme = base.find("://").map_or(0, |i| i + 3);
+    let authority_end = base[after_scheme..]
+        .find('/')
+        .map_or(base.len(), |i| after_scheme + i);
+    if location.starts_with('/') {
+        format!("{}{}", &base[..authority_end], location)
+    } else {
+        let dir_end = base[authority_end..]
+            .rfind('/')
+            .map_or(authority_end, |i| authority_end + i);
+        format!("{}/{}", &base[..dir_end], location)
+    }
+}
+
+fn get(url: &str, limit: u64) -> Result<Response, HttpError> {
+    let parsed = Url::parse(url)?;
+    let mut stream = TcpStream::connect((parsed.host, parsed.port))?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    // HTTP/1.0 keeps the server from answering with chunked encoding
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: pingu/{}\r\nConnection: close\r\n\r\n",
+        parsed.path,
+        parsed.host,
+        env!("CARGO_PKG_VERSION")
+    );
+    stream.write_all(request.as_bytes())?;
+
+    let malformed = |reason: &str| HttpError::Malformed {
+        url: url.to_string(),
+        reason: reason.to_string(),
+    };
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.trim_end().splitn(3, ' ');
+    let status = match (parts.next(), parts.next()) {
+        (Some(version), Some(status)) if version.starts_with("HTTP/") => status
+            .parse()
+            .map_err(|_| malformed("status code is not a number"))?,
+        _ => return Err(malformed("missing status line")),
+    };
+    let reason = parts.next().unwrap_or_default().to_string();
+
+    let mut location = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(malformed("headers end early"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("location") {
+                location = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let body = if status == 200 {
+        args::read_limited(reader.by_ref(), limit)?
+    } else {
+        Vec::new()
+    };
+    Ok(Response {
+        status,
+        reason,
+        location,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::Read, net::TcpListener, thread};
+
+    // Serves one canned response per path, for as many requests as given
+    fn serve(requests: usize, route: fn(&str) -> Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut byte = [0];
+                while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                    request.push(byte[0]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split(' ').nth(1).unwrap_or("/");
+                stream.write_all(&route(path)).unwrap();
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    fn response(status: &str, headers: &str, body: &[u8]) -> Vec<u8> {
+        let mut bytes = format!("HTTP/1.0 {}\r\n{}\r\n", status, headers).into_bytes();
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    fn small_png() -> Vec<u8> {
+        std::fs::read("download.png").unwrap()
+    }
+
+    #[test]
+    fn test_fetch_png() {
+        let base = serve(1, |_| {
+            response("200 OK", "Content-Type: image/png\r\n", &small_png())
+        });
+
+        let bytes = fetch(&format!("{}/img.png", base), 1 << 20).unwrap();
+
+        assert_eq!(bytes, small_png());
+        assert!(pingu::png::Png::try_from(bytes.as_slice()).is_ok());
+    }
+
+    #[test]
+    fn test_fetch_follows_redirects() {
+        let base = serve(2, |path| match path {
+            "/old/img.png" => response("302 Found", "Location: /new/img.png\r\n", b""),
+            "/new/img.png" => response("200 OK", "", &small_png()),
+            _ => response("404 Not Found", "", b""),
+        });
+
+        assert_eq!(
+            fetch(&format!("{}/old/img.png", base), 1 << 20).unwrap(),
+            small_png()
+        );
+    }
+
+    #[test]
+    fn test_fetch_reports_status() {
+        let base = serve(1, |_| response("404 Not Found", "", b"no such image"));
+
+        let err = fetch(&format!("{}/missing.png", base), 1 << 20).unwrap_err();
+
+        assert!(matches!(err, HttpError::Status { status: 404, .. }));
+        assert!(err.to_string().ends_with("returned HTTP 404 Not Found"));
+    }
+
+    #[test]
+    fn test_fetch_redirect_loop() {
+        let base = serve(MAX_REDIRECTS + 1, |_| {
+            response("301 Moved Permanently", "Location: loop.png\r\n", b"")
+        });
+
+        assert!(matches!(
+            fetch(&format!("{}/loop.png", base), 1 << 20),
+            Err(HttpError::TooManyRedirects(_))
+        ));
+    }
+
+    #[test]
+    fn test_fetch_respects_limit() {
+        let base = serve(1, |_| response("200 OK", "", &small_png()));
+
+        assert!(matches!(
+            fetch(&format!("{}/img.png", base), 100),
+            Err(HttpError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_urls() {
+        let err = fetch("https://example.com/img.png", 1 << 20).unwrap_err();
+        assert!(matches!(err, HttpError::UnsupportedScheme(_)));
+        assert!(err.to_string().contains("only http:// URLs are supported"));
+        assert!(matches!(
+            fetch("ftp://example.com/img.png", 1 << 20),
+            Err(HttpError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            fetch("http://example.com:http/", 1 << 20),
+            Err(HttpError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let base = "http://example.com:8080/images/old.png";
+        assert_eq!(resolve(base, "/new.png"), "http://example.com:8080/new.png");
+        assert_eq!(
+            resolve(base, "new.png"),
+            "http://example.com:8080/images/new.png"
+        );
+        assert_eq!(
+            resolve(base, "http://other.org/x.png"),
+            "http://other.org/x.png"
+        );
+        assert_eq!(
+            resolve("http://example.com", "x.png"),
+            "http://example.com/x.png"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::chunk::Chunk;
+
+#[derive(Debug, Error)]
+pub enum IhdrError {
+    #[error("Chunk is not an IHDR chunk: {0}")]
+    NotIhdr(String),
+    #[error("Invalid IHDR length: {0}")]
+    InvalidLength(usize),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Ihdr {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace_method: u8,
+}
+
+#[allow(dead_code)]
+impl Ihdr {
+    pub fn channels(&self) -> u8 {
+        match self.color_type {
+            0 => 1,
+            2 => 3,
+            3 => 1,
+            4 => 2,
+            6 => 4,
+            _ => 0,
+        }
+    }
+
+    pub fn color_type_name(&self) -> &'static str {
+        match self.color_type {
+            0 => "grayscale",
+            2 => "RGB",
+            3 => "palette",
+            4 => "grayscale+alpha",
+            6 => "RGB+alpha",
+            _ => "unknown",
+        }
+    }
+
+    // Bit depths the spec allows for a color type; empty for unknown types
+    pub fn allowed_bit_depths(color_type: u8) -> &'static [u8] {
+        match color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => &[],
+        }
+    }
+
+    pub fn has_valid_depth(&self) -> bool {
+        Self::allowed_bit_depths(self.color_type).contains(&self.bit_depth)
+    }
+
+    pub fn bits_per_pixel(&self) -> u32 {
+        self.channels() as u32 * self.bit_depth as u32
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.width
+            .to_be_bytes()
+            .iter()
+            .chain(self.height.to_be_bytes().iter())
+            .chain(
+                [
+                    self.bit_depth,
+                    self.color_type,
+                    self.compression_method,
+                    self.filter_method,
+                    self.interlace_method,
+                ]
+                .iter(),
+            )
+            .copied()
+            .collect()
+    }
+}
+
+impl TryFrom<&[u8]> for Ihdr {
+    type Error = IhdrError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 13 {
+            return Err(IhdrError::InvalidLength(value.len()));
+        }
+
+        Ok(Ihdr {
+            width: u32::from_be_bytes([value[0], value[1], value[2], value[3]]),
+            height: u32::from_be_bytes([value[4], value[5], value[6], value[7]]),
+            bit_depth: value[8],
+            color_type: value[9],
+            compression_method: value[10],
+            filter_method: value[11],
+            interlace_method: value[12],
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Ihdr {
+    type Error = IhdrError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        if chunk.chunk_type().bytes() != *b"IHDR" {
+            return Err(IhdrError::NotIhdr(chunk.chunk_type().to_string()));
+        }
+
+        Ihdr::try_from(chunk.data())
+    }
+}
+
+impl Display for Ihdr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} x {} image, {}-bit {}, {}",
+            self.width,
+            self.height,
+            self.bits_per_pixel(),
+            self.color_type_name(),
+            if self.interlace_method == 0 {
+                "non-interlaced"
+            } else {
+                "interlaced"
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn testing_ihdr_bytes() -> Vec<u8> {
+        vec![0, 0, 0, 50, 0, 0, 0, 40, 8, 6, 0, 0, 0]
+    }
+
+    #[test]
+    fn test_ihdr_from_bytes() {
+        let ihdr = Ihdr::try_from(testing_ihdr_bytes().as_slice()).unwrap();
+
+        assert_eq!(ihdr.width, 50);
+        assert_eq!(ihdr.height, 40);
+        assert_eq!(ihdr.bit_depth, 8);
+        assert_eq!(ihdr.color_type, 6);
+        assert_eq!(ihdr.bits_per_pixel(), 32);
+        assert_eq!(ihdr.as_bytes(), testing_ihdr_bytes());
+    }
+
+    #[test]
+    fn test_ihdr_invalid_length() {
+        assert!(Ihdr::try_from(&testing_ihdr_bytes()[..12]).is_err());
+    }
+
+    #[test]
+    fn test_ihdr_from_chunk() {
+        let chunk = Chunk::new(ChunkType::from_str("IHDR").unwrap(), testing_ihdr_bytes());
+        assert!(Ihdr::try_from(&chunk).is_ok());
+
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), testing_ihdr_bytes());
+        assert!(Ihdr::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_ihdr_valid_depths() {
+        let mut bytes = testing_ihdr_bytes();
+        assert!(Ihdr::try_from(bytes.as_slice()).unwrap().has_valid_depth());
+
+        // Palette images top out at 8 bits per index
+        bytes[8..10].copy_from_slice(&[16, 3]);
+        assert!(!Ihdr::try_from(bytes.as_slice()).unwrap().has_valid_depth());
+
+        bytes[8..10].copy_from_slice(&[16, 0]);
+        assert!(Ihdr::try_from(bytes.as_slice()).unwrap().has_valid_depth());
+
+        bytes[8..10].copy_from_slice(&[8, 5]);
+        assert!(!Ihdr::try_from(bytes.as_slice()).unwrap().has_valid_depth());
+    }
+
+    #[test]
+    fn test_ihdr_display() {
+        let ihdr = Ihdr::try_from(testing_ihdr_bytes().as_slice()).unwrap();
+        assert_eq!(
+            ihdr.to_string(),
+            "50 x 40 image, 32-bit RGB+alpha, non-interlaced"
+        );
+    }
+}
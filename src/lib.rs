@@ -0,0 +1,21 @@
+pub mod api;
+pub mod builder;
+pub mod chunk;
+pub mod chunk_type;
+pub mod color;
+pub mod ecc;
+pub mod ed25519;
+pub mod exif;
+pub mod handler;
+pub mod ihdr;
+pub mod lsb;
+pub mod png;
+#[cfg(feature = "sha256")]
+pub mod sha256;
+pub mod splt;
+pub mod time;
+pub mod validate;
+pub mod zlib;
+
+pub type Error = Box<dyn std::error::Error>;
+pub type Result<T> = std::result::Result<T, Error>;
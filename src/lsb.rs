@@ -0,0 +1,469 @@
+use thiserror::Error;
+
+use crate::{
+    ihdr::{Ihdr, IhdrError},
+    png::Png,
+    zlib::{self, ZlibError},
+};
+
+#[derive(Debug, Error)]
+pub enum LsbError {
+    #[error("PNG has no IHDR chunk")]
+    MissingHeader,
+    #[error("PNG has no IDAT chunk")]
+    MissingImageData,
+    #[error("LSB mode does not support {0}")]
+    Unsupported(String),
+    #[error("Invalid row filter type: {0}")]
+    InvalidFilter(u8),
+    #[error("A {width}x{height} image is too large to hold in memory")]
+    ImageTooLarge { width: u32, height: u32 },
+    #[error("Image data is {actual} bytes, expected {expected}")]
+    InvalidImageLength { expected: usize, actual: usize },
+    #[error("Message is {len} bytes but the image can only hold {capacity}")]
+    MessageTooLarge { len: usize, capacity: usize },
+    #[error("No LSB message found")]
+    NoMessage,
+    #[error(transparent)]
+    IhdrError(#[from] IhdrError),
+    #[error(transparent)]
+    ZlibError(#[from] ZlibError),
+    #[error(transparent)]
+    PngError(#[from] Box<dyn std::error::Error>),
+}
+
+// The message is stored as a 32-bit big-endian length followed by its bytes
+const LENGTH_BYTES: usize = 4;
+
+struct Pixels {
+    header: Ihdr,
+    rows: Vec<u8>,
+}
+
+impl Pixels {
+    fn read(png: &Png) -> Result<Self, LsbError> {
+        let header = Ihdr::try_from(png.chunk_by_type("IHDR").ok_or(LsbError::MissingHeader)?)?;
+        if header.interlace_method != 0 {
+            return Err(LsbError::Unsupported("interlaced images".to_string()));
+        }
+        if header.color_type == 3 {
+            return Err(LsbError::Unsupported("palette images".to_string()));
+        }
+        if header.channels() == 0 || !matches!(header.bit_depth, 8 | 16) {
+            return Err(LsbError::Unsupported(format!(
+                "{}-bit {} images",
+                header.bit_depth,
+                header.color_type_name()
+            )));
+        }
+
+        if png.chunk_by_type("IDAT").is_none() {
+            return Err(LsbError::MissingImageData);
+        }
+        // IHDR says how much the data inflates to, so a stream that keeps
+        // going past that is cut off rather than filling memory
+        let filtered = zlib::decompress_with_limit(&png.image_data(), filtered_size(&header)?)?;
+        let rows = unfilter(&filtered, &header)?;
+
+        Ok(Pixels { header, rows })
+    }
+
+    // 16-bit samples are big-endian, so only their second byte is touched
+    fn carriers(&mut self) -> impl Iterator<Item = &mut u8> {
+        let step = self.header.bit_depth as usize / 8;
+        self.rows.iter_mut().skip(step - 1).step_by(step)
+    }
+
+    fn capacity(&mut self) -> usize {
+        (self.carriers().count() / 8).saturating_sub(LENGTH_BYTES)
+    }
+
+    // Each row gets the filter whose output has the smallest sum of absolute
+    // values as signed bytes, the heuristic libpng uses, before deflating
+    fn write(self, png: &mut Png) -> Result<(), LsbError> {
+        let stride = row_stride(&self.header)?;
+        let bpp = (self.header.bits_per_pixel() as usize / 8).max(1);
+        let mut filtered = Vec::with_capacity(self.rows.len() + self.header.height as usize);
+        let mut previous = None;
+        for row in self.rows.chunks(stride) {
+            let (filter, line) = (0..=4)
+                .map(|filter| (filter, filter_row(filter, row, previous, bpp)))
+                .min_by_key(|(_, line)| {
+                    line.iter()
+                        .map(|&b| (b as i8).unsigned_abs() as u64)
+                        .sum::<u64>()
+                })
+                .unwrap();
+            filtered.push(filter);
+            filtered.extend_from_slice(&line);
+            previous = Some(row);
+        }
+
+        png.replace_image_data(zlib::compress(&filtered))?;
+        Ok(())
+    }
+}
+
+// Checked, since IHDR dimensions come straight from the file
+fn row_stride(header: &Ihdr) -> Result<usize, LsbError> {
+    (header.width as usize)
+        .checked_mul(header.bits_per_pixel() as usize)
+        .map(|bits| bits.div_ceil(8))
+        .ok_or(LsbError::ImageTooLarge {
+            width: header.width,
+            height: header.height,
+        })
+}
+
+fn filter_row(filter: u8, row: &[u8], previous: Option<&[u8]>, bpp: usize) -> Vec<u8> {
+    (0..row.len())
+        .map(|x| {
+            let left = if x >= bpp { row[x - bpp] } else { 0 };
+            let up = previous.map_or(0, |row| row[x]);
+            let up_left = match previous {
+                Some(row) if x >= bpp => row[x - bpp],
+                _ => 0,
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                _ => paeth(left, up, up_left),
+            };
+            row[x].wrapping_sub(predictor)
+        })
+        .collect()
+}
+
+// Size of the inflated image data: every row plus its filter byte
+fn filtered_size(header: &Ihdr) -> Result<usize, LsbError> {
+    (row_stride(header)? + 1)
+        .checked_mul(header.height as usize)
+        .ok_or(LsbError::ImageTooLarge {
+            width: header.width,
+            height: header.height,
+        })
+}
+
+// Undoes the per-row PNG filters, returning the rows without their filter bytes
+fn unfilter(data: &[u8], header: &Ihdr) -> Result<Vec<u8>, LsbError> {
+    let stride = row_stride(header)?;
+    let expected = filtered_size(header)?;
+    if data.len() != expected {
+        return Err(LsbError::InvalidImageLength {
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    // Filters work on whole pixels, or on bytes for sub-byte depths
+    let bpp = (header.bits_per_pixel() as usize / 8).max(1);
+    let mut rows = vec![0u8; stride * header.height as usize];
+    for (y, line) in data.chunks(stride + 1).enumerate() {
+        let (filter, line) = (line[0], &line[1..]);
+        let (previous, current) = rows.split_at_mut(y * stride);
+        let previous = if y == 0 {
+            None
+        } else {
+            Some(&previous[(y - 1) * stride..])
+        };
+        let current = &mut current[..stride];
+
+        for x in 0..stride {
+            let left = if x >= bpp { current[x - bpp] } else { 0 };
+            let up = previous.map_or(0, |row| row[x]);
+            let up_left = match previous {
+                Some(row) if x >= bpp => row[x - bpp],
+                _ => 0,
+            };
+
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(LsbError::InvalidFilter(filter)),
+            };
+            current[x] = line[x].wrapping_add(predictor);
+        }
+    }
+
+    Ok(rows)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// How many message bytes fit in the image's pixel data
+pub fn capacity(png: &Png) -> Result<usize, LsbError> {
+    Ok(Pixels::read(png)?.capacity())
+}
+
+// Hides the message in the low bit of each sample, most significant bit first.
+// The image data is refiltered and deflated again into a single IDAT.
+pub fn embed(png: &mut Png, message: &[u8]) -> Result<(), LsbError> {
+    let mut pixels = Pixels::read(png)?;
+    let capacity = pixels.capacity();
+    if message.len() > capacity {
+        return Err(LsbError::MessageTooLarge {
+            len: message.len(),
+            capacity,
+        });
+    }
+
+    let payload = (message.len() as u32).to_be_bytes();
+    let bits = payload
+        .iter()
+        .chain(message)
+        .flat_map(|byte| (0..8).rev().map(move |bit| (byte >> bit) & 1));
+    for (carrier, bit) in pixels.carriers().zip(bits) {
+        *carrier = (*carrier & !1) | bit;
+    }
+
+    pixels.write(png)
+}
+
+pub fn extract(png: &Png) -> Result<Vec<u8>, LsbError> {
+    let mut pixels = Pixels::read(png)?;
+    let capacity = pixels.capacity();
+
+    let mut bytes = pixels
+        .carriers()
+        .map(|carrier| *carrier & 1)
+        .collect::<Vec<u8>>()
+        .chunks_exact(8)
+        .map(|bits| bits.iter().fold(0, |byte, bit| (byte << 1) | bit))
+        .collect::<Vec<u8>>();
+
+    if bytes.len() < LENGTH_BYTES {
+        return Err(LsbError::NoMessage);
+    }
+    let len = u32::from_be_bytes(bytes[..LENGTH_BYTES].try_into().unwrap()) as usize;
+    if len > capacity {
+        return Err(LsbError::NoMessage);
+    }
+
+    bytes.truncate(LENGTH_BYTES + len);
+    Ok(bytes.split_off(LENGTH_BYTES))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builder::PngBuilder, chunk::Chunk, chunk_type::ChunkType};
+    use std::str::FromStr;
+
+    fn header(width: u32, height: u32, bit_depth: u8, color_type: u8) -> Ihdr {
+        Ihdr {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        }
+    }
+
+    // Forward filter used only to check unfilter against every filter type
+    fn filter(rows: &[u8], header: &Ihdr, filter: u8) -> Vec<u8> {
+        let stride = row_stride(header).unwrap();
+        let bpp = (header.bits_per_pixel() as usize / 8).max(1);
+        let mut out = Vec::new();
+        for y in 0..header.height as usize {
+            out.push(filter);
+            for x in 0..stride {
+                let at = |y: usize, x: usize| rows[y * stride + x];
+                let left = if x >= bpp { at(y, x - bpp) } else { 0 };
+                let up = if y > 0 { at(y - 1, x) } else { 0 };
+                let up_left = if x >= bpp && y > 0 {
+                    at(y - 1, x - bpp)
+                } else {
+                    0
+                };
+                let predictor = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((left as u16 + up as u16) / 2) as u8,
+                    _ => paeth(left, up, up_left),
+                };
+                out.push(at(y, x).wrapping_sub(predictor));
+            }
+        }
+        out
+    }
+
+    fn testing_png(header: Ihdr, filter_type: u8) -> Png {
+        let stride = row_stride(&header).unwrap();
+        let rows: Vec<u8> = (0..stride * header.height as usize)
+            .map(|i| (i * 37 % 256) as u8)
+            .collect();
+        let image_data = zlib::compress_stored(&filter(&rows, &header, filter_type));
+
+        PngBuilder::new()
+            .with_header(header)
+            .add_chunk(Chunk::new(ChunkType::from_str("IDAT").unwrap(), image_data))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_unfilter_all_filter_types() {
+        let header = header(5, 4, 8, 2);
+        let rows: Vec<u8> = (0..15 * 4).map(|i| (i * 91 % 256) as u8).collect();
+        for filter_type in 0..=4 {
+            let filtered = filter(&rows, &header, filter_type);
+            assert_eq!(unfilter(&filtered, &header).unwrap(), rows);
+        }
+    }
+
+    #[test]
+    fn test_unfilter_rejects_bad_input() {
+        let header = header(2, 1, 8, 0);
+        assert!(matches!(
+            unfilter(&[0, 1], &header),
+            Err(LsbError::InvalidImageLength {
+                expected: 3,
+                actual: 2
+            })
+        ));
+        assert!(matches!(
+            unfilter(&[5, 1, 2], &header),
+            Err(LsbError::InvalidFilter(5))
+        ));
+    }
+
+    #[test]
+    fn test_unfilter_rejects_huge_dimensions() {
+        let header = header(0x7fff_ffff, 0x7fff_ffff, 16, 6);
+        assert!(matches!(
+            unfilter(&[0; 16], &header),
+            Err(LsbError::ImageTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lsb_rejects_image_data_past_ihdr_size() {
+        // 2x2 RGB inflates to 14 bytes; this IDAT is tiny but inflates to 256 KiB
+        let header = header(2, 2, 8, 2);
+        let mut png = PngBuilder::new()
+            .with_header(header)
+            .add_chunk(Chunk::new(
+                ChunkType::from_str("IDAT").unwrap(),
+                zlib::compress(&[0; 1 << 18]),
+            ))
+            .build()
+            .unwrap();
+        assert!(png.image_data().len() < 2048);
+
+        assert!(matches!(
+            extract(&png),
+            Err(LsbError::ZlibError(ZlibError::OutputTooLarge(14)))
+        ));
+        assert!(matches!(
+            embed(&mut png, b"hi"),
+            Err(LsbError::ZlibError(ZlibError::OutputTooLarge(14)))
+        ));
+    }
+
+    // A smooth RGB gradient, deflated the way embed writes it
+    fn gradient_png(size: u32) -> Png {
+        let header = header(size, size, 8, 2);
+        let rows: Vec<u8> = (0..size * size)
+            .flat_map(|i| {
+                let (x, y) = (i % size, i / size);
+                [(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8]
+            })
+            .collect();
+        let image_data = zlib::compress(&filter(&rows, &header, 1));
+
+        PngBuilder::new()
+            .with_header(header)
+            .add_chunk(Chunk::new(ChunkType::from_str("IDAT").unwrap(), image_data))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_embed_keeps_image_data_compressed() {
+        let mut png = gradient_png(64);
+        let before = png.image_data().len();
+        let message = [b"lsb".as_slice(); 100].concat();
+
+        embed(&mut png, &message).unwrap();
+        let after = png.image_data().len();
+
+        // The hidden bits can't be compressed, so allow two bytes of growth per
+        // message byte; stored blocks would take over 12 KiB for this image
+        assert!(
+            after <= before + 2 * message.len(),
+            "{} -> {} bytes",
+            before,
+            after
+        );
+        assert_eq!(extract(&png).unwrap(), message);
+    }
+
+    #[test]
+    fn test_lsb_round_trip() {
+        let mut png = testing_png(header(16, 16, 8, 2), 4);
+        let original = Pixels::read(&png).unwrap().rows;
+
+        embed(&mut png, b"hidden in plain sight").unwrap();
+        let reparsed = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        assert_eq!(extract(&reparsed).unwrap(), b"hidden in plain sight");
+
+        // Only the low bit of any sample may change
+        let modified = Pixels::read(&reparsed).unwrap().rows;
+        assert!(original
+            .iter()
+            .zip(&modified)
+            .all(|(before, after)| before & !1 == after & !1));
+    }
+
+    #[test]
+    fn test_lsb_round_trip_16_bit() {
+        let mut png = testing_png(header(4, 3, 16, 6), 1);
+        assert_eq!(capacity(&png).unwrap(), 4 * 3 * 4 / 8 - LENGTH_BYTES);
+
+        embed(&mut png, b"ok").unwrap();
+        assert_eq!(extract(&png).unwrap(), b"ok");
+    }
+
+    #[test]
+    fn test_lsb_message_too_large() {
+        let mut png = testing_png(header(4, 4, 8, 0), 0);
+        assert_eq!(capacity(&png).unwrap(), 0);
+        assert!(matches!(
+            embed(&mut png, b"x"),
+            Err(LsbError::MessageTooLarge {
+                len: 1,
+                capacity: 0
+            })
+        ));
+        assert!(matches!(extract(&png), Err(LsbError::NoMessage)));
+    }
+
+    #[test]
+    fn test_lsb_rejects_palette_images() {
+        let bytes = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/download.png")).unwrap();
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+        assert!(matches!(capacity(&png), Err(LsbError::Unsupported(_))));
+    }
+}
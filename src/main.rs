@@ -1,108 +1,1210 @@
 mod args;
-mod chunk;
-mod chunk_type;
 mod commands;
-mod png;
+#[cfg(feature = "network")]
+mod http;
+mod json;
+mod manifest;
+mod mmap;
+mod pem;
+mod regex;
+mod snapshot;
+mod spec;
+mod zip;
 
-pub type Error = Box<dyn std::error::Error>;
-pub type Result<T> = std::result::Result<T, Error>;
-
-use std::path::PathBuf;
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+};
 
 use clap::{Parser, Subcommand};
+use pingu::{chunk, chunk_type, png, Result};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Pingu {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Create missing parent directories of output files
+    #[arg(long, global = true)]
+    create_dirs: bool,
+    /// Show sizes as exact byte counts instead of KiB/MiB/GiB
+    #[arg(long, global = true)]
+    bytes: bool,
+    /// Wrap written PNGs in gzip. The data is stored, not compressed again,
+    /// since PNG image data is already deflated
+    #[arg(long, global = true)]
+    gzip_output: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     Encode {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[command(flatten)]
+        message: args::MessageArgs,
+        /// Store the --message-file name with the payload for decode --output-dir
+        #[arg(long, conflicts_with = "message")]
+        preserve_name: bool,
+        /// Rewrite line endings in the message before embedding it
+        #[arg(long, value_enum, default_value_t)]
+        normalize_newlines: args::Newlines,
+        #[command(flatten)]
+        target: args::EncodeTargetArgs,
+        /// Write the PNG here, or - for stdout
         #[arg(short, long)]
-        png: PathBuf,
+        output: Option<PathBuf>,
+        /// Show the resulting chunk layout without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Give the output the modification time of the input file
+        #[arg(long, requires = "output")]
+        preserve_mtime: bool,
+        /// Summarize what the encode changed: chunks and bytes added
+        #[arg(long)]
+        show_changes: bool,
+        /// Add the chunk even if the PNG already has one of the same type
+        #[arg(long, conflicts_with_all = ["replace", "lsb"])]
+        allow_duplicate: bool,
+        /// Overwrite an existing chunk of the same type instead of adding one
+        #[arg(long, conflicts_with = "lsb")]
+        replace: bool,
+        /// Report the message and chunk size before encoding
         #[arg(short, long)]
-        message: String,
+        verbose: bool,
+        /// Encode large messages without asking for confirmation
         #[arg(short, long)]
-        chunk_type: chunk_type::ChunkType,
+        yes: bool,
+        /// Add or update a tIME chunk with the current UTC time
+        #[arg(long)]
+        stamp_time: bool,
+        /// Allow a critical chunk type such as IHDR or IDAT to carry the message
+        #[arg(long, conflicts_with = "lsb")]
+        force: bool,
+        /// Add the message to the end of an existing chunk of the same type,
+        /// on a new line, instead of adding another chunk
+        #[arg(long, conflicts_with_all = ["replace", "allow_duplicate", "lsb"])]
+        append_to_existing: bool,
+        /// Leave the PNG unchanged if a chunk with this exact message exists
+        #[arg(long, conflicts_with = "lsb")]
+        if_absent: bool,
+        /// Byte order of chunk CRCs in the output; le is non-standard
+        #[arg(long, value_enum, default_value_t)]
+        crc_endian: args::CrcEndianArg,
+        /// Re-parse the result and check the message survives before writing
+        #[arg(long)]
+        verify: bool,
+        /// Record the embedded chunk in this JSON sidecar file
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+        /// Description to store with the manifest entry
+        #[arg(long, requires = "manifest", default_value = "")]
+        description: String,
+        /// XOR the message with this repeating key; obfuscation, not encryption
+        #[arg(long, value_parser = clap::builder::NonEmptyStringValueParser::new())]
+        xor_key: Option<String>,
+        /// Add Reed-Solomon parity so decode --ecc can repair damaged bytes;
+        /// every 255-byte block can lose up to PARITY / 2 of them
+        #[arg(
+            long,
+            value_name = "PARITY",
+            num_args = 0..=1,
+            default_missing_value = "32",
+            conflicts_with = "append_to_existing"
+        )]
+        ecc: Option<usize>,
+    },
+    Decode {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[command(flatten)]
+        target: args::DecodeTargetArgs,
+        #[arg(short, long, value_enum, default_value_t)]
+        format: args::DecodeFormat,
+        /// Replace invalid UTF-8 with U+FFFD instead of failing
+        #[arg(long)]
+        lossy: bool,
+        /// Parse the message as JSON and print it indented
+        #[arg(long, conflicts_with_all = ["format", "output", "output_dir"])]
+        pretty_json: bool,
+        /// Print at most this many bytes of the message; 0 prints everything
+        #[arg(long, default_value_t = 64 * 1024)]
+        max_output: usize,
+        /// Read chunks one at a time and stop at the first match
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "type_prefix", "lsb", "mmap", "input_format", "no_signature", "any_signature"
+            ]
+        )]
+        streaming: bool,
+        /// Read buffer capacity in bytes for --streaming, from a file or stdin
+        #[arg(long, default_value_t = args::DEFAULT_BUFFER_SIZE, requires = "streaming")]
+        buffer_size: usize,
+        /// Decode every chunk of this type instead of just the first
+        #[arg(long, conflicts_with_all = ["type_prefix", "lsb", "streaming"])]
+        all: bool,
+        /// Separator between messages with --all; understands \n, \t, \0 and \\
+        #[arg(long, default_value = "\\n")]
+        delimiter: String,
+        /// Write the raw chunk data to a file instead of printing it
         #[arg(short, long)]
         output: Option<PathBuf>,
+        /// Write the payload into this directory under the name stored by
+        /// encode --preserve-name
+        #[arg(long, conflicts_with = "output")]
+        output_dir: Option<PathBuf>,
+        /// Undo encode --xor-key with the same key
+        #[arg(
+            long,
+            value_parser = clap::builder::NonEmptyStringValueParser::new(),
+            conflicts_with_all = ["type_prefix", "all"]
+        )]
+        xor_key: Option<String>,
+        /// Repair the message with the parity added by encode --ecc. Chunk
+        /// CRCs are not checked, since damage would make them fail
+        #[arg(long, conflicts_with_all = ["type_prefix", "all", "streaming"])]
+        ecc: bool,
+        /// Print the chunk type, length and CRC before the message
+        #[arg(long, conflicts_with_all = ["type_prefix", "lsb", "all"])]
+        with_meta: bool,
     },
-    Decode {
+    Remove {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[command(flatten)]
+        target: args::RemoveTargetArgs,
+        /// Where to write the result; defaults to rewriting --png in place
         #[arg(short, long)]
-        png: PathBuf,
+        output: Option<PathBuf>,
+        /// Keep an empty chunk of the same type in place of the removed one
+        #[arg(long)]
+        tombstone: bool,
+        /// Remove every chunk of this type rather than just the first
+        #[arg(long, conflicts_with = "tombstone")]
+        all: bool,
+        /// Print only the number of chunks removed
+        #[arg(long)]
+        count: bool,
+        /// Report what would be removed without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Keep the modification time of the input file
+        #[arg(long)]
+        preserve_mtime: bool,
+        /// Write the removed data to stdout instead of listing the chunks
+        #[arg(long, conflicts_with = "count")]
+        print_data: bool,
+        /// Write the removed data to this file
+        #[arg(long, conflicts_with = "print_data")]
+        data_output: Option<PathBuf>,
+    },
+    Print {
+        #[command(flatten)]
+        input: args::MultiInputArgs,
+        #[arg(long)]
+        pngcheck: bool,
+        /// List each chunk with a description of its type
+        #[arg(short, long, conflicts_with = "pngcheck")]
+        verbose: bool,
+        /// With --verbose, also show the first N bytes of each chunk's data
+        #[arg(long, value_name = "N", requires = "verbose")]
+        preview: Option<usize>,
+        #[arg(long)]
+        show_trailing: bool,
+        /// Only print chunks of this type; may be given more than once
+        #[arg(short, long = "chunk-type", conflicts_with_all = ["pngcheck", "verbose"])]
+        chunk_types: Vec<chunk_type::ChunkType>,
+        /// Print one JSON object per chunk as it is read, without loading the
+        /// whole file
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "pngcheck", "verbose", "chunk_types", "show_trailing",
+                "mmap", "input_format", "no_signature", "any_signature", "skip_leading"
+            ]
+        )]
+        jsonl: bool,
+    },
+    ExtractAll {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[command(flatten)]
+        chunk_type: args::ChunkTypeArgs,
+        /// Zip archive to write, one entry per matching chunk
         #[arg(short, long)]
-        chunk_type: chunk_type::ChunkType,
+        output: PathBuf,
+        /// How to name the entries; with hash, repeated chunks are stored once
+        #[arg(long, value_enum, default_value_t)]
+        name_by: args::ChunkNaming,
     },
-    Remove {
+    /// Exit non-zero with a diff unless a chunk holds the expected message
+    Assert {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[command(flatten)]
+        chunk_type: args::ChunkTypeArgs,
+        #[command(flatten)]
+        expected: args::ExpectedArgs,
+    },
+    /// Print the PNG as JSON, with chunk and trailing data as hex
+    ExportJson {
+        #[command(flatten)]
+        input: args::InputArgs,
+    },
+    /// Rebuild a PNG from the JSON written by export-json
+    ImportJson {
         #[arg(short, long)]
-        png: PathBuf,
+        json: PathBuf,
         #[arg(short, long)]
-        chunk_type: chunk_type::ChunkType,
+        output: PathBuf,
     },
-    Print {
+    /// Write the image data stream from all IDAT chunks to a file
+    Idat {
+        #[command(flatten)]
+        input: args::InputArgs,
         #[arg(short, long)]
-        png: PathBuf,
+        output: PathBuf,
+        /// Inflate the zlib stream to the filtered scanlines, giving up past
+        /// --max-input-size bytes
+        #[arg(long)]
+        decompress: bool,
+    },
+    /// List chunks whose types aren't defined by the PNG spec
+    Hidden {
+        #[command(flatten)]
+        input: args::InputArgs,
     },
+    Validate {
+        #[command(flatten)]
+        input: args::InputArgs,
+    },
+    /// Check which chunks recorded in a manifest are still in the PNG
+    Manifest {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[arg(short, long)]
+        manifest: PathBuf,
+        /// Only list entries recorded on or after this date or UTC timestamp
+        #[arg(long, value_parser = manifest::parse_since)]
+        since: Option<String>,
+    },
+    /// Measure parse and serialize throughput
+    #[command(hide = true)]
+    Bench {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+    },
+    Canonicalize {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Also remove tIME chunks, so the same content always gives the
+        /// same bytes
+        #[arg(long)]
+        deterministic: bool,
+        /// With --deterministic, also remove text chunks keyed "Creation
+        /// Time", "date:create", "date:modify" or "date:timestamp"
+        #[arg(long, requires = "deterministic")]
+        strip_dated_text: bool,
+    },
+    /// Move chunks into the given type order; unlisted chunks keep their
+    /// relative order after the listed ones
+    Reorder {
+        #[command(flatten)]
+        input: args::InputArgs,
+        /// Comma-separated chunk types, e.g. IHDR,gAMA,ruSt,IDAT,IEND
+        #[arg(long, value_delimiter = ',', required = true)]
+        order: Vec<String>,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Find the chunks whose data contains a string or pattern
+    Grep {
+        #[command(flatten)]
+        input: args::InputArgs,
+        /// Text to look for, or with --regex a pattern using . [] * + ? ^ $ \d \w \s
+        #[arg(long, value_parser = clap::builder::NonEmptyStringValueParser::new())]
+        pattern: String,
+        #[arg(long)]
+        regex: bool,
+        /// Bytes of context to show on each side of a match
+        #[arg(long, default_value_t = 16)]
+        context: usize,
+        /// Print only the number of chunks with a match
+        #[arg(long, conflicts_with = "context")]
+        count: bool,
+    },
+    /// Recompute every chunk's CRC from its type and data
+    FixCrc {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Extract whatever was appended after the IEND chunk
+    Carve {
+        #[command(flatten)]
+        input: args::InputArgs,
+        /// Where to write the carved bytes, or - for stdout
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Rebuild a PNG whose signature is damaged, starting from its IHDR chunk
+    Recover {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Add every chunk listed in a JSON spec file, in order
+    Apply {
+        #[command(flatten)]
+        input: args::InputArgs,
+        /// JSON array of {"type", "message"} objects
+        #[arg(long)]
+        spec: PathBuf,
+        /// Where to write the result; defaults to rewriting --png in place
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print a hash of the whole file, to check it is unchanged later
+    Hash {
+        #[command(flatten)]
+        input: args::InputArgs,
+        #[arg(long, value_enum, default_value_t)]
+        algo: args::HashAlgo,
+    },
+    /// Show the EXIF tags stored in the eXIf chunk
+    Exif {
+        #[command(flatten)]
+        input: args::InputArgs,
+    },
+    /// Sign the file with an Ed25519 key and store the signature in a siGN chunk
+    ///
+    /// The Ed25519 code is pingu's own and has not been audited, so don't rely
+    /// on it where a forged or leaked signing key would matter.
+    Sign {
+        #[command(flatten)]
+        input: args::InputArgs,
+        /// PEM private key, as written by openssl genpkey -algorithm ed25519
+        #[arg(long)]
+        key: PathBuf,
+        /// Where to write the signed PNG; defaults to rewriting --png in place
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Check the siGN chunk written by sign against an Ed25519 public key
+    VerifySig {
+        #[command(flatten)]
+        input: args::InputArgs,
+        /// PEM public key, as written by openssl pkey -pubout
+        #[arg(long)]
+        key: PathBuf,
+    },
+    /// Explain a chunk type and check it is valid and not a standard type
+    CheckType { chunk_type: String },
+}
+
+fn main() {
+    #[cfg(unix)]
+    reset_sigpipe();
+
+    // Report errors with their Display message rather than the Debug form
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+// Rust starts with SIGPIPE ignored, so printing into a pipe whose reader has
+// gone (pingu ... | head) panics with "Broken pipe". Restoring the default
+// action ends the process quietly instead, like other command line tools.
+#[cfg(unix)]
+fn reset_sigpipe() {
+    use std::os::raw::c_int;
+
+    const SIGPIPE: c_int = 13;
+    const SIG_DFL: usize = 0;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: usize) -> usize;
+    }
+
+    unsafe {
+        signal(SIGPIPE, SIG_DFL);
+    }
 }
 
 #[allow(unused_variables, dead_code)]
-fn main() -> Result<()> {
+fn run() -> Result<()> {
     let cli = Pingu::parse();
 
     match cli.command {
         Some(Commands::Encode {
-            png,
+            input,
             message,
-            chunk_type,
+            preserve_name,
+            normalize_newlines,
+            target,
             output,
+            dry_run,
+            preserve_mtime,
+            show_changes,
+            allow_duplicate,
+            replace,
+            force,
+            stamp_time,
+            verbose,
+            yes,
+            append_to_existing,
+            if_absent,
+            crc_endian,
+            verify,
+            manifest,
+            description,
+            xor_key,
+            ecc,
         }) => {
             //read the png file into byte slice
-            let png_data = std::fs::read(&png)?;
+            let png_data = input.read()?;
 
-            let chunk = chunk::Chunk::new(chunk_type, message.as_bytes().to_vec());
-            let mut png = png::Png::try_from(png_data.as_slice())?;
-            png.append_chunk(chunk);
+            let message = match message.file_name() {
+                Some(name) if preserve_name => {
+                    commands::wrap_named_payload(&name, &message.read()?)
+                }
+                _ => commands::normalize_newlines(&message.read()?, normalize_newlines),
+            };
+            let message = match &xor_key {
+                Some(key) => commands::xor_mask(&message, key.as_bytes()),
+                None => message,
+            };
+            let message = match ecc {
+                Some(parity) => pingu::ecc::encode(&message, parity)?,
+                None => message,
+            };
+            let mut png = input.parse(&png_data)?;
+            let chunks_before = png.chunk_count();
+            let index = if target.lsb() {
+                pingu::lsb::embed(&mut png, &message)?;
+                None
+            } else {
+                let chunk_type = target
+                    .chunk_type()
+                    .expect("clap requires a chunk type without --lsb");
+                commands::check_reserved(&chunk_type.to_string(), force)?;
+                let chunk = chunk::Chunk::new(chunk_type, message.clone());
+                let warning =
+                    commands::size_warning(&chunk, commands::LARGE_MESSAGE_BYTES, cli.bytes);
+                if verbose || warning.is_some() {
+                    eprintln!("{}", commands::message_size_report(&chunk));
+                }
+                if let Some(warning) = warning {
+                    eprintln!("{}", warning);
+                    if !yes && !commands::confirm("Encode anyway?")? {
+                        return Err("Not encoding; pass --yes to skip the prompt".into());
+                    }
+                }
+                if if_absent && png.iter_chunks().any(|ch| *ch == chunk) {
+                    eprintln!("{} chunk with this message already present", chunk_type);
+                    None
+                } else if replace {
+                    Some(png.replace_chunk(chunk))
+                } else if append_to_existing {
+                    Some(png.append_to_chunk(chunk, b"\n"))
+                } else {
+                    commands::check_duplicate(&png, &chunk_type.to_string(), allow_duplicate)?;
+                    Some(pingu::api::add_message(&mut png, chunk_type, &message))
+                }
+            };
 
-            let png_bytes = png.as_bytes();
-            if let Some(output) = output {
-                std::fs::write(&output, &png_bytes)?;
+            if stamp_time {
+                png.replace_chunk(pingu::time::Time::now().to_chunk());
+            }
+
+            if dry_run {
+                print!("{}", commands::chunk_layout(&png, index, cli.bytes));
+                return Ok(());
+            }
+
+            let png_bytes = png.as_bytes_with_crc_endian(crc_endian.into());
+            if verify {
+                let chunk_type = target.chunk_type().map(|t| t.to_string());
+                // An appended message shares its chunk with what was there before
+                let expected = match index.and_then(|i| png.iter_chunks().nth(i)) {
+                    Some(chunk) if append_to_existing => chunk.data(),
+                    _ => &message[..],
+                };
+                // Checked before --crc-endian swaps CRC bytes, which the parser
+                // would otherwise reject as corrupt
+                commands::verify_encoded(&png.as_bytes(), chunk_type.as_deref(), expected)?;
+            }
+
+            if let Some(output) = &output {
+                let mtime = commands::input_mtime(&input.png, preserve_mtime)?;
+                let bytes = commands::png_output(&png_bytes, cli.gzip_output);
+                commands::write_output(output, &bytes, cli.create_dirs)?;
+                commands::restore_mtime(output, mtime)?;
             } else {
                 println!("{}", png);
             }
+
+            if show_changes {
+                let before = (chunks_before, png_data.len());
+                let summary = commands::change_summary(before, &png, png_bytes.len(), index);
+                if output.as_deref().is_some_and(args::is_stdio) {
+                    eprint!("{}", summary);
+                } else {
+                    print!("{}", summary);
+                }
+            }
+
+            if let Some(path) = manifest {
+                let chunk_type = target
+                    .chunk_type()
+                    .map_or("LSB".to_string(), |t| t.to_string());
+                let mut manifest = manifest::Manifest::load(&path)?;
+                manifest.push(&chunk_type, &description);
+                manifest.save(&path)?;
+            }
+            Ok(())
+        }
+        Some(Commands::Decode {
+            input,
+            target,
+            format,
+            lossy,
+            pretty_json,
+            max_output,
+            streaming,
+            buffer_size,
+            all,
+            delimiter,
+            output,
+            output_dir,
+            xor_key,
+            ecc,
+            with_meta,
+        }) => {
+            let (label, data, meta) = if streaming {
+                let chunk_type = target
+                    .chunk_type()
+                    .expect("clap requires a chunk type with --streaming")
+                    .to_string();
+                match png::Png::find_chunk_streaming(input.reader(buffer_size)?, &chunk_type)? {
+                    Some(chunk) => {
+                        let meta = (chunk.length(), chunk.crc());
+                        (chunk_type, chunk.data().to_vec(), Some(meta))
+                    }
+                    None => {
+                        println!("Chunk not found");
+                        return Ok(());
+                    }
+                }
+            } else {
+                let png_data = input.read()?;
+                let png = if ecc {
+                    let skip = args::leading_bytes(&png_data, input.skip_leading);
+                    png::Png::try_from_fixing_crcs(&png_data[skip..])?.0
+                } else {
+                    input.parse(&png_data)?
+                };
+
+                if target.lsb() {
+                    ("LSB".to_string(), pingu::lsb::extract(&png)?, None)
+                } else {
+                    let (label, chunks) = match target.type_prefix() {
+                        Some(prefix) => {
+                            (format!("{}**", prefix), png.chunks_by_type_prefix(prefix))
+                        }
+                        None => {
+                            let chunk_type = target.chunk_type().expect(
+                                "clap requires a chunk type without --type-prefix or --lsb",
+                            );
+                            let label = chunk_type.to_string();
+                            let chunks = if all {
+                                png.iter_chunks().filter(|ch| ch.is_type(&label)).collect()
+                            } else {
+                                pingu::api::message_chunk(&png, &chunk_type).into_iter().collect()
+                            };
+                            (label, chunks)
+                        }
+                    };
+
+                    if chunks.is_empty() {
+                        println!("Chunk not found");
+                        return Ok(());
+                    }
+
+                    let data: Vec<u8> = if all {
+                        commands::join_messages(&chunks, &delimiter)
+                    } else {
+                        chunks.iter().flat_map(|ch| ch.data()).copied().collect()
+                    };
+                    let meta = match chunks[..] {
+                        [chunk] => Some((chunk.length(), chunk.crc())),
+                        _ => None,
+                    };
+                    (label, data, meta)
+                }
+            };
+            let data = if ecc { pingu::ecc::decode(&data)? } else { data };
+            let data = match &xor_key {
+                Some(key) => commands::xor_mask(&data, key.as_bytes()),
+                None => data,
+            };
+
+            if let Some(output) = output {
+                commands::write_output(&output, &data, cli.create_dirs)?;
+                return Ok(());
+            }
+
+            if let Some(dir) = output_dir {
+                let (name, payload) = match commands::unwrap_named_payload(&data) {
+                    Some((name, payload)) => (name.to_string(), payload),
+                    None => {
+                        let name = format!("{}.bin", label.replace('*', "_"));
+                        eprintln!("warning: no stored file name, writing {}", name);
+                        (name, &data[..])
+                    }
+                };
+                let path = dir.join(name);
+                commands::write_output(&path, payload, cli.create_dirs)?;
+                println!("{}", path.display());
+                return Ok(());
+            }
+
+            // Cutting JSON short would only make it fail to parse
+            let shown = if pretty_json {
+                &data[..]
+            } else {
+                commands::truncate_output(&data, max_output)
+            };
+            let meta = meta.filter(|_| with_meta);
+            if let (Some(meta), false) = (meta, format == args::DecodeFormat::Json) {
+                print!("{}", commands::chunk_meta(&label, meta));
+            }
+            match format {
+                args::DecodeFormat::Text if pretty_json => {
+                    let message = commands::decode_text(&label, shown, lossy)?;
+                    println!("{}", commands::pretty_json(&label, &message)?)
+                }
+                args::DecodeFormat::Text => {
+                    println!("{}", commands::decode_text(&label, shown, lossy)?)
+                }
+                args::DecodeFormat::Hex => println!("{}", commands::to_hex(shown)),
+                args::DecodeFormat::Json => {
+                    let message = commands::decode_text(&label, shown, lossy)?;
+                    println!("{}", commands::decoded_json(&label, &message, meta));
+                }
+            }
+            if shown.len() < data.len() {
+                eprintln!(
+                    "note: showing {} of {} bytes; use --output <FILE> or --max-output 0 \
+                     for all of it",
+                    shown.len(),
+                    data.len()
+                );
+            }
+
+            Ok(())
+        }
+        Some(Commands::Remove {
+            input,
+            target,
+            output,
+            tombstone,
+            all,
+            count,
+            dry_run,
+            preserve_mtime,
+            print_data,
+            data_output,
+        }) => {
+            if print_data && !dry_run && args::is_stdio(output.as_ref().unwrap_or(&input.png)) {
+                let message = "--print-data and the PNG cannot both go to stdout; \
+                               pass --output <FILE> or --dry-run";
+                return Err(message.into());
+            }
+            let png_data = input.read()?;
+            let mut png = input.parse(&png_data)?;
+
+            let chunk_type = if target.auto() {
+                commands::auto_removal_type(&png).ok_or("No hidden chunk to remove")?
+            } else {
+                target
+                    .chunk_type()
+                    .expect("clap requires a chunk type without --auto")
+            };
+            let removed = if all {
+                png.remove_chunks(&chunk_type.to_string())
+            } else if tombstone {
+                vec![png.tombstone_chunk(&chunk_type.to_string())?]
+            } else {
+                vec![pingu::api::take_chunk(&mut png, &chunk_type)?]
+            };
+
+            if removed.is_empty() && !count {
+                return Err("Cannot find the chunk".into());
+            }
+
+            if let Some(path) = &data_output {
+                commands::write_output(path, &commands::removed_data(&removed), cli.create_dirs)?;
+            }
+
+            if !dry_run {
+                // Release any mapping of the file before overwriting it
+                drop(png_data);
+                let mtime = commands::input_mtime(&input.png, preserve_mtime)?;
+                let output = output.unwrap_or(input.png);
+                commands::write_output(
+                    &output,
+                    &commands::png_output(&png.as_bytes(), cli.gzip_output),
+                    cli.create_dirs,
+                )?;
+                commands::restore_mtime(&output, mtime)?;
+
+                // Keep the report out of the PNG bytes when piping them
+                if args::is_stdio(&output) {
+                    eprint!("{}", commands::removal_report(&removed, count));
+                    return Ok(());
+                }
+            }
+
+            if print_data {
+                let data = commands::removed_data(&removed);
+                commands::write_output(Path::new("-"), &data, cli.create_dirs)?;
+            } else {
+                print!("{}", commands::removal_report(&removed, count));
+            }
+
+            Ok(())
+        }
+        Some(Commands::Print {
+            input,
+            pngcheck,
+            verbose,
+            preview,
+            show_trailing,
+            chunk_types,
+            jsonl,
+        }) => {
+            let print_one = |input: &args::InputArgs| -> Result<()> {
+                if jsonl {
+                    let mut stdout = std::io::stdout().lock();
+                    let mut index = 0;
+                    let mut reader = input.reader(args::DEFAULT_BUFFER_SIZE)?;
+                    png::Png::for_each_chunk(&mut reader, |offset, chunk| {
+                        let line = commands::chunk_json_line(index, offset, &chunk);
+                        index += 1;
+                        stdout.write_all(line.as_bytes())
+                    })?;
+                    return Ok(());
+                }
+
+                let png_data = input.read()?;
+                let png = input.parse(&png_data)?;
+
+                if pngcheck {
+                    let name = input.png.display().to_string();
+                    print!("{}", commands::pngcheck_report(&name, &png, png_data.len()));
+                } else if verbose {
+                    print!("{}", commands::chunk_descriptions(&png, cli.bytes, preview));
+                } else if !chunk_types.is_empty() {
+                    println!("{}", commands::filter_chunks(&png, &chunk_types));
+                } else {
+                    println!("{}", png);
+                }
+
+                if show_trailing {
+                    print!("{}", commands::trailing_report(&png, cli.bytes));
+                }
+
+                Ok(())
+            };
+
+            let mut inputs = input.inputs();
+            if input.png.len() == 1 {
+                return print_one(&inputs.next().expect("clap requires --png"));
+            }
+
+            // With several files, keep going past failures and summarize at the end
+            let mut printed = 0;
+            for (i, input) in inputs.enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("==> {} <==", input.png.display());
+                match print_one(&input) {
+                    Ok(()) => printed += 1,
+                    Err(e) => eprintln!("{}: {}", input.png.display(), e),
+                }
+            }
+
+            let total = input.png.len();
+            println!("\n{} of {} files printed", printed, total);
+            if printed < total {
+                return Err(format!("{} files failed", total - printed).into());
+            }
             Ok(())
         }
-        Some(Commands::Decode { png, chunk_type }) => {
-            let png_data = std::fs::read(&png)?;
-            let png = png::Png::try_from(png_data.as_slice())?;
+        Some(Commands::ExtractAll {
+            input,
+            chunk_type,
+            output,
+            name_by,
+        }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
 
-            let chunk = png.chunk_by_type(chunk_type.to_string().as_str());
-            if let Some(chunk) = chunk {
-                let message = chunk.data_as_string()?;
-                println!("{}", message);
+            let chunk_type = chunk_type.chunk_type().to_string();
+            let (count, archive) = commands::extract_all_zip(&png, &chunk_type, name_by)?;
+            if count == 0 {
+                println!("Chunk not found");
+                return Ok(());
+            }
+
+            commands::write_output(&output, &archive, cli.create_dirs)?;
+            if args::is_stdio(&output) {
+                eprintln!("Extracted {} chunks", count);
             } else {
+                println!("Extracted {} chunks to {}", count, output.display());
+            }
+
+            Ok(())
+        }
+        Some(Commands::Assert {
+            input,
+            chunk_type,
+            expected,
+        }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
+
+            let chunk_type = chunk_type.chunk_type().to_string();
+            let chunk = png
+                .chunk_by_type(&chunk_type)
+                .ok_or_else(|| format!("Chunk {} not found", chunk_type))?;
+            commands::assert_message(&chunk_type, chunk.data(), &expected.read()?)?;
+
+            Ok(())
+        }
+        Some(Commands::ExportJson { input }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
+
+            println!("{}", snapshot::to_json(&png).to_pretty());
+
+            Ok(())
+        }
+        Some(Commands::ImportJson { json, output }) => {
+            let png = snapshot::from_json(&std::fs::read_to_string(&json)?)?;
+            commands::write_output(
+                &output,
+                &commands::png_output(&png.as_bytes(), cli.gzip_output),
+                cli.create_dirs,
+            )?;
+
+            Ok(())
+        }
+        Some(Commands::Idat {
+            input,
+            output,
+            decompress,
+        }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
+
+            if png.chunk_by_type("IDAT").is_none() {
                 println!("Chunk not found");
+                return Ok(());
             }
 
+            let limit = usize::try_from(input.max_input_size).unwrap_or(usize::MAX);
+            let data = commands::idat_stream(&png, decompress, limit)?;
+            commands::write_output(&output, &data, cli.create_dirs)?;
+
             Ok(())
         }
-        Some(Commands::Remove { png, chunk_type }) => {
-            let png_data = std::fs::read(&png)?;
-            let mut png = png::Png::try_from(png_data.as_slice())?;
+        Some(Commands::Hidden { input }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
 
-            let removed_chunk = png.remove_chunk(chunk_type.to_string().as_str())?;
+            print!("{}", commands::hidden_report(&png, cli.bytes));
 
-            println!("{}", removed_chunk);
+            Ok(())
+        }
+        Some(Commands::Validate { input }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data).map_err(|e| {
+                let skip = args::leading_bytes(&png_data, input.skip_leading);
+                match pingu::validate::byte_order_hint(&png_data[skip..], &e) {
+                    Some(hint) => format!("{}\n{}", e, hint).into(),
+                    None => pingu::Error::from(e),
+                }
+            })?;
+
+            let problems = pingu::validate::validate(&png);
+            if problems.is_empty() {
+                println!("{}: OK", input.png.display());
+                return Ok(());
+            }
+
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            Err(format!("{} problems found", problems.len()).into())
+        }
+        Some(Commands::Manifest {
+            input,
+            manifest,
+            since,
+        }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
+
+            let manifest = manifest::Manifest::load(&manifest)?;
+            print!("{}", manifest.cross_reference(&png, since.as_deref()));
+
+            Ok(())
+        }
+        Some(Commands::Bench { input, iterations }) => {
+            let png_data = input.read()?;
+
+            let stats = commands::bench(&png_data, iterations, |bytes| input.parse(bytes))?;
+            print!("{}", commands::bench_report(&stats));
+
+            Ok(())
+        }
+        Some(Commands::Canonicalize {
+            input,
+            output,
+            deterministic,
+            strip_dated_text,
+        }) => {
+            let png_data = input.read()?;
+            let mut png = input.parse(&png_data)?;
+
+            if deterministic {
+                for chunk in png.strip_volatile(strip_dated_text) {
+                    eprintln!("removed {} chunk", chunk.chunk_type());
+                }
+            }
+
+            for problem in png.canonicalize() {
+                eprintln!("warning: {}", problem);
+            }
+
+            commands::write_output(
+                &output,
+                &commands::png_output(&png.as_bytes(), cli.gzip_output),
+                cli.create_dirs,
+            )?;
 
             Ok(())
         }
-        Some(Commands::Print { png }) => {
-            let png_data = std::fs::read(&png)?;
-            let png = png::Png::try_from(png_data.as_slice())?;
+        Some(Commands::Reorder {
+            input,
+            order,
+            output,
+        }) => {
+            let png_data = input.read()?;
+            let mut png = input.parse(&png_data)?;
+
+            let order: Vec<&str> = order.iter().map(|t| t.trim()).collect();
+            png.reorder(&order)?;
 
-            println!("{}", png);
+            commands::write_output(
+                &output,
+                &commands::png_output(&png.as_bytes(), cli.gzip_output),
+                cli.create_dirs,
+            )?;
+
+            Ok(())
+        }
+        Some(Commands::Grep {
+            input,
+            pattern,
+            regex,
+            context,
+            count: count_only,
+        }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
+
+            let regex = regex.then(|| regex::Regex::new(&pattern)).transpose()?;
+            let find = |data: &[u8]| match &regex {
+                Some(regex) => regex.find_all(data),
+                None => commands::find_bytes(data, pattern.as_bytes()),
+            };
+            let count = if count_only {
+                let count = commands::grep_count(&png, find);
+                println!("{}", count);
+                count
+            } else {
+                let (report, count) = commands::grep_report(&png, find, context);
+                print!("{}", report);
+                count
+            };
+
+            if count == 0 {
+                return Err(format!("No chunk contains {:?}", pattern).into());
+            }
+            Ok(())
+        }
+        Some(Commands::FixCrc { input, output }) => {
+            let png_data = input.read()?;
+            let (png, fixed) = png::Png::try_from_fixing_crcs(&png_data)?;
+
+            commands::write_output(
+                &output,
+                &commands::png_output(&png.as_bytes(), cli.gzip_output),
+                cli.create_dirs,
+            )?;
+            let report = commands::crc_fix_report(&png, &fixed);
+            if args::is_stdio(&output) {
+                eprint!("{}", report);
+            } else {
+                print!("{}", report);
+            }
+
+            Ok(())
+        }
+        Some(Commands::Carve { input, output }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
+
+            let trailing = png.trailing_bytes();
+            if trailing.is_empty() {
+                return Err("Nothing follows the IEND chunk".into());
+            }
+            commands::write_output(&output, trailing, cli.create_dirs)?;
+            let report = format!(
+                "Carved {} bytes ({}) to {}",
+                trailing.len(),
+                commands::guess_file_type(trailing),
+                output.display()
+            );
+            if args::is_stdio(&output) {
+                eprintln!("{}", report);
+            } else {
+                println!("{}", report);
+            }
+
+            Ok(())
+        }
+        Some(Commands::Recover { input, output }) => {
+            let png_data = input.read()?;
+            let (offset, png) = png::Png::recover(&png_data)?;
+
+            if offset != 8 {
+                eprintln!("IHDR found at offset {}; expected 8", offset);
+            }
+            commands::write_output(
+                &output,
+                &commands::png_output(&png.as_bytes(), cli.gzip_output),
+                cli.create_dirs,
+            )?;
+            if !args::is_stdio(&output) {
+                println!("Recovered {} chunks into {}", png.chunk_count(), output.display());
+            }
+
+            Ok(())
+        }
+        Some(Commands::Apply {
+            input,
+            spec,
+            output,
+        }) => {
+            let chunks = spec::parse(&std::fs::read_to_string(&spec)?)?;
+            let png_data = input.read()?;
+            let mut png = input.parse(&png_data)?;
+
+            let count = chunks.len();
+            for chunk in chunks {
+                png.insert_chunk_before_iend(chunk);
+            }
+
+            // Release any mapping of the file before overwriting it
+            drop(png_data);
+            let output = output.unwrap_or(input.png);
+            commands::write_output(
+                &output,
+                &commands::png_output(&png.as_bytes(), cli.gzip_output),
+                cli.create_dirs,
+            )?;
+            if args::is_stdio(&output) {
+                eprintln!("Added {} chunks", count);
+            } else {
+                println!("Added {} chunks to {}", count, output.display());
+            }
+
+            Ok(())
+        }
+        Some(Commands::Hash { input, algo }) => {
+            let png_data = input.read()?;
+
+            println!(
+                "{}  {}",
+                commands::file_hash(&png_data, algo),
+                input.png.display()
+            );
+
+            Ok(())
+        }
+        Some(Commands::Sign { input, key, output }) => {
+            let png_data = input.read()?;
+            let mut png = input.parse(&png_data)?;
+            let secret = pem::private_key(&std::fs::read_to_string(&key)?)?;
+
+            commands::sign_png(&mut png, &secret);
+            let output = output.unwrap_or(input.png);
+            commands::write_output(
+                &output,
+                &commands::png_output(&png.as_bytes(), cli.gzip_output),
+                cli.create_dirs,
+            )?;
+            if !args::is_stdio(&output) {
+                println!("Signed {}", output.display());
+            }
+
+            Ok(())
+        }
+        Some(Commands::VerifySig { input, key }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
+            let public = pem::public_key(&std::fs::read_to_string(&key)?)?;
+
+            commands::verify_png(&png, &public)?;
+            println!("{}: signature OK", input.png.display());
+
+            Ok(())
+        }
+        Some(Commands::Exif { input }) => {
+            let png_data = input.read()?;
+            let png = input.parse(&png_data)?;
+
+            let Some(chunk) = png.chunk_by_type("eXIf") else {
+                println!("No eXIf chunk found");
+                return Ok(());
+            };
+            let exif = pingu::exif::Exif::try_from(chunk.data())?;
+            for tag in exif.tags() {
+                println!("{}", tag);
+            }
+
+            Ok(())
+        }
+        Some(Commands::CheckType { chunk_type }) => {
+            let chunk_type: chunk_type::ChunkType = chunk_type.parse()?;
+
+            print!("{}", commands::check_type_report(&chunk_type));
+            if !chunk_type.is_valid() {
+                return Err(format!("{} is not a valid chunk type", chunk_type).into());
+            }
 
             Ok(())
         }
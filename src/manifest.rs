@@ -0,0 +1,273 @@
+use std::{io::ErrorKind, path::Path, time::SystemTime};
+
+use pingu::{png::Png, time::Time};
+use thiserror::Error;
+
+use crate::json::{self, JsonError, Value};
+
+#[derive(Debug, Error)]
+pub enum ManifestError {
+    #[error("Cannot read manifest: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] JsonError),
+    #[error("Invalid manifest entry {0}: expected chunk_type, timestamp and description")]
+    InvalidEntry(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub chunk_type: String,
+    pub timestamp: String,
+    pub description: String,
+}
+
+// Sidecar bookkeeping for embedded messages, stored as a JSON array. The PNG
+// itself is never touched.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    entries: Vec<ManifestEntry>,
+}
+
+#[allow(dead_code)]
+impl Manifest {
+    // A missing file is an empty manifest, so the first encode creates it
+    pub fn load(path: &Path) -> Result<Self, ManifestError> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let value = json::parse(&text)?;
+        let items = value.as_array().ok_or(ManifestError::InvalidEntry(0))?;
+        let entries = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let field = |name| {
+                    item.get(name)
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                        .ok_or(ManifestError::InvalidEntry(i))
+                };
+                Ok(ManifestEntry {
+                    chunk_type: field("chunk_type")?,
+                    timestamp: field("timestamp")?,
+                    description: field("description")?,
+                })
+            })
+            .collect::<Result<_, ManifestError>>()?;
+
+        Ok(Manifest { entries })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ManifestError> {
+        let items = self
+            .entries
+            .iter()
+            .map(|entry| {
+                Value::Object(vec![
+                    ("chunk_type".to_string(), entry.chunk_type.as_str().into()),
+                    ("timestamp".to_string(), entry.timestamp.as_str().into()),
+                    ("description".to_string(), entry.description.as_str().into()),
+                ])
+            })
+            .collect();
+
+        std::fs::write(path, Value::Array(items).to_pretty() + "\n")?;
+        Ok(())
+    }
+
+    pub fn push(&mut self, chunk_type: &str, description: &str) {
+        self.entries.push(ManifestEntry {
+            chunk_type: chunk_type.to_string(),
+            timestamp: format_timestamp(SystemTime::now()),
+            description: description.to_string(),
+        });
+    }
+
+    pub fn entries(&self) -> &[ManifestEntry] {
+        &self.entries
+    }
+
+    // One line per entry saying whether its chunk is still in the PNG. The nth
+    // entry for a type is present when the PNG has at least n chunks of it.
+    // since, a timestamp from parse_since, leaves out entries made before it.
+    pub fn cross_reference(&self, png: &Png, since: Option<&str>) -> String {
+        let mut seen: Vec<&str> = Vec::new();
+        let mut report = String::new();
+        for entry in &self.entries {
+            let nth = seen.iter().filter(|t| **t == entry.chunk_type).count();
+            seen.push(&entry.chunk_type);
+            // Timestamps share one fixed-width UTC format, so text order is time order
+            if since.is_some_and(|since| entry.timestamp.as_str() < since) {
+                continue;
+            }
+
+            let found = png
+                .iter_chunks()
+                .filter(|ch| ch.is_type(&entry.chunk_type))
+                .count();
+            let status = if found > nth { "present" } else { "missing" };
+            let line = format!(
+                "{}  {}  {:<7}  {}",
+                entry.chunk_type, entry.timestamp, status, entry.description
+            );
+            report.push_str(line.trim_end());
+            report.push('\n');
+        }
+        report
+    }
+}
+
+// Accepts a date (2024-05-01) or a timestamp in the manifest's format and
+// returns it as a full timestamp for comparing with entries
+pub fn parse_since(value: &str) -> Result<String, String> {
+    let full = if value.len() == 10 {
+        format!("{}T00:00:00Z", value)
+    } else {
+        value.to_string()
+    };
+    let shape_ok = full.len() == 20
+        && full.bytes().enumerate().all(|(i, b)| match i {
+            4 | 7 => b == b'-',
+            10 => b == b'T',
+            13 | 16 => b == b':',
+            19 => b == b'Z',
+            _ => b.is_ascii_digit(),
+        });
+    if !shape_ok {
+        return Err(format!(
+            "expected a date like 2024-05-01 or a UTC timestamp like 2024-05-01T12:30:00Z, \
+             found {:?}",
+            value
+        ));
+    }
+    Ok(full)
+}
+
+// RFC 3339 in UTC, e.g. 2024-05-01T12:30:00Z
+pub fn format_timestamp(time: SystemTime) -> String {
+    let time = Time::from(time);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pingu::{chunk::Chunk, chunk_type::ChunkType};
+    use std::{
+        str::FromStr,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(1_709_210_096)),
+            "2024-02-29T12:34:56Z"
+        );
+    }
+
+    #[test]
+    fn test_manifest_entries_accumulate() {
+        let path = std::env::temp_dir().join("pingu_manifest_accumulate.json");
+        let _ = std::fs::remove_file(&path);
+
+        for (chunk_type, description) in [("ruSt", "first"), ("ruSt", "second"), ("noTe", "")] {
+            let mut manifest = Manifest::load(&path).unwrap();
+            manifest.push(chunk_type, description);
+            manifest.save(&path).unwrap();
+        }
+
+        let manifest = Manifest::load(&path).unwrap();
+        let entries: Vec<(&str, &str)> = manifest
+            .entries()
+            .iter()
+            .map(|e| (e.chunk_type.as_str(), e.description.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            [("ruSt", "first"), ("ruSt", "second"), ("noTe", "")]
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_cross_reference() {
+        let mut manifest = Manifest::default();
+        manifest.push("ruSt", "kept");
+        manifest.push("ruSt", "stripped");
+
+        let png = Png::from_chunks(vec![Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            b"kept".to_vec(),
+        )]);
+        let report = manifest.cross_reference(&png, None);
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert!(lines[0].contains("present  kept"));
+        assert!(lines[1].contains("missing  stripped"));
+    }
+
+    #[test]
+    fn test_cross_reference_since() {
+        let entry = |timestamp: &str, description: &str| ManifestEntry {
+            chunk_type: "ruSt".to_string(),
+            timestamp: timestamp.to_string(),
+            description: description.to_string(),
+        };
+        let manifest = Manifest {
+            entries: vec![
+                entry("2023-12-31T23:59:59Z", "last year"),
+                entry("2024-05-01T00:00:00Z", "may day"),
+                entry("2024-06-15T08:30:00Z", "june"),
+            ],
+        };
+        let png = Png::from_chunks(Vec::new());
+        let since = |value| {
+            let since = parse_since(value).unwrap();
+            let report = manifest.cross_reference(&png, Some(&since));
+            report
+                .lines()
+                .map(|line| line.rsplit("  ").next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(since("2024-05-01"), ["may day", "june"]);
+        assert_eq!(since("2024-05-01T00:00:01Z"), ["june"]);
+        assert_eq!(since("2023-01-01"), ["last year", "may day", "june"]);
+        assert!(since("2025-01-01").is_empty());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("2024-05-01").unwrap(), "2024-05-01T00:00:00Z");
+        assert_eq!(
+            parse_since("2024-05-01T12:30:00Z").unwrap(),
+            "2024-05-01T12:30:00Z"
+        );
+        assert!(parse_since("2024-5-1").is_err());
+        assert!(parse_since("yesterday").is_err());
+        assert!(parse_since("2024-05-01 12:30:00").is_err());
+    }
+
+    #[test]
+    fn test_load_rejects_bad_entries() {
+        let path = std::env::temp_dir().join("pingu_manifest_invalid.json");
+        std::fs::write(&path, r#"[{"chunk_type": "ruSt"}]"#).unwrap();
+
+        assert!(matches!(
+            Manifest::load(&path),
+            Err(ManifestError::InvalidEntry(0))
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
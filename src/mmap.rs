@@ -0,0 +1,146 @@
+use std::{fs::File, ops::Deref, path::Path};
+
+// Read-only private mapping of a whole file. Only 64-bit unix targets get a real
+// mapping; everywhere else `open` falls back to reading the file into memory.
+pub struct Mmap {
+    inner: Inner,
+}
+
+enum Inner {
+    #[cfg(all(unix, target_pointer_width = "64"))]
+    Mapped { ptr: *const u8, len: usize },
+    #[allow(dead_code)]
+    Owned(Vec<u8>),
+}
+
+impl Mmap {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+        Self::map(&file, len)
+    }
+
+    #[cfg(all(unix, target_pointer_width = "64"))]
+    fn map(file: &File, len: usize) -> std::io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        // mmap rejects empty mappings
+        if len == 0 {
+            return Ok(Mmap {
+                inner: Inner::Owned(Vec::new()),
+            });
+        }
+
+        let ptr = unsafe {
+            sys::mmap(
+                std::ptr::null_mut(),
+                len,
+                sys::PROT_READ,
+                sys::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == sys::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(Mmap {
+            inner: Inner::Mapped {
+                ptr: ptr as *const u8,
+                len,
+            },
+        })
+    }
+
+    #[cfg(not(all(unix, target_pointer_width = "64")))]
+    fn map(mut file: &File, len: usize) -> std::io::Result<Self> {
+        use std::io::Read;
+
+        let mut bytes = Vec::with_capacity(len);
+        file.read_to_end(&mut bytes)?;
+        Ok(Mmap {
+            inner: Inner::Owned(bytes),
+        })
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.inner {
+            #[cfg(all(unix, target_pointer_width = "64"))]
+            Inner::Mapped { ptr, len } => unsafe { std::slice::from_raw_parts(*ptr, *len) },
+            Inner::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        #[cfg(all(unix, target_pointer_width = "64"))]
+        if let Inner::Mapped { ptr, len } = self.inner {
+            unsafe {
+                sys::munmap(ptr as *mut _, len);
+            }
+        }
+    }
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+mod sys {
+    use std::os::raw::{c_int, c_void};
+
+    pub const PROT_READ: c_int = 1;
+    pub const MAP_PRIVATE: c_int = 2;
+    pub const MAP_FAILED: *mut c_void = !0 as *mut c_void;
+
+    extern "C" {
+        pub fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: c_int,
+            flags: c_int,
+            fd: c_int,
+            offset: i64,
+        ) -> *mut c_void;
+        pub fn munmap(addr: *mut c_void, len: usize) -> c_int;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("download.png")
+    }
+
+    #[test]
+    fn test_mmap_matches_read() {
+        let mapped = Mmap::open(&fixture()).unwrap();
+        let read = std::fs::read(fixture()).unwrap();
+
+        assert_eq!(&mapped[..], &read[..]);
+    }
+
+    #[test]
+    fn test_mmap_empty_file() {
+        let path = std::env::temp_dir().join("pingu_mmap_empty.png");
+        std::fs::write(&path, []).unwrap();
+
+        let mapped = Mmap::open(&path).unwrap();
+        assert!(mapped.is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mmap_missing_file() {
+        assert!(Mmap::open(Path::new("does/not/exist.png")).is_err());
+    }
+}
@@ -1,60 +1,656 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    io::{self, Read},
+};
 
-use crate::chunk::Chunk;
+use crate::chunk::{Chunk, CrcEndian};
 
 #[derive(Debug, thiserror::Error)]
 pub enum PngError {
-    #[error("Failed to parse PNG from bytes")]
-    ParseError,
+    #[error("Failed to parse PNG from bytes: chunk {index} at offset {offset} is truncated")]
+    ParseError { index: usize, offset: usize },
+    #[error("Invalid chunk {index} at offset {offset}: {source}")]
+    InvalidChunk {
+        index: usize,
+        offset: usize,
+        source: crate::chunk::ChunkError,
+    },
     #[error("Invalid PNG header")]
     InvalidHeader,
+    #[error("This looks like an {0} file, which is not supported")]
+    UnsupportedFormat(&'static str),
+    #[error("File is empty")]
+    Empty,
+    #[error("File is too small to be a PNG: {0} bytes")]
+    TooSmall(usize),
     #[error(transparent)]
     ChunkError(#[from] crate::chunk::ChunkError),
+    #[error("Found {0} trailing bytes after IEND")]
+    TrailingBytes(usize),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Cannot decompress gzip input: {0}")]
+    Gzip(#[from] crate::zlib::ZlibError),
+    #[allow(clippy::enum_variant_names)]
     #[error("PngError: {0}")]
     PngError(String),
 }
 pub struct Png {
     chunks: Vec<Chunk>,
+    // Bytes found after IEND. Lenient parsing keeps them (they are a common
+    // hiding place) and `as_bytes` writes them back; `try_from_strict` rejects them.
+    trailing: Vec<u8>,
+    // Normally STANDARD_HEADER; `try_from_any_signature` keeps whatever was there
+    signature: [u8; 8],
+    // Offset of the first chunk in the parsed input: 0 for a bare chunk stream,
+    // though `as_bytes` still writes a signature ahead of it
+    chunks_start: usize,
 }
 
+// Text keywords that encoders fill with the time of writing: the PNG spec's
+// "Creation Time" and the ones ImageMagick adds
+pub const DATED_TEXT_KEYWORDS: [&str; 4] = [
+    "Creation Time",
+    "date:create",
+    "date:modify",
+    "date:timestamp",
+];
+
 #[allow(unused_variables, dead_code)]
 impl Png {
-    const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+    pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+    pub fn from_chunks(chunks: Vec<Chunk>) -> Self {
+        Self {
+            chunks,
+            trailing: Vec::new(),
+            signature: Self::STANDARD_HEADER,
+            chunks_start: Self::STANDARD_HEADER.len(),
+        }
+    }
+
+    pub fn signature(&self) -> &[u8; 8] {
+        &self.signature
+    }
+
+    // Skips signature validation for files with a damaged or deliberately
+    // altered signature, and writes the same 8 bytes back out
+    pub fn try_from_any_signature(value: &[u8]) -> Result<Self, PngError> {
+        Self::check_size(value, Self::STANDARD_HEADER.len())?;
+        let mut png = Self::parse_chunks(value, Self::STANDARD_HEADER.len())?;
+        png.signature.copy_from_slice(&value[..8]);
+        Ok(png)
+    }
+
+    // Parses a bare sequence of chunks with no leading PNG signature
+    pub fn try_from_chunk_stream(value: &[u8]) -> Result<Self, PngError> {
+        Self::check_size(value, 0)?;
+        Self::parse_chunks(value, 0)
+    }
+
+    // Repairs a file whose first bytes are damaged but whose chunks are intact:
+    // finds the first IHDR chunk with a valid CRC and parses the chunk stream
+    // from there under a fresh signature. Returns the IHDR offset as well.
+    pub fn recover(value: &[u8]) -> Result<(usize, Self), PngError> {
+        const IHDR_START: [u8; 8] = [0, 0, 0, 13, b'I', b'H', b'D', b'R'];
+
+        let offset = (0..value.len().saturating_sub(IHDR_START.len()))
+            .filter(|&i| value[i..].starts_with(&IHDR_START))
+            .find(|&i| {
+                value
+                    .get(i..i + 25)
+                    .is_some_and(|ihdr| Chunk::try_from(ihdr).is_ok())
+            })
+            .ok_or_else(|| {
+                PngError::PngError("No intact IHDR chunk to recover from".to_string())
+            })?;
+        Ok((offset, Self::try_from_chunk_stream(&value[offset..])?))
+    }
 
-    fn from_chunks(chunks: Vec<Chunk>) -> Self {
-        Self { chunks }
+    // Catches empty and clearly truncated input up front: even the smallest
+    // PNG has the signature (if expected) and one 12-byte empty chunk
+    fn check_size(value: &[u8], signature_len: usize) -> Result<(), PngError> {
+        if value.is_empty() {
+            return Err(PngError::Empty);
+        }
+        if value.len() < signature_len + 12 {
+            return Err(PngError::TooSmall(value.len()));
+        }
+        Ok(())
+    }
+
+    // MNG and JNG share PNG's chunk layout but not its signature, so people
+    // reasonably try them here; name the format rather than just rejecting it
+    fn check_signature(signature: &[u8]) -> Result<(), PngError> {
+        match signature {
+            s if s == Self::STANDARD_HEADER => Ok(()),
+            [0x8A, b'M', b'N', b'G', 13, 10, 26, 10] => {
+                Err(PngError::UnsupportedFormat("MNG"))
+            }
+            [0x8B, b'J', b'N', b'G', 13, 10, 26, 10] => {
+                Err(PngError::UnsupportedFormat("JNG"))
+            }
+            _ => Err(PngError::InvalidHeader),
+        }
+    }
+
+    // Recovers whatever parses before the first error, e.g. from a truncated
+    // download. The error, if any, says where and why parsing stopped.
+    pub fn try_from_partial(value: &[u8]) -> (Self, Option<PngError>) {
+        if let Err(e) = Self::check_size(value, Self::STANDARD_HEADER.len()) {
+            return (Self::from_chunks(Vec::new()), Some(e));
+        }
+        if let Err(e) = Self::check_signature(&value[..8]) {
+            return (Self::from_chunks(Vec::new()), Some(e));
+        }
+
+        let mut chunks = Vec::new();
+        let mut trailing = Vec::new();
+        let error = Self::parse_chunks_into(value, 8, &mut chunks, &mut trailing, None).err();
+        let mut png = Self::from_chunks(chunks);
+        png.trailing = trailing;
+        (png, error)
+    }
+
+    fn parse_chunks(value: &[u8], start: usize) -> Result<Self, PngError> {
+        let mut chunks = Vec::new();
+        let mut trailing = Vec::new();
+        Self::parse_chunks_into(value, start, &mut chunks, &mut trailing, None)?;
+        let mut png = Self::from_chunks(chunks);
+        png.trailing = trailing;
+        png.chunks_start = start;
+        Ok(png)
+    }
+
+    // Accepts chunks with a wrong CRC and recomputes it, returning the
+    // indices of the chunks that were fixed
+    pub fn try_from_fixing_crcs(value: &[u8]) -> Result<(Self, Vec<usize>), PngError> {
+        Self::check_size(value, Self::STANDARD_HEADER.len())?;
+        Self::check_signature(&value[..8])?;
+
+        let mut chunks = Vec::new();
+        let mut trailing = Vec::new();
+        let mut fixed = Vec::new();
+        Self::parse_chunks_into(value, 8, &mut chunks, &mut trailing, Some(&mut fixed))?;
+        let mut png = Self::from_chunks(chunks);
+        png.trailing = trailing;
+        Ok((png, fixed))
+    }
+
+    fn parse_chunks_into(
+        value: &[u8],
+        start: usize,
+        chunks: &mut Vec<Chunk>,
+        trailing: &mut Vec<u8>,
+        mut fixed_crcs: Option<&mut Vec<usize>>,
+    ) -> Result<(), PngError> {
+        let mut position = start;
+
+        while position < value.len() {
+            // Ensure there's enough data for length, type, and CRC at minimum
+            if value.len() - position < 12 {
+                return Err(PngError::ParseError {
+                    index: chunks.len(),
+                    offset: position,
+                });
+            }
+
+            // The first four bytes after the position are the chunk length
+            let length =
+                u32::from_be_bytes(value[position..position + 4].try_into().unwrap()) as usize;
+
+            // Ensure total length, including the CRC, is within bounds
+            match length.checked_add(12).and_then(|n| position.checked_add(n)) {
+                Some(end) if end <= value.len() => {}
+                _ => {
+                    return Err(PngError::ParseError {
+                        index: chunks.len(),
+                        offset: position,
+                    })
+                }
+            }
+
+            // Extract the chunk bytes including length, type, data, and CRC
+            let chunk_bytes = &value[position..position + 12 + length];
+
+            // Attempt to parse the chunk
+            let chunk = match fixed_crcs.as_deref_mut() {
+                Some(fixed) => Chunk::try_from_fixing_crc(chunk_bytes).map(|(chunk, was_fixed)| {
+                    if was_fixed {
+                        fixed.push(chunks.len());
+                    }
+                    chunk
+                }),
+                None => Chunk::try_from(chunk_bytes),
+            }
+            .map_err(|source| PngError::InvalidChunk {
+                index: chunks.len(),
+                offset: position,
+                source,
+            })?;
+
+            let is_iend = chunk.is_type("IEND");
+            chunks.push(chunk);
+
+            // Move to the next chunk position: current position + length + 12 bytes for length, type, and CRC
+            position += length + 12;
+
+            // Nothing after IEND is part of the PNG; keep it aside untouched
+            if is_iend {
+                *trailing = value[position..].to_vec();
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Reads chunks one at a time and stops at the first of the given type,
+    // skipping over the data of everything before it without keeping it.
+    pub fn find_chunk_streaming<R: Read>(
+        mut reader: R,
+        chunk_type: &str,
+    ) -> Result<Option<Chunk>, PngError> {
+        let mut signature = [0; 8];
+        reader
+            .read_exact(&mut signature)
+            .map_err(|_| PngError::InvalidHeader)?;
+        Self::check_signature(&signature)?;
+
+        let mut offset = signature.len();
+        for index in 0.. {
+            let truncated = |e: io::Error| match e.kind() {
+                io::ErrorKind::UnexpectedEof => PngError::ParseError { index, offset },
+                _ => PngError::Io(e),
+            };
+
+            // A clean end of input between chunks just means no match
+            let mut header = [0; 8];
+            match read_full(&mut reader, &mut header)? {
+                0 => return Ok(None),
+                8 => {}
+                _ => return Err(PngError::ParseError { index, offset }),
+            }
+            let length = chunk_length(header, index, offset)?;
+
+            if &header[4..] == chunk_type.as_bytes() {
+                let bytes = read_chunk(&mut reader, header, length)?;
+                if bytes.len() < 12 + length {
+                    return Err(PngError::ParseError { index, offset });
+                }
+                return Chunk::try_from(&bytes[..])
+                    .map(Some)
+                    .map_err(|source| PngError::InvalidChunk {
+                        index,
+                        offset,
+                        source,
+                    });
+            }
+            if &header[4..] == b"IEND" {
+                return Ok(None);
+            }
+
+            let skip = length as u64 + 4;
+            if io::copy(&mut reader.by_ref().take(skip), &mut io::sink()).map_err(truncated)? < skip
+            {
+                return Err(PngError::ParseError { index, offset });
+            }
+            offset += 8 + length + 4;
+        }
+        unreachable!()
+    }
+
+    // Hands each chunk and its offset to visit as soon as it is read, without
+    // keeping earlier ones. Stops after IEND, leaving the reader just past it.
+    pub fn for_each_chunk<R: Read>(
+        reader: &mut R,
+        mut visit: impl FnMut(usize, Chunk) -> io::Result<()>,
+    ) -> Result<(), PngError> {
+        let mut signature = [0; 8];
+        if read_full(reader, &mut signature)? < 8 {
+            return Err(PngError::InvalidHeader);
+        }
+        Self::check_signature(&signature)?;
+
+        let mut offset = signature.len();
+        for index in 0.. {
+            let mut header = [0; 8];
+            match read_full(reader, &mut header)? {
+                0 => return Ok(()),
+                8 => {}
+                _ => return Err(PngError::ParseError { index, offset }),
+            }
+            let length = chunk_length(header, index, offset)?;
+
+            let bytes = read_chunk(reader, header, length)?;
+            if bytes.len() < 12 + length {
+                return Err(PngError::ParseError { index, offset });
+            }
+            let chunk = Chunk::try_from(&bytes[..]).map_err(|source| PngError::InvalidChunk {
+                index,
+                offset,
+                source,
+            })?;
+            let is_end = chunk.is_type("IEND");
+            visit(offset, chunk)?;
+            offset += bytes.len();
+
+            if is_end {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn try_from_strict(value: &[u8]) -> Result<Self, PngError> {
+        let png = Self::try_from(value)?;
+        if !png.trailing.is_empty() {
+            return Err(PngError::TrailingBytes(png.trailing.len()));
+        }
+        Ok(png)
+    }
+
+    pub fn trailing_bytes(&self) -> &[u8] {
+        &self.trailing
     }
 
     fn chunks(self) -> Vec<Chunk> {
         self.chunks
     }
 
+    pub fn chunks_with_offsets(&self) -> impl Iterator<Item = (usize, &Chunk)> {
+        self.chunks.iter().scan(self.chunks_start, |offset, ch| {
+            let chunk_offset = *offset;
+            *offset += ch.length() as usize + 12;
+            Some((chunk_offset, ch))
+        })
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    // Sum of chunk data lengths, excluding the length/type/CRC framing
+    pub fn total_data_len(&self) -> usize {
+        self.chunks.iter().map(|ch| ch.length() as usize).sum()
+    }
+
+    pub fn iter_chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.iter()
+    }
+
     pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
         self.chunks
             .iter()
-            .find(|&ch| ch.chunk_type().to_string() == chunk_type)
+            .find(|&ch| ch.is_type(chunk_type))
+    }
+
+    // Chunks whose type starts with `prefix`, ordered by the rest of the type
+    pub fn chunks_by_type_prefix(&self, prefix: &str) -> Vec<&Chunk> {
+        let mut chunks: Vec<&Chunk> = self
+            .chunks
+            .iter()
+            .filter(|ch| ch.chunk_type().bytes().starts_with(prefix.as_bytes()))
+            .collect();
+        chunks.sort_by_key(|ch| ch.chunk_type().bytes());
+        chunks
+    }
+
+    // The zlib stream can be split across any number of IDAT chunks
+    pub fn image_data(&self) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .filter(|ch| ch.is_type("IDAT"))
+            .flat_map(|ch| ch.data())
+            .copied()
+            .collect()
+    }
+
+    // Replaces every IDAT chunk with a single one at the first IDAT's position
+    pub fn replace_image_data(&mut self, data: Vec<u8>) -> crate::Result<()> {
+        let index = self
+            .chunks
+            .iter()
+            .position(|ch| ch.is_type("IDAT"))
+            .ok_or_else(|| PngError::PngError("Cannot find the chunk".to_string()))?;
+        let chunk_type = *self.chunks[index].chunk_type();
+
+        self.chunks.retain(|ch| !ch.is_type("IDAT"));
+        self.chunks.insert(index, Chunk::new(chunk_type, data));
+        Ok(())
     }
 
     pub fn append_chunk(&mut self, chunk: Chunk) {
         self.chunks.push(chunk)
     }
 
+    // Keeps IEND as the final chunk; falls back to appending when there is none.
+    // Returns the index the chunk ended up at.
+    pub fn insert_chunk_before_iend(&mut self, chunk: Chunk) -> usize {
+        let index = self
+            .chunks
+            .iter()
+            .rposition(|ch| ch.is_type("IEND"))
+            .unwrap_or(self.chunks.len());
+        self.chunks.insert(index, chunk);
+        index
+    }
+
+    // Overwrites the first chunk of the same type in place, or inserts the
+    // chunk before IEND when there is none. Returns the chunk's index.
+    pub fn replace_chunk(&mut self, chunk: Chunk) -> usize {
+        let chunk_type = chunk.chunk_type().to_string();
+        match self.chunks.iter().position(|ch| ch.is_type(&chunk_type)) {
+            Some(index) => {
+                self.chunks[index] = chunk;
+                index
+            }
+            None => self.insert_chunk_before_iend(chunk),
+        }
+    }
+
+    // Adds the chunk's data onto the end of the first chunk of the same type,
+    // after the separator, or inserts the chunk before IEND when there is none.
+    // Returns the chunk's index.
+    pub fn append_to_chunk(&mut self, chunk: Chunk, separator: &[u8]) -> usize {
+        let chunk_type = chunk.chunk_type().to_string();
+        match self.chunks.iter().position(|ch| ch.is_type(&chunk_type)) {
+            Some(index) => {
+                let existing = &mut self.chunks[index];
+                existing.data_mut().extend_from_slice(separator);
+                existing.data_mut().extend_from_slice(chunk.data());
+                existing.recompute();
+                index
+            }
+            None => self.insert_chunk_before_iend(chunk),
+        }
+    }
+
     pub fn remove_chunk(&mut self, chunk_type: &str) -> crate::Result<Chunk> {
         let index = self
             .chunks
             .iter()
-            .position(|ch| ch.chunk_type().to_string() == chunk_type)
+            .position(|ch| ch.is_type(chunk_type))
             .ok_or_else(|| PngError::PngError("Cannot find the chunk".to_string()))?;
         Ok(self.chunks.remove(index))
     }
 
+    // Removes every chunk of this type, returning them in file order
+    pub fn remove_chunks(&mut self, chunk_type: &str) -> Vec<Chunk> {
+        let (removed, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(|ch| ch.is_type(chunk_type));
+        self.chunks = kept;
+        removed
+    }
+
+    // Stable-sorts chunks into the order the PNG spec requires, keeping the
+    // relative order within each group. Returns the problems sorting can't fix.
+    pub fn canonicalize(&mut self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for required in ["IHDR", "IEND"] {
+            match self.chunks.iter().filter(|ch| ch.is_type(required)).count() {
+                0 => problems.push(format!("missing {} chunk", required)),
+                1 => {}
+                n => problems.push(format!("{} {} chunks, expected one", n, required)),
+            }
+        }
+
+        for chunk in &self.chunks {
+            if chunk.chunk_type().is_critical() && ordering_rank(chunk).is_none() {
+                problems.push(format!(
+                    "unknown critical chunk {} has no ordering rule",
+                    chunk.chunk_type()
+                ));
+            }
+        }
+
+        self.chunks
+            .sort_by_key(|ch| ordering_rank(ch).unwrap_or(OrderingRank::AfterImageData));
+
+        problems
+    }
+
+    // Drops chunks that record when the file was written rather than what it
+    // shows: every tIME chunk and, with dated_text, tEXt, zTXt and iTXt
+    // chunks whose keyword is in DATED_TEXT_KEYWORDS. Returns what was removed.
+    pub fn strip_volatile(&mut self, dated_text: bool) -> Vec<Chunk> {
+        let is_volatile = |chunk: &Chunk| {
+            if chunk.is_type("tIME") {
+                return true;
+            }
+            let is_text = ["tEXt", "zTXt", "iTXt"].iter().any(|t| chunk.is_type(t));
+            let keyword = chunk.data().split(|&b| b == 0).next().unwrap_or_default();
+            dated_text && is_text && DATED_TEXT_KEYWORDS.iter().any(|k| k.as_bytes() == keyword)
+        };
+        let (removed, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(is_volatile);
+        self.chunks = kept;
+        removed
+    }
+
+    // Moves the first chunk of each listed type to the front, in list order;
+    // a type listed twice takes its next occurrence. Everything unlisted
+    // follows in its original relative order. Nothing moves on error.
+    // Chunks move whole: the CRC only covers type and data, so it is kept
+    // as stored rather than recomputed.
+    pub fn reorder(&mut self, order: &[&str]) -> crate::Result<()> {
+        let mut taken = vec![false; self.chunks.len()];
+        let mut positions = Vec::with_capacity(self.chunks.len());
+        for chunk_type in order {
+            let position = (0..self.chunks.len())
+                .find(|&i| !taken[i] && self.chunks[i].is_type(chunk_type))
+                .ok_or_else(|| PngError::PngError(format!("No {} chunk to move", chunk_type)))?;
+            taken[position] = true;
+            positions.push(position);
+        }
+        positions.extend((0..self.chunks.len()).filter(|&i| !taken[i]));
+
+        let mut chunks: Vec<Option<Chunk>> =
+            std::mem::take(&mut self.chunks).into_iter().map(Some).collect();
+        self.chunks = positions
+            .into_iter()
+            .map(|i| chunks[i].take().unwrap())
+            .collect();
+        Ok(())
+    }
+
+    // Swaps the first chunk of this type for an empty one of the same type,
+    // so chunk count and positions survive while the payload is erased.
+    pub fn tombstone_chunk(&mut self, chunk_type: &str) -> crate::Result<Chunk> {
+        let chunk = self
+            .chunks
+            .iter_mut()
+            .find(|ch| ch.is_type(chunk_type))
+            .ok_or_else(|| PngError::PngError("Cannot find the chunk".to_string()))?;
+        let tombstone = Chunk::new(*chunk.chunk_type(), Vec::new());
+        Ok(std::mem::replace(chunk, tombstone))
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
-        Self::STANDARD_HEADER.into_iter()
-        .chain(self.chunks.iter().flat_map(|ch| ch.as_bytes()))
+        self.as_bytes_with_crc_endian(CrcEndian::Big)
+    }
+
+    pub fn as_bytes_with_crc_endian(&self, crc_endian: CrcEndian) -> Vec<u8> {
+        self.signature.into_iter()
+        .chain(self.chunks.iter().flat_map(|ch| ch.as_bytes_with_crc_endian(crc_endian)))
+        .chain(self.trailing.iter().copied())
         .collect::<Vec<u8>>()
     }
 }
 
+// The spec caps chunk lengths at 2^31 - 1. Streaming reads check this before
+// reading anything, as the length is the first thing a corrupt file gets wrong.
+const MAX_CHUNK_LENGTH: u32 = (1 << 31) - 1;
+
+fn chunk_length(header: [u8; 8], index: usize, offset: usize) -> Result<usize, PngError> {
+    let length = u32::from_be_bytes(header[..4].try_into().unwrap());
+    if length > MAX_CHUNK_LENGTH {
+        return Err(PngError::InvalidChunk {
+            index,
+            offset,
+            source: crate::chunk::ChunkError::InvalidLength(length as usize),
+        });
+    }
+    Ok(length as usize)
+}
+
+// Reads the chunk following header into a buffer that grows as bytes arrive,
+// rather than one sized up front from the untrusted length. The result is
+// short if the input ends early.
+fn read_chunk<R: Read>(reader: &mut R, header: [u8; 8], length: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = header.to_vec();
+    reader.take(length as u64 + 4).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+// Like read_exact, but reports how much was read when input runs out
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum OrderingRank {
+    Header,
+    BeforePalette,
+    Palette,
+    BeforeImageData,
+    ImageData,
+    AfterImageData,
+    End,
+}
+
+// Where a known chunk type must sit. Unknown ancillary chunks (including
+// embedded messages) go after the image data, which is always a safe place.
+fn ordering_rank(chunk: &Chunk) -> Option<OrderingRank> {
+    let rank = match &chunk.chunk_type().bytes() {
+        b"IHDR" => OrderingRank::Header,
+        b"cHRM" | b"cICP" | b"gAMA" | b"iCCP" | b"mDCv" | b"cLLi" | b"sBIT" | b"sRGB" => {
+            OrderingRank::BeforePalette
+        }
+        b"PLTE" => OrderingRank::Palette,
+        b"bKGD" | b"hIST" | b"tRNS" | b"eXIf" | b"pHYs" | b"sPLT" => {
+            OrderingRank::BeforeImageData
+        }
+        b"IDAT" => OrderingRank::ImageData,
+        b"IEND" => OrderingRank::End,
+        _ if chunk.chunk_type().is_critical() => return None,
+        _ => OrderingRank::AfterImageData,
+    };
+    Some(rank)
+}
+
 impl Display for Png {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut png_str = String::new();
@@ -70,46 +666,11 @@ impl TryFrom<&[u8]> for Png {
     type Error = PngError;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        if value.len() < 8 {
-            return Err(PngError::InvalidHeader);
-        }
-
-        // Check if the header matches the standard PNG header
-        if &value[..8] != Self::STANDARD_HEADER {
-            return Err(PngError::InvalidHeader);
-        }
-
-        let mut chunks = Vec::new();
-        let mut position = 8; // Start after the header
-
-        while position < value.len() {
-            // Ensure there's enough data for length, type, and CRC at minimum
-            if value.len() - position < 12 {
-                return Err(PngError::ParseError);
-            }
-
-            // The first four bytes after the position are the chunk length
-            let length =
-                u32::from_be_bytes(value[position..position + 4].try_into().unwrap()) as usize;
-
-            // Ensure total length is within bounds
-            if position + 8 + length > value.len() {
-                return Err(PngError::ParseError);
-            }
-
-            // Extract the chunk bytes including length, type, data, and CRC
-            let chunk_bytes = &value[position..position + 12 + length];
-
-            // Attempt to parse the chunk
-            let chunk = Chunk::try_from(chunk_bytes).map_err(|e| PngError::ChunkError(e))?;
-
-            chunks.push(chunk);
+        Self::check_size(value, Self::STANDARD_HEADER.len())?;
 
-            // Move to the next chunk position: current position + length + 12 bytes for length, type, and CRC
-            position += length + 12;
-        }
+        Self::check_signature(&value[..8])?;
 
-        Ok(Png { chunks })
+        Self::parse_chunks(value, Self::STANDARD_HEADER.len())
     }
 }
 
@@ -207,6 +768,90 @@ mod tests {
         assert!(png.is_err());
     }
 
+    #[test]
+    fn test_signature() {
+        assert_eq!(Png::STANDARD_HEADER, *b"\x89PNG\r\n\x1a\n");
+
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.signature(), &Png::STANDARD_HEADER);
+        assert_eq!(png.as_bytes()[..8], Png::STANDARD_HEADER);
+    }
+
+    #[test]
+    fn test_any_signature_is_preserved() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[..8].copy_from_slice(b"NOTAPNG!");
+
+        assert!(Png::try_from(bytes.as_slice()).is_err());
+        let png = Png::try_from_any_signature(&bytes).unwrap();
+        assert_eq!(png.signature(), b"NOTAPNG!");
+        assert_eq!(png.chunk_count(), 7);
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_chunk_stream_without_signature() {
+        let bytes = &PNG_FILE[8..];
+
+        assert!(Png::try_from(bytes).is_err());
+        let png = Png::try_from_chunk_stream(bytes).unwrap();
+        assert_eq!(png.chunk_count(), 7);
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_recover_zeroed_signature() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[..8].fill(0);
+        assert!(Png::try_from(bytes.as_slice()).is_err());
+
+        let (offset, png) = Png::recover(&bytes).unwrap();
+
+        assert_eq!(offset, 8);
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+        assert!(Png::try_from(png.as_bytes().as_slice()).is_ok());
+    }
+
+    #[test]
+    fn test_recover_skips_junk_and_fake_ihdr() {
+        // A lookalike IHDR header with no valid chunk behind it comes first
+        let mut bytes = b"garbage\0\0\0\x0dIHDRnot really".to_vec();
+        bytes.extend_from_slice(&PNG_FILE[8..]);
+
+        let (offset, png) = Png::recover(&bytes).unwrap();
+
+        assert_eq!(offset, 25);
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_recover_without_ihdr() {
+        assert!(Png::recover(&[0; 64]).is_err());
+        assert!(Png::recover(&PNG_FILE[9..]).is_err());
+    }
+
+    #[test]
+    fn test_fixing_crcs() {
+        let mut bytes = PNG_FILE.to_vec();
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        // Break the CRC of the first, third and last chunks
+        let crc_ends: Vec<usize> = png
+            .chunks_with_offsets()
+            .map(|(offset, chunk)| offset + chunk.byte_len())
+            .collect();
+        for &index in &[0, 2, 6] {
+            bytes[crc_ends[index] - 1] ^= 0x5a;
+        }
+        assert!(Png::try_from(bytes.as_slice()).is_err());
+
+        let (fixed, indices) = Png::try_from_fixing_crcs(&bytes).unwrap();
+
+        assert_eq!(indices, vec![0, 2, 6]);
+        assert_eq!(fixed.as_bytes(), PNG_FILE.to_vec());
+        let (_, indices) = Png::try_from_fixing_crcs(&PNG_FILE[..]).unwrap();
+        assert!(indices.is_empty());
+    }
+
     #[test]
     fn test_list_chunks() {
         let png = testing_png();
@@ -222,6 +867,244 @@ mod tests {
         assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
     }
 
+    #[test]
+    fn test_find_chunk_streaming() {
+        let chunk = Png::find_chunk_streaming(&PNG_FILE[..], "RuSt").unwrap().unwrap();
+        let full = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(chunk.as_bytes(), full.chunk_by_type("RuSt").unwrap().as_bytes());
+
+        assert!(Png::find_chunk_streaming(&PNG_FILE[..], "nOne").unwrap().is_none());
+        assert!(matches!(
+            Png::find_chunk_streaming(&PNG_FILE[1..], "RuSt"),
+            Err(PngError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn test_find_chunk_streaming_stops_at_match() {
+        // Everything after the match is garbage and must never be read
+        let full = Png::try_from(&PNG_FILE[..]).unwrap();
+        let (offset, _) = full.chunks_with_offsets().nth(1).unwrap();
+        let mut bytes = PNG_FILE[..offset].to_vec();
+        bytes.extend_from_slice(&full.iter_chunks().nth(1).unwrap().as_bytes());
+        bytes.extend_from_slice(b"garbage");
+
+        let chunk_type = full.iter_chunks().nth(1).unwrap().chunk_type().to_string();
+        assert!(Png::find_chunk_streaming(&bytes[..], &chunk_type)
+            .unwrap()
+            .is_some());
+
+        // A truncated chunk before the match is reported with its position
+        assert!(matches!(
+            Png::find_chunk_streaming(&PNG_FILE[..40], "RuSt"),
+            Err(PngError::ParseError { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_find_chunk_streaming_checks_length() {
+        let mut bytes = Png::STANDARD_HEADER.to_vec();
+        bytes.extend_from_slice(&[0xff, 0xff, 0xff, 0xf0]);
+        bytes.extend_from_slice(b"RuSt data");
+        assert!(matches!(
+            Png::find_chunk_streaming(&bytes[..], "RuSt"),
+            Err(PngError::InvalidChunk {
+                index: 0,
+                source: crate::chunk::ChunkError::InvalidLength(0xffff_fff0),
+                ..
+            })
+        ));
+
+        // The largest valid length is only as much memory as the input provides
+        bytes[8..12].copy_from_slice(&MAX_CHUNK_LENGTH.to_be_bytes());
+        assert!(matches!(
+            Png::find_chunk_streaming(&bytes[..], "RuSt"),
+            Err(PngError::ParseError { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_chunk_by_type_is_case_sensitive() {
+        let png = Png::from_chunks(vec![
+            chunk_from_strings("ruSt", "lower").unwrap(),
+            chunk_from_strings("RUST", "upper").unwrap(),
+        ]);
+
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"lower");
+        assert_eq!(png.chunk_by_type("RUST").unwrap().data(), b"upper");
+        assert!(png.chunk_by_type("rust").is_none());
+        assert!(png.chunk_by_type("RuSt").is_none());
+    }
+
+    #[test]
+    fn test_chunks_by_type_prefix() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("msAc", "third").unwrap());
+        png.append_chunk(chunk_from_strings("msAa", "first").unwrap());
+        png.append_chunk(chunk_from_strings("mxAb", "other").unwrap());
+        png.append_chunk(chunk_from_strings("msAb", "second").unwrap());
+
+        let messages: Vec<String> = png
+            .chunks_by_type_prefix("ms")
+            .iter()
+            .map(|ch| ch.data_as_string().unwrap())
+            .collect();
+
+        assert_eq!(messages, vec!["first", "second", "third"]);
+    }
+
+    fn chunk_types(png: &Png) -> Vec<String> {
+        png.chunks
+            .iter()
+            .map(|ch| ch.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("tEXt", "comment").unwrap(),
+            chunk_from_strings("PLTE", "palette").unwrap(),
+            chunk_from_strings("gAMA", "gamma").unwrap(),
+            chunk_from_strings("IDAT", "first").unwrap(),
+            chunk_from_strings("tRNS", "transparency").unwrap(),
+            chunk_from_strings("IDAT", "second").unwrap(),
+            chunk_from_strings("cHRM", "chromaticity").unwrap(),
+            chunk_from_strings("ruSt", "message").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+
+        let problems = png.canonicalize();
+
+        assert!(problems.is_empty());
+        assert_eq!(
+            chunk_types(&png),
+            vec![
+                "IHDR", "gAMA", "cHRM", "PLTE", "tRNS", "IDAT", "IDAT", "tEXt", "ruSt", "IEND"
+            ]
+        );
+        let idat: Vec<String> = png
+            .chunks
+            .iter()
+            .filter(|ch| ch.is_type("IDAT"))
+            .map(|ch| ch.data_as_string().unwrap())
+            .collect();
+        assert_eq!(idat, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_reorder() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IDAT", "first").unwrap(),
+            chunk_from_strings("gAMA", "gamma").unwrap(),
+            chunk_from_strings("IDAT", "second").unwrap(),
+            chunk_from_strings("ruSt", "message").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+
+        png.reorder(&["IHDR", "ruSt", "gAMA"]).unwrap();
+
+        assert_eq!(
+            chunk_types(&Png::try_from(&png.as_bytes()[..]).unwrap()),
+            vec!["IHDR", "ruSt", "gAMA", "IDAT", "IDAT", "IEND"]
+        );
+        let idat: Vec<String> = png
+            .chunks
+            .iter()
+            .filter(|ch| ch.is_type("IDAT"))
+            .map(|ch| ch.data_as_string().unwrap())
+            .collect();
+        assert_eq!(idat, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_reorder_keeps_stored_crcs() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let original: Vec<Vec<u8>> = png.chunks.iter().map(|ch| ch.as_bytes()).collect();
+        let mut types = chunk_types(&png);
+        types.reverse();
+
+        let mut reordered = Png::try_from(&PNG_FILE[..]).unwrap();
+        let order: Vec<&str> = types.iter().map(|t| t.as_str()).collect();
+        reordered.reorder(&order).unwrap();
+
+        let moved: Vec<Vec<u8>> = reordered.chunks.iter().map(|ch| ch.as_bytes()).collect();
+        assert_ne!(moved, original);
+        for chunk in &original {
+            assert!(moved.contains(chunk));
+        }
+    }
+
+    #[test]
+    fn test_reorder_missing_type() {
+        let mut png = testing_png();
+
+        assert!(png.reorder(&["LASt", "LASt"]).is_err());
+        assert!(png.reorder(&["nOne"]).is_err());
+        assert_eq!(chunk_types(&png), vec!["FrSt", "miDl", "LASt"]);
+    }
+
+    #[test]
+    fn test_strip_volatile_makes_output_deterministic() {
+        let encode = |time: &str, created: &str| {
+            let mut png = Png::from_chunks(vec![
+                chunk_from_strings("IHDR", "header").unwrap(),
+                chunk_from_strings("tIME", time).unwrap(),
+                chunk_from_strings("tEXt", &format!("date:create\0{}", created)).unwrap(),
+                chunk_from_strings("tEXt", "Title\0pingu").unwrap(),
+                chunk_from_strings("IDAT", "pixels").unwrap(),
+                chunk_from_strings("IEND", "").unwrap(),
+            ]);
+            let removed = png.strip_volatile(true);
+            png.canonicalize();
+            (removed.len(), png.as_bytes())
+        };
+
+        let (removed, first) = encode("2024-01-01", "2024-01-01T00:00:00");
+        let (_, second) = encode("2025-06-30", "2025-06-30T12:00:00");
+        assert_eq!(removed, 2);
+        assert_eq!(first, second);
+
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("tIME", "now").unwrap(),
+            chunk_from_strings("tEXt", "Creation Time\0today").unwrap(),
+            chunk_from_strings("zTXt", "Title\0pingu").unwrap(),
+        ]);
+        png.strip_volatile(false);
+        assert_eq!(chunk_types(&png), vec!["tEXt", "zTXt"]);
+        png.strip_volatile(true);
+        assert_eq!(chunk_types(&png), vec!["zTXt"]);
+    }
+
+    #[test]
+    fn test_canonicalize_moves_message_before_iend() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_strings("ruSt", "after the end").unwrap());
+
+        png.canonicalize();
+
+        let types = chunk_types(&png);
+        assert_eq!(types.last().unwrap(), "IEND");
+        assert_eq!(types[types.len() - 2], "ruSt");
+    }
+
+    #[test]
+    fn test_canonicalize_reports_problems() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IDAT", "data").unwrap(),
+            chunk_from_strings("ABCD", "unknown critical").unwrap(),
+        ]);
+
+        let problems = png.canonicalize();
+
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].contains("missing IHDR"));
+        assert!(problems[1].contains("missing IEND"));
+        assert!(problems[2].contains("ABCD"));
+    }
+
     #[test]
     fn test_append_chunk() {
         let mut png = testing_png();
@@ -240,6 +1123,99 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_remove_chunks() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("TeSt", "one").unwrap(),
+            chunk_from_strings("FrSt", "I am the first chunk").unwrap(),
+            chunk_from_strings("TeSt", "two").unwrap(),
+        ]);
+
+        let removed = png.remove_chunks("TeSt");
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[1].data(), b"two");
+        assert_eq!(png.chunk_count(), 1);
+        assert!(png.remove_chunks("TeSt").is_empty());
+    }
+
+    #[test]
+    fn test_chunk_count_and_total_data_len() {
+        let png = testing_png();
+        assert_eq!(png.chunk_count(), 3);
+        assert_eq!(png.total_data_len(), 20 + 18 + 19);
+
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.chunk_count(), 7);
+        assert_eq!(png.total_data_len(), PNG_FILE.len() - 8 - 7 * 12);
+    }
+
+    #[test]
+    fn test_chunks_with_offsets() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let offsets: Vec<(usize, String)> = png
+            .chunks_with_offsets()
+            .map(|(offset, ch)| (offset, ch.chunk_type().to_string()))
+            .collect();
+
+        assert_eq!(offsets[0], (8, "IHDR".to_string()));
+        assert_eq!(offsets[1], (33, "sRGB".to_string()));
+        for (offset, chunk_type) in &offsets {
+            assert_eq!(&PNG_FILE[offset + 4..offset + 8], chunk_type.as_bytes());
+        }
+
+        // Without a signature the chunks start right at the beginning
+        let stream = &PNG_FILE[8..];
+        let png = Png::try_from_chunk_stream(stream).unwrap();
+        let (offset, chunk) = png.chunks_with_offsets().nth(1).unwrap();
+        assert_eq!(offset, 25);
+        assert_eq!(&stream[offset + 4..offset + 8], chunk.chunk_type().bytes());
+    }
+
+    #[test]
+    fn test_trailing_bytes_after_iend() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend_from_slice(b"PK\x03\x04 hidden payload");
+
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(png.trailing_bytes(), b"PK\x03\x04 hidden payload");
+        assert_eq!(png.chunks().last().unwrap().chunk_type().to_string(), "IEND");
+    }
+
+    #[test]
+    fn test_trailing_bytes_round_trip() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend_from_slice(b"trailing");
+
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_strict_rejects_trailing_bytes() {
+        let mut bytes = PNG_FILE.to_vec();
+        assert!(Png::try_from_strict(bytes.as_ref()).is_ok());
+
+        bytes.extend_from_slice(b"trailing");
+        assert!(matches!(
+            Png::try_from_strict(bytes.as_ref()),
+            Err(PngError::TrailingBytes(8))
+        ));
+    }
+
+    #[test]
+    fn test_tombstone_chunk() {
+        let mut png = testing_png();
+        let removed = png.tombstone_chunk("miDl").unwrap();
+        assert_eq!(removed.data_as_string().unwrap(), "I am another chunk");
+
+        let reparsed = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        assert_eq!(reparsed.chunk_count(), 3);
+        let tombstone = reparsed.chunk_by_type("miDl").unwrap();
+        assert_eq!(tombstone.length(), 0);
+        assert_eq!(tombstone.crc(), crc32fast::hash(b"miDl"));
+        assert!(png.tombstone_chunk("NoNe").is_err());
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -255,6 +1231,332 @@ mod tests {
         pretty_assertions::assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_as_bytes_preserves_order_and_signature() {
+        let png = Png::from_chunks(vec![
+            Chunk::new(
+                ChunkType::from_str("IHDR").unwrap(),
+                vec![0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0],
+            ),
+            chunk_from_strings("ruSt", "hi").unwrap(),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ]);
+
+        #[rustfmt::skip]
+        let expected: Vec<u8> = vec![
+            137, 80, 78, 71, 13, 10, 26, 10,                // Signature
+            0, 0, 0, 13, 73, 72, 68, 82,                    // IHDR length + type
+            0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0,          // IHDR data
+            58, 126, 155, 85,                               // IHDR CRC
+            0, 0, 0, 2, 114, 117, 83, 116,                  // ruSt length + type
+            104, 105,                                       // ruSt data
+            213, 16, 58, 69,                                // ruSt CRC
+            0, 0, 0, 0, 73, 69, 78, 68,                     // IEND length + type
+            174, 66, 96, 130,                               // IEND CRC
+        ];
+
+        pretty_assertions::assert_eq!(png.as_bytes(), expected);
+    }
+
+    #[test]
+    fn test_insert_chunk_before_iend() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let index = png.insert_chunk_before_iend(chunk_from_strings("TeSt", "Message").unwrap());
+
+        let chunks = png.chunks();
+        assert_eq!(index, chunks.len() - 2);
+        let types: Vec<String> = chunks
+            .iter()
+            .map(|ch| ch.chunk_type().to_string())
+            .collect();
+        assert_eq!(types.last().unwrap(), "IEND");
+        assert_eq!(types[types.len() - 2], "TeSt");
+    }
+
+    #[test]
+    fn test_replace_chunk() {
+        let mut png = testing_png();
+        let index = png.replace_chunk(chunk_from_strings("FrSt", "replaced").unwrap());
+        assert_eq!(index, 0);
+        assert_eq!(png.chunk_count(), 3);
+        assert_eq!(png.chunk_by_type("FrSt").unwrap().data(), b"replaced");
+
+        let index = png.replace_chunk(chunk_from_strings("NeWw", "new").unwrap());
+        assert_eq!(index, 3);
+        assert_eq!(png.chunk_count(), 4);
+    }
+
+    #[test]
+    fn test_append_to_chunk() {
+        let mut png = testing_png();
+        let index = png.append_to_chunk(chunk_from_strings("miDl", "appended").unwrap(), b"\n");
+        assert_eq!(index, 1);
+        assert_eq!(png.chunk_count(), 3);
+
+        let chunk = png.chunk_by_type("miDl").unwrap();
+        assert_eq!(chunk.data(), b"I am another chunk\nappended");
+        assert_eq!(chunk.length(), 27);
+        let reparsed = Chunk::try_from(chunk.as_bytes().as_slice()).unwrap();
+        assert_eq!(reparsed.crc(), chunk.crc());
+
+        let index = png.append_to_chunk(chunk_from_strings("NeWw", "new").unwrap(), b"\n");
+        assert_eq!(index, 3);
+        assert_eq!(png.chunk_by_type("NeWw").unwrap().data(), b"new");
+    }
+
+    #[test]
+    fn test_replace_image_data() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IDAT", "first").unwrap(),
+            chunk_from_strings("IDAT", "second").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+        assert_eq!(png.image_data(), b"firstsecond");
+
+        png.replace_image_data(b"replaced".to_vec()).unwrap();
+        let types: Vec<String> = png
+            .iter_chunks()
+            .map(|ch| ch.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+        assert_eq!(png.image_data(), b"replaced");
+
+        assert!(Png::from_chunks(vec![]).replace_image_data(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_error_names_third_chunk() {
+        let mut bytes = Png::from_chunks(testing_chunks()).as_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+
+        let err = Png::try_from(bytes.as_ref()).err().unwrap();
+        let offset = 8 + (12 + 20) + (12 + 18);
+        assert!(matches!(
+            err,
+            PngError::InvalidChunk {
+                index: 2,
+                offset: o,
+                source: crate::chunk::ChunkError::InvalidCrc,
+            } if o == offset
+        ));
+        assert!(err.to_string().contains("chunk 2"));
+    }
+
+    #[test]
+    fn test_error_source_chain_from_bad_chunk_type() {
+        use std::error::Error;
+
+        let mut bytes = Png::from_chunks(testing_chunks()).as_bytes();
+        // Type byte of the second chunk
+        bytes[8 + (12 + 20) + 4] = b'1';
+
+        let err = Png::try_from(bytes.as_ref()).err().unwrap();
+        assert!(matches!(err, PngError::InvalidChunk { index: 1, .. }));
+
+        let chunk_err = err.source().unwrap();
+        assert!(matches!(
+            chunk_err.downcast_ref(),
+            Some(crate::chunk::ChunkError::InvalidChunkType(
+                crate::chunk_type::ChunkTypeErr::InvalidByte(b'1', _)
+            ))
+        ));
+        assert!(err.to_string().starts_with("Invalid chunk 1 at offset 40: Invalid byte: 49"));
+    }
+
+    #[test]
+    fn test_png_error_is_send_and_sync() {
+        fn assert_error<E: std::error::Error + Send + Sync + 'static>() {}
+        assert_error::<PngError>();
+        assert_error::<crate::chunk::ChunkError>();
+    }
+
+    // Hands out at most a few bytes per read, like a slow pipe
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_for_each_chunk_errors() {
+        let ignore = |_, _| Ok(());
+        assert!(matches!(
+            Png::for_each_chunk(&mut Trickle(&PNG_FILE[..5]), ignore),
+            Err(PngError::InvalidHeader)
+        ));
+        assert!(matches!(
+            Png::for_each_chunk(&mut Trickle(&PNG_FILE[..PNG_FILE.len() - 2]), ignore),
+            Err(PngError::ParseError { index: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn test_for_each_chunk() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend_from_slice(b"after IEND");
+        let mut reader = io::BufReader::with_capacity(1, Trickle(&bytes));
+
+        let mut seen = Vec::new();
+        Png::for_each_chunk(&mut reader, |offset, chunk| {
+            seen.push((offset, chunk.chunk_type().to_string()));
+            Ok(())
+        })
+        .unwrap();
+
+        let expected: Vec<_> = Png::try_from(&PNG_FILE[..])
+            .unwrap()
+            .chunks_with_offsets()
+            .map(|(offset, chunk)| (offset, chunk.chunk_type().to_string()))
+            .collect();
+        assert_eq!(seen, expected);
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"after IEND");
+    }
+
+    #[test]
+    fn test_for_each_chunk_checks_length() {
+        let full = Png::try_from(&PNG_FILE[..]).unwrap();
+        let (offset, _) = full.chunks_with_offsets().nth(1).unwrap();
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[offset..offset + 4].copy_from_slice(&[0x80, 0, 0, 0]);
+
+        let result = Png::for_each_chunk(&mut Trickle(&bytes), |_, _| Ok(()));
+        assert!(matches!(
+            result,
+            Err(PngError::InvalidChunk {
+                index: 1,
+                source: crate::chunk::ChunkError::InvalidLength(0x8000_0000),
+                ..
+            })
+        ));
+
+        // A valid but huge length fails on the missing bytes, without
+        // allocating for them first
+        bytes[offset..offset + 4].copy_from_slice(&MAX_CHUNK_LENGTH.to_be_bytes());
+        assert!(matches!(
+            Png::for_each_chunk(&mut Trickle(&bytes), |_, _| Ok(())),
+            Err(PngError::ParseError { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_truncated_error_names_chunk() {
+        let bytes = &PNG_FILE[..PNG_FILE.len() - 2];
+        let err = Png::try_from(bytes).err().unwrap();
+
+        assert!(matches!(err, PngError::ParseError { index: 6, .. }));
+    }
+
+    #[test]
+    fn test_try_from_partial_recovers_truncated_png() {
+        let full = Png::try_from(&PNG_FILE[..]).unwrap();
+        let (offset, _) = full.chunks_with_offsets().nth(3).unwrap();
+
+        // Cut the file partway through the fourth chunk
+        let (png, error) = Png::try_from_partial(&PNG_FILE[..offset + 20]);
+        assert_eq!(png.chunk_count(), 3);
+        assert!(matches!(error, Some(PngError::ParseError { index: 3, .. })));
+        for (recovered, original) in png.iter_chunks().zip(full.iter_chunks()) {
+            assert_eq!(recovered.as_bytes(), original.as_bytes());
+        }
+
+        let (png, error) = Png::try_from_partial(&PNG_FILE[..]);
+        assert_eq!(png.chunk_count(), full.chunk_count());
+        assert!(error.is_none());
+
+        let (png, error) = Png::try_from_partial(&PNG_FILE[1..]);
+        assert_eq!(png.chunk_count(), 0);
+        assert!(matches!(error, Some(PngError::InvalidHeader)));
+    }
+
+    #[test]
+    fn test_mng_and_jng_signatures() {
+        let mut mng = PNG_FILE.to_vec();
+        mng[..8].copy_from_slice(&[0x8A, b'M', b'N', b'G', 13, 10, 26, 10]);
+        let err = Png::try_from(mng.as_slice()).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "This looks like an MNG file, which is not supported"
+        );
+
+        let mut jng = PNG_FILE.to_vec();
+        jng[..8].copy_from_slice(&[0x8B, b'J', b'N', b'G', 13, 10, 26, 10]);
+        assert!(matches!(
+            Png::find_chunk_streaming(jng.as_slice(), "IEND"),
+            Err(PngError::UnsupportedFormat("JNG"))
+        ));
+
+        let mut other = PNG_FILE.to_vec();
+        other[0] = 0;
+        assert!(matches!(
+            Png::try_from(other.as_slice()),
+            Err(PngError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn test_empty_and_too_small_input() {
+        assert!(matches!(Png::try_from(&[][..]), Err(PngError::Empty)));
+        assert!(matches!(
+            Png::try_from(&PNG_FILE[..4]),
+            Err(PngError::TooSmall(4))
+        ));
+        assert!(matches!(
+            Png::try_from(&PNG_FILE[..8]),
+            Err(PngError::TooSmall(8))
+        ));
+        assert!(matches!(
+            Png::try_from_chunk_stream(&[]),
+            Err(PngError::Empty)
+        ));
+        assert!(matches!(
+            Png::try_from_partial(&[]).1,
+            Some(PngError::Empty)
+        ));
+    }
+
+    #[test]
+    fn test_missing_crc_is_an_error() {
+        // Declared data fits, but the trailing CRC was cut off
+        let bytes = &PNG_FILE[..PNG_FILE.len() - 2];
+        assert!(Png::try_from(bytes).is_err());
+    }
+
+    #[test]
+    fn test_mangled_input_never_panics() {
+        // Cheap deterministic stand-in for the cargo-fuzz target in fuzz/
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for len in 0..PNG_FILE.len().min(128) {
+            let _ = Png::try_from(&PNG_FILE[..len]);
+        }
+
+        for _ in 0..2000 {
+            let mut bytes = PNG_FILE.to_vec();
+            for _ in 0..1 + next() % 4 {
+                let index = next() as usize % bytes.len();
+                bytes[index] = next() as u8;
+            }
+            let cut = next() as usize % bytes.len();
+            let _ = Png::try_from(&bytes[..]);
+            let _ = Png::try_from(&bytes[..cut]);
+        }
+    }
+
     #[test]
     fn test_png_trait_impls() {
         let chunk_bytes: Vec<u8> = testing_chunks()
@@ -0,0 +1,272 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RegexError {
+    #[error("Pattern ends with an unfinished escape")]
+    TrailingBackslash,
+    #[error("Unclosed [ in pattern")]
+    UnclosedClass,
+    #[error("Nothing to repeat before {0:?} at offset {1}")]
+    NothingToRepeat(char, usize),
+    #[error("{0:?} at offset {1} is not supported; only . [] * + ? ^ $ and escapes are")]
+    Unsupported(char, usize),
+}
+
+// A small byte-oriented regular expression: literals, ., [classes], the
+// \d \w \s escapes, the * + ? quantifiers and ^ $ anchors. There are no
+// groups or alternation. Matching backtracks, which is fine for chunk-sized
+// inputs and patterns typed on a command line.
+#[derive(Debug)]
+pub struct Regex {
+    pieces: Vec<Piece>,
+    anchored_start: bool,
+    anchored_end: bool,
+}
+
+#[derive(Debug)]
+struct Piece {
+    atom: Atom,
+    min: usize,
+    max: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Atom {
+    Byte(u8),
+    Any,
+    Class {
+        ranges: Vec<(u8, u8)>,
+        negated: bool,
+    },
+}
+
+impl Atom {
+    fn matches(&self, byte: u8) -> bool {
+        match self {
+            Atom::Byte(b) => *b == byte,
+            Atom::Any => byte != b'\n',
+            Atom::Class { ranges, negated } => {
+                ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&byte)) != *negated
+            }
+        }
+    }
+
+    // \d, \w and \s, or the escaped byte itself
+    fn escape(byte: u8) -> Atom {
+        let class = |ranges: &[(u8, u8)]| Atom::Class {
+            ranges: ranges.to_vec(),
+            negated: false,
+        };
+        match byte {
+            b'd' => class(&[(b'0', b'9')]),
+            b'w' => class(&[(b'a', b'z'), (b'A', b'Z'), (b'0', b'9'), (b'_', b'_')]),
+            b's' => class(&[(b' ', b' '), (b'\t', b'\r')]),
+            b'n' => Atom::Byte(b'\n'),
+            b't' => Atom::Byte(b'\t'),
+            b'0' => Atom::Byte(0),
+            other => Atom::Byte(other),
+        }
+    }
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, RegexError> {
+        let bytes = pattern.as_bytes();
+        let anchored_start = bytes.first() == Some(&b'^');
+        let anchored_end = bytes.len() > usize::from(anchored_start)
+            && bytes.last() == Some(&b'$')
+            && !bytes.ends_with(b"\\$");
+        let body = &bytes[usize::from(anchored_start)..bytes.len() - usize::from(anchored_end)];
+        let offset = usize::from(anchored_start);
+
+        let mut pieces: Vec<Piece> = Vec::new();
+        let mut i = 0;
+        while i < body.len() {
+            let atom = match body[i] {
+                b'\\' => {
+                    i += 1;
+                    Atom::escape(*body.get(i).ok_or(RegexError::TrailingBackslash)?)
+                }
+                b'.' => Atom::Any,
+                b'[' => {
+                    let (atom, end) = parse_class(body, i)?;
+                    i = end;
+                    atom
+                }
+                quantifier @ (b'*' | b'+' | b'?') => {
+                    let piece = pieces
+                        .last_mut()
+                        .filter(|piece| piece.min == 1 && piece.max == Some(1))
+                        .ok_or(RegexError::NothingToRepeat(quantifier as char, offset + i))?;
+                    (piece.min, piece.max) = match quantifier {
+                        b'*' => (0, None),
+                        b'+' => (1, None),
+                        _ => (0, Some(1)),
+                    };
+                    i += 1;
+                    continue;
+                }
+                special @ (b'(' | b')' | b'|' | b'{' | b'^' | b'$') => {
+                    return Err(RegexError::Unsupported(special as char, offset + i))
+                }
+                byte => Atom::Byte(byte),
+            };
+            pieces.push(Piece {
+                atom,
+                min: 1,
+                max: Some(1),
+            });
+            i += 1;
+        }
+
+        Ok(Regex {
+            pieces,
+            anchored_start,
+            anchored_end,
+        })
+    }
+
+    // Start and end of every non-overlapping match, leftmost first
+    pub fn find_all(&self, data: &[u8]) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while start <= data.len() {
+            let found = (start..=data.len())
+                .take(if self.anchored_start { 1 } else { usize::MAX })
+                .find_map(|s| self.match_at(&self.pieces, data, s).map(|e| (s, e)));
+            let Some((s, e)) = found else { break };
+            matches.push((s, e));
+            if self.anchored_start {
+                break;
+            }
+            start = if e > s { e } else { e + 1 };
+        }
+        matches
+    }
+
+    fn match_at(&self, pieces: &[Piece], data: &[u8], pos: usize) -> Option<usize> {
+        let Some((piece, rest)) = pieces.split_first() else {
+            return (!self.anchored_end || pos == data.len()).then_some(pos);
+        };
+
+        // Greedy: take as many as allowed, then give back one at a time
+        let max = piece.max.unwrap_or(usize::MAX);
+        let available = data[pos..]
+            .iter()
+            .take(max)
+            .take_while(|&&b| piece.atom.matches(b))
+            .count();
+        (piece.min..=available)
+            .rev()
+            .find_map(|n| self.match_at(rest, data, pos + n))
+    }
+}
+
+// [abc], [a-z0-9], [^...]; a ] right after [ or [^ is a literal
+fn parse_class(body: &[u8], open: usize) -> Result<(Atom, usize), RegexError> {
+    let mut i = open + 1;
+    let negated = body.get(i) == Some(&b'^');
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let first = i;
+    loop {
+        let byte = *body.get(i).ok_or(RegexError::UnclosedClass)?;
+        if byte == b']' && i > first {
+            return Ok((Atom::Class { ranges, negated }, i));
+        }
+        let lo = if byte == b'\\' {
+            i += 1;
+            match Atom::escape(*body.get(i).ok_or(RegexError::UnclosedClass)?) {
+                Atom::Byte(b) => b,
+                Atom::Class {
+                    ranges: escaped, ..
+                } => {
+                    ranges.extend(escaped);
+                    i += 1;
+                    continue;
+                }
+                Atom::Any => unreachable!("escape never yields Any"),
+            }
+        } else {
+            byte
+        };
+        match body.get(i + 1..i + 3) {
+            Some([b'-', hi]) if *hi != b']' => {
+                ranges.push((lo, *hi));
+                i += 3;
+            }
+            _ => {
+                ranges.push((lo, lo));
+                i += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(pattern: &str, data: &str) -> Vec<(usize, usize)> {
+        Regex::new(pattern).unwrap().find_all(data.as_bytes())
+    }
+
+    #[test]
+    fn test_literals_and_dot() {
+        assert_eq!(
+            find("secret", "a secret, another secret"),
+            vec![(2, 8), (18, 24)]
+        );
+        assert_eq!(find("s.c", "sec sac s\nc"), vec![(0, 3), (4, 7)]);
+        assert_eq!(find("a\\.b", "axb a.b"), vec![(4, 7)]);
+        assert!(find("nope", "haystack").is_empty());
+    }
+
+    #[test]
+    fn test_quantifiers() {
+        assert_eq!(find("ab*c", "ac abc abbbc"), vec![(0, 2), (3, 6), (7, 12)]);
+        assert_eq!(find("ab+c", "ac abc"), vec![(3, 6)]);
+        assert_eq!(find("colou?r", "color colour"), vec![(0, 5), (6, 12)]);
+        // Greedy, but backtracks to let the rest match
+        assert_eq!(find("a.*b", "a1b2b3"), vec![(0, 5)]);
+    }
+
+    #[test]
+    fn test_classes_and_escapes() {
+        assert_eq!(find("[0-9]+", "id 42 and 7"), vec![(3, 5), (10, 11)]);
+        assert_eq!(find("\\d+", "id 42 and 7"), vec![(3, 5), (10, 11)]);
+        assert_eq!(find("[^a-z ]+", "abc DEF ghi"), vec![(4, 7)]);
+        assert_eq!(find("\\w+@\\w+", "mail me@host now"), vec![(5, 12)]);
+        assert_eq!(find("[\\s,]+", "a, b"), vec![(1, 3)]);
+        assert_eq!(find("[]x]", "a]x"), vec![(1, 2), (2, 3)]);
+        assert_eq!(find("[a-]", "-"), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_anchors() {
+        assert_eq!(find("^key", "key key"), vec![(0, 3)]);
+        assert!(find("^key", " key").is_empty());
+        assert_eq!(find("end$", "end the end"), vec![(8, 11)]);
+        assert_eq!(find("^whole$", "whole"), vec![(0, 5)]);
+        assert_eq!(find("cost \\$", "cost $"), vec![(0, 6)]);
+    }
+
+    #[test]
+    fn test_empty_matches_advance() {
+        assert_eq!(find("x*", "ab"), vec![(0, 0), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        let err = |pattern| Regex::new(pattern).unwrap_err();
+        assert_eq!(err("*a"), RegexError::NothingToRepeat('*', 0));
+        assert_eq!(err("a**"), RegexError::NothingToRepeat('*', 2));
+        assert_eq!(err("[abc"), RegexError::UnclosedClass);
+        assert_eq!(err("abc\\"), RegexError::TrailingBackslash);
+        assert_eq!(err("a|b"), RegexError::Unsupported('|', 1));
+        assert_eq!(err("^(a)"), RegexError::Unsupported('(', 1));
+    }
+}
@@ -0,0 +1,150 @@
+use pingu::{
+    chunk::{self, Chunk},
+    chunk_type::{ChunkType, ChunkTypeErr},
+    png::Png,
+};
+use thiserror::Error;
+
+use crate::{
+    commands::to_hex,
+    json::{self, JsonError, Value},
+};
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error(transparent)]
+    Json(#[from] JsonError),
+    #[error("Invalid PNG snapshot: {0}")]
+    Invalid(String),
+    #[error(transparent)]
+    ChunkType(#[from] ChunkTypeErr),
+    #[error("Chunk {index} has CRC {expected} in the snapshot but its data hashes to {actual}")]
+    CrcMismatch {
+        index: usize,
+        expected: u64,
+        actual: u32,
+    },
+}
+
+// JSON form of a parsed PNG, with binary data as lowercase hex:
+// {"chunks": [{"type", "length", "crc", "data"}, ...], "trailing": "..."}
+pub fn to_json(png: &Png) -> Value {
+    let chunks = png
+        .iter_chunks()
+        .map(|chunk| {
+            Value::Object(vec![
+                ("type".to_string(), chunk.chunk_type().to_string().into()),
+                ("length".to_string(), (chunk.length() as u64).into()),
+                ("crc".to_string(), (chunk.crc() as u64).into()),
+                ("data".to_string(), to_hex(chunk.data()).into()),
+            ])
+        })
+        .collect();
+
+    Value::Object(vec![
+        ("chunks".to_string(), Value::Array(chunks)),
+        ("trailing".to_string(), to_hex(png.trailing_bytes()).into()),
+    ])
+}
+
+// Rebuilds the PNG from its JSON form. Lengths and CRCs are derived from the
+// data, so a stored CRC that doesn't match is reported rather than trusted.
+pub fn from_json(text: &str) -> Result<Png, SnapshotError> {
+    let value = json::parse(text)?;
+    let invalid = |reason: &str| SnapshotError::Invalid(reason.to_string());
+
+    let chunks = value
+        .get("chunks")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("missing chunks array"))?
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let chunk_type: ChunkType = item
+                .get("type")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid("chunk without a type"))?
+                .parse()?;
+            let data = item
+                .get("data")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid("chunk without data"))?;
+            let chunk = Chunk::new(chunk_type, from_hex(data)?);
+
+            if let Some(expected) = item.get("crc").and_then(Value::as_u64) {
+                if expected != chunk.crc() as u64 {
+                    return Err(SnapshotError::CrcMismatch {
+                        index,
+                        expected,
+                        actual: chunk.crc(),
+                    });
+                }
+            }
+            Ok(chunk)
+        })
+        .collect::<Result<Vec<_>, SnapshotError>>()?;
+
+    let trailing = match value.get("trailing").and_then(Value::as_str) {
+        Some(hex) => from_hex(hex)?,
+        None => Vec::new(),
+    };
+
+    let mut bytes = Png::from_chunks(chunks).as_bytes();
+    bytes.extend_from_slice(&trailing);
+    Png::try_from(bytes.as_slice()).map_err(|e| SnapshotError::Invalid(e.to_string()))
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, SnapshotError> {
+    chunk::decode_hex(hex).map_err(|_| SnapshotError::Invalid(format!("bad hex data {:?}", hex)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let bytes = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/download.png")).unwrap();
+        let mut bytes_with_trailing = bytes.clone();
+        bytes_with_trailing.extend_from_slice(b"after IEND");
+
+        for bytes in [bytes, bytes_with_trailing] {
+            let png = Png::try_from(bytes.as_slice()).unwrap();
+            let text = to_json(&png).to_pretty();
+
+            assert_eq!(from_json(&text).unwrap().as_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let png = Png::from_chunks(vec![Chunk::new("IEND".parse().unwrap(), Vec::new())]);
+        assert_eq!(
+            to_json(&png).to_string(),
+            r#"{"chunks":[{"type":"IEND","length":0,"crc":2923585666,"data":""}],"trailing":""}"#
+        );
+    }
+
+    #[test]
+    fn test_from_json_rejects_bad_snapshots() {
+        let bad_crc = r#"{"chunks":[{"type":"IEND","crc":1,"data":""}]}"#;
+        assert!(matches!(
+            from_json(bad_crc),
+            Err(SnapshotError::CrcMismatch { index: 0, .. })
+        ));
+
+        let bad_hex = r#"{"chunks":[{"type":"ruSt","data":"abc"}]}"#;
+        assert!(matches!(from_json(bad_hex), Err(SnapshotError::Invalid(_))));
+        let signed_hex = r#"{"chunks":[{"type":"ruSt","data":"+f"}]}"#;
+        assert!(matches!(
+            from_json(signed_hex),
+            Err(SnapshotError::Invalid(_))
+        ));
+
+        let bad_type = r#"{"chunks":[{"type":"ru5t","data":""}]}"#;
+        assert!(matches!(
+            from_json(bad_type),
+            Err(SnapshotError::ChunkType(_))
+        ));
+    }
+}
@@ -0,0 +1,80 @@
+use pingu::{chunk::Chunk, chunk_type::ChunkType};
+use thiserror::Error;
+
+use crate::json::{self, JsonError, Value};
+
+#[derive(Debug, Error)]
+pub enum SpecError {
+    #[error(transparent)]
+    Json(#[from] JsonError),
+    #[error("Invalid spec entry {index}: {reason}")]
+    Invalid { index: usize, reason: String },
+}
+
+// Chunks to add, listed as a JSON array: [{"type": "ruSt", "message": "..."}].
+// Every entry is checked before any chunk is built, so a bad type late in the
+// list doesn't leave the earlier ones half-applied.
+pub fn parse(text: &str) -> Result<Vec<Chunk>, SpecError> {
+    let value = json::parse(text)?;
+    let entries = value.as_array().ok_or_else(|| SpecError::Invalid {
+        index: 0,
+        reason: "spec must be a JSON array".to_string(),
+    })?;
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let invalid = |reason: String| SpecError::Invalid { index, reason };
+            let chunk_type: ChunkType = entry
+                .get("type")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid("missing type".to_string()))?
+                .parse()
+                .map_err(|e| invalid(format!("{}", e)))?;
+            if !chunk_type.is_valid() {
+                return Err(invalid(format!("{} has the reserved bit set", chunk_type)));
+            }
+            let message = entry
+                .get("message")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid("missing message".to_string()))?;
+            Ok(Chunk::new(chunk_type, message.as_bytes().to_vec()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spec() {
+        let chunks = parse(
+            r#"[
+                {"type": "ruSt", "message": "first"},
+                {"type": "teSt", "message": "second\nline"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].is_type("ruSt"));
+        assert_eq!(chunks[0].data(), b"first");
+        assert!(chunks[1].is_type("teSt"));
+        assert_eq!(chunks[1].data(), b"second\nline");
+    }
+
+    #[test]
+    fn test_parse_spec_rejects_bad_entries() {
+        let err = parse(r#"[{"type": "ruSt", "message": "ok"}, {"type": "ru5t", "message": "x"}]"#)
+            .err()
+            .unwrap();
+        assert!(matches!(err, SpecError::Invalid { index: 1, .. }));
+
+        assert!(parse(r#"[{"type": "Rust", "message": "x"}]"#).is_err());
+        assert!(parse(r#"[{"type": "ruSt"}]"#).is_err());
+        assert!(parse(r#"{"type": "ruSt", "message": "x"}"#).is_err());
+        assert!(parse("[").is_err());
+    }
+}
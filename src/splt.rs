@@ -0,0 +1,186 @@
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::chunk::Chunk;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SpltError {
+    #[error("Chunk is not an sPLT chunk: {0}")]
+    NotSplt(String),
+    #[error("sPLT palette name is missing its null terminator")]
+    UnterminatedName,
+    #[error("sPLT palette name must be 1 to 79 bytes, found {0}")]
+    InvalidNameLength(usize),
+    #[error("sPLT ends before its sample depth")]
+    MissingSampleDepth,
+    #[error("sPLT sample depth must be 8 or 16, found {0}")]
+    InvalidSampleDepth(u8),
+    #[error("sPLT has {0} bytes of entries, not a whole number of {1}-byte entries")]
+    InvalidEntries(usize, usize),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SpltEntry {
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+    pub alpha: u16,
+    pub frequency: u16,
+}
+
+// A suggested palette: a named set of colors for viewers that can't show
+// the full image
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SuggestedPalette {
+    pub name: String,
+    pub sample_depth: u8,
+    pub entries: Vec<SpltEntry>,
+}
+
+impl TryFrom<&[u8]> for SuggestedPalette {
+    type Error = SpltError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let name_end = value
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(SpltError::UnterminatedName)?;
+        if !(1..=79).contains(&name_end) {
+            return Err(SpltError::InvalidNameLength(name_end));
+        }
+        // Names are Latin-1, so each byte is its own code point
+        let name = value[..name_end].iter().map(|&b| b as char).collect();
+
+        let sample_depth = *value
+            .get(name_end + 1)
+            .ok_or(SpltError::MissingSampleDepth)?;
+        let sample_bytes = match sample_depth {
+            8 => 1,
+            16 => 2,
+            depth => return Err(SpltError::InvalidSampleDepth(depth)),
+        };
+
+        let entry_len = 4 * sample_bytes + 2;
+        let entries = &value[name_end + 2..];
+        if !entries.len().is_multiple_of(entry_len) {
+            return Err(SpltError::InvalidEntries(entries.len(), entry_len));
+        }
+
+        let sample = |bytes: &[u8], i: usize| match sample_bytes {
+            1 => bytes[i] as u16,
+            _ => u16::from_be_bytes([bytes[2 * i], bytes[2 * i + 1]]),
+        };
+        let entries = entries
+            .chunks(entry_len)
+            .map(|entry| SpltEntry {
+                red: sample(entry, 0),
+                green: sample(entry, 1),
+                blue: sample(entry, 2),
+                alpha: sample(entry, 3),
+                frequency: u16::from_be_bytes([entry[entry_len - 2], entry[entry_len - 1]]),
+            })
+            .collect();
+
+        Ok(SuggestedPalette {
+            name,
+            sample_depth,
+            entries,
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for SuggestedPalette {
+    type Error = SpltError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        if chunk.chunk_type().bytes() != *b"sPLT" {
+            return Err(SpltError::NotSplt(chunk.chunk_type().to_string()));
+        }
+
+        SuggestedPalette::try_from(chunk.data())
+    }
+}
+
+impl Display for SuggestedPalette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "\"{}\", {}-bit samples, {} entries",
+            self.name,
+            self.sample_depth,
+            self.entries.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn testing_splt_bytes() -> Vec<u8> {
+        let mut bytes = b"web safe\0".to_vec();
+        bytes.push(8);
+        bytes.extend_from_slice(&[255, 0, 0, 255, 0, 10]);
+        bytes.extend_from_slice(&[0, 0, 255, 128, 0, 5]);
+        bytes
+    }
+
+    #[test]
+    fn test_splt_from_chunk() {
+        let chunk = Chunk::new(ChunkType::from_str("sPLT").unwrap(), testing_splt_bytes());
+        let palette = SuggestedPalette::try_from(&chunk).unwrap();
+
+        assert_eq!(palette.name, "web safe");
+        assert_eq!(palette.sample_depth, 8);
+        assert_eq!(palette.entries.len(), 2);
+        assert_eq!(
+            palette.entries[1],
+            SpltEntry {
+                red: 0,
+                green: 0,
+                blue: 255,
+                alpha: 128,
+                frequency: 5
+            }
+        );
+        assert_eq!(
+            palette.to_string(),
+            "\"web safe\", 8-bit samples, 2 entries"
+        );
+    }
+
+    #[test]
+    fn test_splt_16_bit() {
+        let mut bytes = b"deep\0".to_vec();
+        bytes.push(16);
+        bytes.extend_from_slice(&[1, 0, 2, 0, 3, 0, 255, 255, 0, 1]);
+        let palette = SuggestedPalette::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(palette.entries[0].red, 256);
+        assert_eq!(palette.entries[0].alpha, 65535);
+        assert_eq!(palette.entries[0].frequency, 1);
+    }
+
+    #[test]
+    fn test_splt_malformed() {
+        let parse = |bytes: &[u8]| SuggestedPalette::try_from(bytes).unwrap_err();
+
+        assert_eq!(parse(b"no terminator"), SpltError::UnterminatedName);
+        assert_eq!(parse(b"\0\x08"), SpltError::InvalidNameLength(0));
+        assert_eq!(parse(b"name\0\x07"), SpltError::InvalidSampleDepth(7));
+        assert_eq!(parse(b"name\0"), SpltError::MissingSampleDepth);
+
+        let mut bytes = testing_splt_bytes();
+        bytes.pop();
+        assert_eq!(parse(&bytes), SpltError::InvalidEntries(11, 6));
+
+        let chunk = Chunk::new(ChunkType::from_str("PLTE").unwrap(), testing_splt_bytes());
+        assert!(matches!(
+            SuggestedPalette::try_from(&chunk),
+            Err(SpltError::NotSplt(_))
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use thiserror::Error;
+
+use crate::{chunk::Chunk, chunk_type::ChunkType};
+
+#[derive(Debug, Error)]
+pub enum TimeError {
+    #[error("Chunk is not a tIME chunk: {0}")]
+    NotTime(String),
+    #[error("Invalid tIME length: {0}")]
+    InvalidLength(usize),
+}
+
+// Contents of a tIME chunk: the image's last modification time, in UTC
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Time {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl Time {
+    pub fn now() -> Self {
+        Self::from(SystemTime::now())
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.year
+            .to_be_bytes()
+            .iter()
+            .chain([self.month, self.day, self.hour, self.minute, self.second].iter())
+            .copied()
+            .collect()
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let chunk_type = ChunkType::try_from(*b"tIME").expect("standard chunk types are valid");
+        Chunk::new(chunk_type, self.as_bytes())
+    }
+}
+
+// Times before 1970 clamp to the epoch
+impl From<SystemTime> for Time {
+    fn from(time: SystemTime) -> Self {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let (days, secs) = (secs / 86_400, secs % 86_400);
+
+        // Days since the epoch to a proleptic Gregorian date, after
+        // Howard Hinnant's civil_from_days
+        let days = days + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+        Time {
+            year: year.min(u16::MAX as u64) as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (secs / 3600) as u8,
+            minute: (secs / 60 % 60) as u8,
+            second: (secs % 60) as u8,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for Time {
+    type Error = TimeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() != 7 {
+            return Err(TimeError::InvalidLength(value.len()));
+        }
+
+        Ok(Time {
+            year: u16::from_be_bytes([value[0], value[1]]),
+            month: value[2],
+            day: value[3],
+            hour: value[4],
+            minute: value[5],
+            second: value[6],
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for Time {
+    type Error = TimeError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        if chunk.chunk_type().bytes() != *b"tIME" {
+            return Err(TimeError::NotTime(chunk.chunk_type().to_string()));
+        }
+
+        Time::try_from(chunk.data())
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{str::FromStr, time::Duration};
+
+    #[test]
+    fn test_time_from_system_time() {
+        assert_eq!(
+            Time::from(UNIX_EPOCH).to_string(),
+            "1970-01-01 00:00:00 UTC"
+        );
+        // 2024-02-29 12:34:56, a leap day
+        let time = Time::from(UNIX_EPOCH + Duration::from_secs(1_709_210_096));
+        assert_eq!(time.to_string(), "2024-02-29 12:34:56 UTC");
+    }
+
+    #[test]
+    fn test_time_round_trip() {
+        let time = Time::now();
+        let chunk = time.to_chunk();
+
+        assert_eq!(chunk.length(), 7);
+        assert_eq!(Time::try_from(&chunk).unwrap(), time);
+
+        let bytes = chunk.as_bytes();
+        let parsed = Chunk::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(Time::try_from(&parsed).unwrap(), time);
+    }
+
+    #[test]
+    fn test_time_invalid() {
+        assert!(matches!(
+            Time::try_from(&[7, 232, 1][..]),
+            Err(TimeError::InvalidLength(3))
+        ));
+
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0; 7]);
+        assert!(matches!(Time::try_from(&chunk), Err(TimeError::NotTime(_))));
+    }
+}
@@ -0,0 +1,384 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::{
+    chunk::Chunk,
+    ihdr::Ihdr,
+    png::{Png, PngError},
+};
+
+pub const IEND_CRC: u32 = 0xAE42_6082;
+
+// CRC values a tool writes when it fills the field in without computing it
+const BLANK_CRCS: [u32; 2] = [0, 0xFFFF_FFFF];
+
+// Structural checks that go beyond what parsing enforces. Each entry in the
+// returned list describes one problem; an empty list means the PNG is valid.
+pub fn validate(png: &Png) -> Vec<String> {
+    let mut problems = Vec::new();
+    check_ihdr(png, &mut problems);
+    check_iend(png, &mut problems);
+    check_crcs(png, &mut problems);
+    problems
+}
+
+// Parsing stops with a truncation error when a length runs past the end of
+// the file. If the same four bytes read little-endian give a length that
+// fits, the file was most likely written by a tool with the byte order wrong.
+pub fn byte_order_hint(bytes: &[u8], err: &PngError) -> Option<String> {
+    let PngError::ParseError { index, offset } = *err else {
+        return None;
+    };
+    let field: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+    let chunk_type = bytes.get(offset + 4..offset + 8)?;
+    let (big, little) = (u32::from_be_bytes(field), u32::from_le_bytes(field));
+    let fits = |length: u32| {
+        (length as usize)
+            .checked_add(offset + 12)
+            .is_some_and(|end| end <= bytes.len())
+    };
+
+    (chunk_type.iter().all(u8::is_ascii_alphabetic) && !fits(big) && fits(little)).then(|| {
+        format!(
+            "chunk {} at offset {} declares length {}, or {} read little-endian: \
+             file may use little-endian integers (non-standard)",
+            index, offset, big, little
+        )
+    })
+}
+
+// Stored CRCs always match at this point, so a blank value or two different
+// chunks sharing one means the data was chosen to produce that CRC
+fn check_crcs(png: &Png, problems: &mut Vec<String>) {
+    let chunks: Vec<_> = png.iter_chunks().collect();
+    // The first chunk with each CRC, and the first after it with that CRC but
+    // other contents. The earliest clash for any chunk is one of the two, so
+    // each chunk is checked in constant time.
+    let mut seen: HashMap<u32, (usize, Option<usize>)> = HashMap::new();
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        if BLANK_CRCS.contains(&chunk.crc()) {
+            problems.push(format!(
+                "{} chunk at index {} has blank CRC {:#010x}",
+                chunk.chunk_type(),
+                index,
+                chunk.crc()
+            ));
+        }
+
+        let earlier = match seen.entry(chunk.crc()) {
+            Entry::Vacant(entry) => {
+                entry.insert((index, None));
+                None
+            }
+            Entry::Occupied(mut entry) => {
+                let (first, other) = entry.get_mut();
+                let first_chunk = chunks[*first];
+                if first_chunk.chunk_type() != chunk.chunk_type()
+                    || first_chunk.data() != chunk.data()
+                {
+                    other.get_or_insert(index);
+                    Some(*first)
+                } else {
+                    *other
+                }
+            }
+        };
+        if let Some(earlier) = earlier {
+            problems.push(format!(
+                "{} chunk at index {} shares CRC {:#010x} with the {} chunk at index {} \
+                 but has different contents",
+                chunk.chunk_type(),
+                index,
+                chunk.crc(),
+                chunks[earlier].chunk_type(),
+                earlier
+            ));
+        }
+    }
+}
+
+fn check_ihdr(png: &Png, problems: &mut Vec<String>) {
+    let Some(ihdr) = png
+        .chunk_by_type("IHDR")
+        .and_then(|ch| Ihdr::try_from(ch).ok())
+    else {
+        return;
+    };
+    if !ihdr.has_valid_depth() {
+        problems.push(format!(
+            "IHDR bit depth {} is not allowed for color type {} ({})",
+            ihdr.bit_depth,
+            ihdr.color_type,
+            ihdr.color_type_name()
+        ));
+    }
+}
+
+// Parsing stops at the first IEND, so anything after it, including a second
+// IEND, is only found by looking through the trailing bytes for chunks
+fn check_iend(png: &Png, problems: &mut Vec<String>) {
+    let Some((offset, iend)) = png
+        .chunks_with_offsets()
+        .find(|(_, chunk)| chunk.is_type("IEND"))
+    else {
+        problems.push("missing IEND chunk".to_string());
+        return;
+    };
+    if iend.length() != 0 {
+        problems.push(format!(
+            "IEND chunk at offset {} has length {}, expected 0",
+            offset,
+            iend.length()
+        ));
+    }
+
+    let trailing = png.trailing_bytes();
+    if trailing.is_empty() {
+        return;
+    }
+    let trailing_offset = offset + 12 + iend.length() as usize;
+    let after = chunks_in(trailing);
+    for (at, chunk) in &after {
+        if chunk.is_type("IEND") {
+            problems.push(format!(
+                "duplicate IEND chunk at offset {}",
+                trailing_offset + at
+            ));
+        }
+    }
+    if !after.is_empty() {
+        problems.push(format!(
+            "IEND chunk is followed by {} more chunks",
+            after.len()
+        ));
+    }
+    problems.push(format!("{} bytes follow the IEND chunk", trailing.len()));
+}
+
+// Whole chunks with valid CRCs at the start of bytes, with their offsets
+fn chunks_in(bytes: &[u8]) -> Vec<(usize, Chunk)> {
+    let mut chunks = Vec::new();
+    let mut position = 0;
+    while let Some(header) = bytes.get(position..position + 4) {
+        let length = u32::from_be_bytes(header.try_into().unwrap()) as usize;
+        let Some(chunk) = length
+            .checked_add(position + 12)
+            .and_then(|end| bytes.get(position..end))
+            .and_then(|slice| Chunk::try_from(slice).ok())
+        else {
+            break;
+        };
+        chunks.push((position, chunk));
+        position += 12 + length;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    // 1x1, 8-bit grayscale
+    fn ihdr() -> Chunk {
+        chunk("IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0])
+    }
+
+    // Four bytes that, appended to the chunk type and data, make the CRC
+    // come out as target (the usual CRC-32 reversal trick)
+    fn forge_crc(chunk_type: &str, data: &[u8], target: u32) -> [u8; 4] {
+        let table: Vec<u32> = (0..256u32)
+            .map(|n| {
+                (0..8).fold(n, |c, _| {
+                    if c & 1 == 1 {
+                        0xEDB8_8320 ^ (c >> 1)
+                    } else {
+                        c >> 1
+                    }
+                })
+            })
+            .collect();
+        let prefix: Vec<u8> = chunk_type.bytes().chain(data.iter().copied()).collect();
+
+        let mut register = !target;
+        for _ in 0..4 {
+            let index = table
+                .iter()
+                .position(|t| t >> 24 == register >> 24)
+                .unwrap();
+            register = ((register ^ table[index]) << 8) | index as u32;
+        }
+        (register ^ !crc32fast::hash(&prefix)).to_le_bytes()
+    }
+
+    fn chunk_with_crc(chunk_type: &str, data: &[u8], crc: u32) -> Chunk {
+        let mut data = data.to_vec();
+        data.extend(forge_crc(chunk_type, &data, crc));
+        chunk(chunk_type, &data)
+    }
+
+    #[test]
+    fn test_valid_iend() {
+        let iend = chunk("IEND", b"");
+        assert_eq!(iend.crc(), IEND_CRC);
+
+        let png = Png::from_chunks(vec![ihdr(), iend]);
+        assert!(validate(&png).is_empty());
+    }
+
+    #[test]
+    fn test_mangled_iend() {
+        let mut bytes = Png::from_chunks(vec![ihdr()]).as_bytes();
+        bytes.extend(chunk("IEND", b"junk").as_bytes());
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            validate(&png),
+            vec!["IEND chunk at offset 33 has length 4, expected 0"]
+        );
+    }
+
+    #[test]
+    fn test_chunks_after_iend() {
+        let mut bytes = Png::from_chunks(vec![ihdr(), chunk("IEND", b"")]).as_bytes();
+        bytes.extend(chunk("ruSt", b"appended after the end").as_bytes());
+        bytes.extend(chunk("IEND", b"").as_bytes());
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(png.chunk_count(), 2);
+
+        assert_eq!(
+            validate(&png),
+            vec![
+                "duplicate IEND chunk at offset 79",
+                "IEND chunk is followed by 2 more chunks",
+                "46 bytes follow the IEND chunk",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bytes_after_iend() {
+        let mut png = Png::from_chunks(vec![ihdr(), chunk("IEND", b"")]);
+        png.append_chunk(chunk("ruSt", b"after"));
+        let png = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        assert_eq!(
+            validate(&png),
+            vec![
+                "IEND chunk is followed by 1 more chunks",
+                "17 bytes follow the IEND chunk"
+            ]
+        );
+
+        // Bytes that don't form a chunk are only counted
+        let mut bytes = Png::from_chunks(vec![ihdr(), chunk("IEND", b"")]).as_bytes();
+        bytes.extend_from_slice(b"not a chunk at all");
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(validate(&png), vec!["18 bytes follow the IEND chunk"]);
+    }
+
+    #[test]
+    fn test_blank_crc() {
+        let png = Png::from_chunks(vec![
+            ihdr(),
+            chunk_with_crc("ruSt", b"zeroed", 0),
+            chunk("IEND", b""),
+        ]);
+
+        assert_eq!(
+            validate(&png),
+            vec!["ruSt chunk at index 1 has blank CRC 0x00000000"]
+        );
+    }
+
+    #[test]
+    fn test_shared_crc() {
+        let first = chunk("ruSt", b"first");
+        let collision = chunk_with_crc("ruSt", b"second", first.crc());
+        let png = Png::from_chunks(vec![
+            ihdr(),
+            first,
+            chunk("teXt", b"first"),
+            collision,
+            chunk("IEND", b""),
+        ]);
+        let problems = validate(&png);
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("ruSt chunk at index 3 shares CRC"));
+        assert!(problems[0].contains("ruSt chunk at index 1"));
+    }
+
+    #[test]
+    fn test_shared_crc_after_identical_chunk() {
+        let first = chunk("ruSt", b"first");
+        let collision = chunk_with_crc("ruSt", b"second", first.crc());
+        let png = Png::from_chunks(vec![
+            ihdr(),
+            first,
+            collision,
+            chunk("ruSt", b"first"),
+            chunk("IEND", b""),
+        ]);
+
+        // The repeat of the first chunk is identical to it but still clashes
+        // with the forged chunk in between
+        let problems = validate(&png);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("ruSt chunk at index 2 shares CRC"));
+        assert!(problems[1].starts_with("ruSt chunk at index 3 shares CRC"));
+        assert!(problems[1].contains("ruSt chunk at index 2"));
+    }
+
+    #[test]
+    fn test_identical_chunks_share_crc() {
+        let png = Png::from_chunks(vec![
+            ihdr(),
+            chunk("ruSt", b"same"),
+            chunk("ruSt", b"same"),
+            chunk("IEND", b""),
+        ]);
+        assert!(validate(&png).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_bit_depth() {
+        let header = [0, 0, 0, 1, 0, 0, 0, 1, 16, 3, 0, 0, 0];
+        let png = Png::from_chunks(vec![chunk("IHDR", &header), chunk("IEND", b"")]);
+
+        assert_eq!(
+            validate(&png),
+            vec!["IHDR bit depth 16 is not allowed for color type 3 (palette)"]
+        );
+    }
+
+    #[test]
+    fn test_byte_order_hint() {
+        let png = Png::from_chunks(vec![ihdr(), chunk("tEXt", b"a\0b"), chunk("IEND", b"")]);
+        let mut bytes = png.as_bytes();
+        // tEXt follows the signature and the 25-byte IHDR chunk
+        bytes[33..37].reverse();
+
+        let err = Png::try_from(bytes.as_slice()).err().unwrap();
+        assert_eq!(
+            byte_order_hint(&bytes, &err).unwrap(),
+            "chunk 1 at offset 33 declares length 50331648, or 3 read little-endian: \
+             file may use little-endian integers (non-standard)"
+        );
+
+        // Plain truncation isn't blamed on byte order
+        let bytes = png.as_bytes();
+        let err = Png::try_from(&bytes[..bytes.len() - 2]).err().unwrap();
+        assert_eq!(byte_order_hint(&bytes[..bytes.len() - 2], &err), None);
+    }
+
+    #[test]
+    fn test_missing_iend() {
+        let png = Png::from_chunks(vec![ihdr()]);
+        assert_eq!(validate(&png), vec!["missing IEND chunk"]);
+    }
+}
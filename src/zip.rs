@@ -0,0 +1,162 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ZipError {
+    #[error("Too many zip entries: {0}")]
+    TooManyEntries(usize),
+    #[error("Zip entry {0} is too large")]
+    EntryTooLarge(String),
+}
+
+// Minimal writer for uncompressed ("stored") zip archives, enough to bundle
+// extracted chunk data without pulling in a zip dependency. No zip64 support.
+#[derive(Default)]
+pub struct ZipWriter {
+    archive: Vec<u8>,
+    central_directory: Vec<u8>,
+    entries: usize,
+}
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+// 1980-01-01 00:00, the earliest date a zip entry can carry
+const DOS_DATE: u16 = (1 << 5) | 1;
+const DOS_TIME: u16 = 0;
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_entry(&mut self, name: &str, data: &[u8]) -> Result<(), ZipError> {
+        let too_large = || ZipError::EntryTooLarge(name.to_string());
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| too_large())?;
+        let offset = u32::try_from(self.archive.len()).map_err(|_| too_large())?;
+        let crc = crc32fast::hash(data);
+
+        let archive = &mut self.archive;
+        archive.extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        archive.extend_from_slice(&10u16.to_le_bytes()); // version needed
+        archive.extend_from_slice(&0u16.to_le_bytes()); // flags
+        archive.extend_from_slice(&0u16.to_le_bytes()); // method: stored
+        archive.extend_from_slice(&DOS_TIME.to_le_bytes());
+        archive.extend_from_slice(&DOS_DATE.to_le_bytes());
+        archive.extend_from_slice(&crc.to_le_bytes());
+        archive.extend_from_slice(&size.to_le_bytes()); // compressed size
+        archive.extend_from_slice(&size.to_le_bytes()); // uncompressed size
+        archive.extend_from_slice(&name_len.to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(data);
+
+        let central = &mut self.central_directory;
+        central.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central.extend_from_slice(&10u16.to_le_bytes()); // version needed
+        central.extend_from_slice(&0u16.to_le_bytes()); // flags
+        central.extend_from_slice(&0u16.to_le_bytes()); // method: stored
+        central.extend_from_slice(&DOS_TIME.to_le_bytes());
+        central.extend_from_slice(&DOS_DATE.to_le_bytes());
+        central.extend_from_slice(&crc.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&name_len.to_le_bytes());
+        central.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+
+        self.entries += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<Vec<u8>, ZipError> {
+        let entries =
+            u16::try_from(self.entries).map_err(|_| ZipError::TooManyEntries(self.entries))?;
+        let too_large = || ZipError::EntryTooLarge("central directory".to_string());
+        let directory_offset = u32::try_from(self.archive.len()).map_err(|_| too_large())?;
+        let directory_len = u32::try_from(self.central_directory.len()).map_err(|_| too_large())?;
+
+        self.archive.extend_from_slice(&self.central_directory);
+        self.archive
+            .extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        self.archive.extend_from_slice(&[0; 4]); // disk numbers
+        self.archive.extend_from_slice(&entries.to_le_bytes());
+        self.archive.extend_from_slice(&entries.to_le_bytes());
+        self.archive.extend_from_slice(&directory_len.to_le_bytes());
+        self.archive
+            .extend_from_slice(&directory_offset.to_le_bytes());
+        self.archive.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+        Ok(self.archive)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], at: usize) -> usize {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> usize {
+        u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize
+    }
+
+    // Reads entries back through the central directory, checking each CRC
+    pub fn read_entries(archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let end = archive.len() - 22;
+        assert_eq!(
+            u32_at(archive, end),
+            END_OF_CENTRAL_DIRECTORY_SIGNATURE as usize
+        );
+
+        let count = u16_at(archive, end + 10);
+        let mut position = u32_at(archive, end + 16);
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            assert_eq!(u32_at(archive, position), CENTRAL_HEADER_SIGNATURE as usize);
+            let crc = u32_at(archive, position + 16) as u32;
+            let size = u32_at(archive, position + 24);
+            let name_len = u16_at(archive, position + 28);
+            let local = u32_at(archive, position + 42);
+            let name = &archive[position + 46..position + 46 + name_len];
+
+            assert_eq!(u32_at(archive, local), LOCAL_HEADER_SIGNATURE as usize);
+            let data_start = local + 30 + u16_at(archive, local + 26) + u16_at(archive, local + 28);
+            let data = archive[data_start..data_start + size].to_vec();
+            assert_eq!(crc32fast::hash(&data), crc);
+
+            entries.push((String::from_utf8(name.to_vec()).unwrap(), data));
+            position += 46 + name_len;
+        }
+        entries
+    }
+
+    #[test]
+    fn test_zip_round_trip() {
+        let mut zip = ZipWriter::new();
+        zip.add_entry("first.bin", &[0, 1, 2, 255]).unwrap();
+        zip.add_entry("second.txt", b"hello zip").unwrap();
+        let archive = zip.finish().unwrap();
+
+        assert_eq!(&archive[..4], b"PK\x03\x04");
+        assert_eq!(
+            read_entries(&archive),
+            vec![
+                ("first.bin".to_string(), vec![0, 1, 2, 255]),
+                ("second.txt".to_string(), b"hello zip".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_zip() {
+        let archive = ZipWriter::new().finish().unwrap();
+
+        assert_eq!(archive.len(), 22);
+        assert!(read_entries(&archive).is_empty());
+    }
+}
@@ -0,0 +1,718 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ZlibError {
+    #[error("Invalid zlib header")]
+    InvalidHeader,
+    #[error("Compressed data ends unexpectedly")]
+    Truncated,
+    #[error("Invalid deflate block type")]
+    InvalidBlockType,
+    #[error("Stored block length does not match its complement")]
+    InvalidStoredLength,
+    #[error("Invalid Huffman code")]
+    InvalidCode,
+    #[error("Back-reference distance {0} reaches before the start of the output")]
+    InvalidDistance(usize),
+    #[error("Adler-32 checksum mismatch")]
+    ChecksumMismatch,
+    #[error("Invalid gzip header")]
+    InvalidGzipHeader,
+    #[error("gzip CRC-32 or length does not match the decompressed data")]
+    GzipTrailerMismatch,
+    #[error("Decompressed data is larger than {0} bytes")]
+    OutputTooLarge(usize),
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+// Flags in the gzip header that announce optional fields before the data
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest run that can't overflow u32 before reducing
+    for block in data.chunks(5552) {
+        for &byte in block {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+// Wraps data in a zlib stream made of uncompressed deflate blocks. The output
+// is larger than the input but any conforming decoder can read it.
+pub fn compress_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 65535 * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(65535).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(is_final as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+// How many earlier positions with the same hash to try before settling
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: usize = 15;
+
+// Deflates data into a zlib stream: greedy LZ77 matching over hash chains,
+// written as one block with the fixed Huffman codes. That skips building
+// per-block code tables at some cost in ratio, but is far smaller than
+// stored blocks on image data.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    writer.out.extend_from_slice(&[0x78, 0x5e]);
+    // BFINAL, then BTYPE 01 for fixed codes
+    writer.write(1, 1);
+    writer.write(1, 2);
+
+    let hash = |i: usize| {
+        let key = (data[i] as usize) << 16 | (data[i + 1] as usize) << 8 | data[i + 2] as usize;
+        key.wrapping_mul(2654435761) >> 7 & ((1 << HASH_BITS) - 1)
+    };
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut previous = vec![usize::MAX; data.len()];
+
+    let mut i = 0;
+    while i < data.len() {
+        let (mut best_len, mut best_distance) = (0, 0);
+        if i + MIN_MATCH <= data.len() {
+            let mut candidate = head[hash(i)];
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || i - candidate > WINDOW_SIZE {
+                    break;
+                }
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[i..])
+                    .take(MAX_MATCH)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    (best_len, best_distance) = (len, i - candidate);
+                    if len == MAX_MATCH {
+                        break;
+                    }
+                }
+                candidate = previous[candidate];
+            }
+        }
+
+        let step = if best_len >= MIN_MATCH {
+            writer.write_match(best_len, best_distance);
+            best_len
+        } else {
+            writer.write_literal(data[i] as u16);
+            1
+        };
+        let end = (i + step).min(data.len().saturating_sub(MIN_MATCH - 1));
+        for (j, link) in previous.iter_mut().enumerate().take(end).skip(i) {
+            let h = hash(j);
+            *link = head[h];
+            head[h] = j;
+        }
+        i += step;
+    }
+    writer.write_literal(END_OF_BLOCK);
+
+    let mut out = writer.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+const END_OF_BLOCK: u16 = 256;
+
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buffer: u64,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    // Plain values go in least significant bit first
+    fn write(&mut self, value: u32, count: u32) {
+        self.bit_buffer |= (value as u64) << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.out.push(self.bit_buffer as u8);
+            self.bit_buffer >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    // Huffman codes go in most significant bit first
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    // The fixed literal/length code from RFC 1951 section 3.2.6
+    fn write_literal(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn write_match(&mut self, len: usize, distance: usize) {
+        let code = LENGTH_BASE.iter().rposition(|&base| base as usize <= len).unwrap();
+        self.write_literal(257 + code as u16);
+        self.write((len - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+
+        let code = DISTANCE_BASE.iter().rposition(|&base| base as usize <= distance).unwrap();
+        self.write_code(code as u32, 5);
+        self.write((distance - DISTANCE_BASE[code] as usize) as u32, DISTANCE_EXTRA[code] as u32);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.out.push(self.bit_buffer as u8);
+        }
+        self.out
+    }
+}
+
+// Whether data starts with a header that decompress would accept. Random
+// bytes pass about one time in 500, so this is a hint, not a guarantee.
+pub fn has_zlib_header(data: &[u8]) -> bool {
+    let [cmf, flg, ..] = *data else {
+        return false;
+    };
+    let header_check = (cmf as u16) << 8 | flg as u16;
+    // Deflate only, no preset dictionary
+    cmf & 0x0f == 8 && header_check.is_multiple_of(31) && flg & 0x20 == 0
+}
+
+pub fn has_gzip_header(data: &[u8]) -> bool {
+    data.starts_with(&GZIP_MAGIC)
+}
+
+// Same stored blocks as compress_stored, in a gzip wrapper instead of zlib
+pub fn gzip_stored(data: &[u8]) -> Vec<u8> {
+    let deflate = compress_stored(data);
+    let mut out = Vec::with_capacity(deflate.len() + 12);
+    // Deflate, no flags, no modification time, unknown OS
+    out.extend_from_slice(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255]);
+    out.extend_from_slice(&deflate[2..deflate.len() - 4]);
+    out.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+// Decompresses the first member of a gzip file, skipping the optional name,
+// comment and extra fields. Fails once the output passes limit bytes, since a
+// small file can inflate to gigabytes.
+pub fn gzip_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, ZlibError> {
+    if !has_gzip_header(data) || data.get(2) != Some(&8) {
+        return Err(ZlibError::InvalidGzipHeader);
+    }
+    let flags = *data.get(3).ok_or(ZlibError::Truncated)?;
+
+    let mut position = 10;
+    if flags & FEXTRA != 0 {
+        let len = data.get(position..position + 2).ok_or(ZlibError::Truncated)?;
+        position += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let text = data.get(position..).ok_or(ZlibError::Truncated)?;
+            let end = text.iter().position(|&b| b == 0).ok_or(ZlibError::Truncated)?;
+            position += end + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        position += 2;
+    }
+
+    let mut reader = BitReader::new(data.get(position..).ok_or(ZlibError::Truncated)?);
+    let out = inflate(&mut reader, limit)?;
+
+    let trailer_at = position + reader.byte_position();
+    let trailer = data
+        .get(trailer_at..trailer_at + 8)
+        .ok_or(ZlibError::Truncated)?;
+    let crc = u32::from_le_bytes(trailer[..4].try_into().unwrap());
+    let size = u32::from_le_bytes(trailer[4..].try_into().unwrap());
+    if crc != crc32fast::hash(&out) || size != out.len() as u32 {
+        return Err(ZlibError::GzipTrailerMismatch);
+    }
+
+    Ok(out)
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, ZlibError> {
+    decompress_with_limit(data, usize::MAX)
+}
+
+// Like decompress, but fails once the output passes limit bytes
+pub fn decompress_with_limit(data: &[u8], limit: usize) -> Result<Vec<u8>, ZlibError> {
+    if data.len() < 6 {
+        return Err(ZlibError::Truncated);
+    }
+
+    if !has_zlib_header(data) {
+        return Err(ZlibError::InvalidHeader);
+    }
+
+    let mut reader = BitReader::new(&data[2..]);
+    let out = inflate(&mut reader, limit)?;
+
+    let checksum_at = 2 + reader.byte_position();
+    let checksum = data
+        .get(checksum_at..checksum_at + 4)
+        .ok_or(ZlibError::Truncated)?;
+    if u32::from_be_bytes(checksum.try_into().unwrap()) != adler32(&out) {
+        return Err(ZlibError::ChecksumMismatch);
+    }
+
+    Ok(out)
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32, ZlibError> {
+        while self.bit_count < count {
+            let byte = *self.data.get(self.position).ok_or(ZlibError::Truncated)?;
+            self.position += 1;
+            self.bit_buffer |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+
+        let value = self.bit_buffer & ((1u64 << count) - 1) as u32;
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+
+    fn byte_position(&self) -> usize {
+        self.position
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], ZlibError> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or(ZlibError::Truncated)?;
+        self.position += count;
+        Ok(bytes)
+    }
+}
+
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Huffman { counts, symbols }
+    }
+
+    // Canonical codes are read one bit at a time, most significant bit first
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, ZlibError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(ZlibError::InvalidCode)
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// Order in which code length code lengths are stored in a dynamic block
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn inflate(reader: &mut BitReader, limit: usize) -> Result<Vec<u8>, ZlibError> {
+    let mut out = Vec::new();
+
+    loop {
+        let is_final = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => inflate_stored(reader, &mut out, limit)?,
+            1 => {
+                let (literals, distances) = fixed_tables();
+                inflate_block(reader, &mut out, &literals, &distances, limit)?
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(reader)?;
+                inflate_block(reader, &mut out, &literals, &distances, limit)?
+            }
+            _ => return Err(ZlibError::InvalidBlockType),
+        }
+
+        if is_final {
+            reader.align_to_byte();
+            return Ok(out);
+        }
+    }
+}
+
+fn inflate_stored(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    limit: usize,
+) -> Result<(), ZlibError> {
+    reader.align_to_byte();
+    let header = reader.bytes(4)?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let complement = u16::from_le_bytes([header[2], header[3]]);
+    if len != !complement {
+        return Err(ZlibError::InvalidStoredLength);
+    }
+
+    if out.len() + len as usize > limit {
+        return Err(ZlibError::OutputTooLarge(limit));
+    }
+    out.extend_from_slice(reader.bytes(len as usize)?);
+    Ok(())
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), ZlibError> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let code_length_table = Huffman::new(&code_lengths);
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut index = 0;
+    while index < lengths.len() {
+        let symbol = code_length_table.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths[..index].last().ok_or(ZlibError::InvalidCode)?;
+                (previous, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            18 => (0, 11 + reader.bits(7)? as usize),
+            _ => return Err(ZlibError::InvalidCode),
+        };
+        if index + repeat > lengths.len() {
+            return Err(ZlibError::InvalidCode);
+        }
+        lengths[index..index + repeat].fill(value);
+        index += repeat;
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count]),
+        Huffman::new(&lengths[literal_count..]),
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+    limit: usize,
+) -> Result<(), ZlibError> {
+    loop {
+        if out.len() > limit {
+            return Err(ZlibError::OutputTooLarge(limit));
+        }
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol - 257;
+                let length =
+                    LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+
+                let index = distances.decode(reader)? as usize;
+                if index >= DISTANCE_BASE.len() {
+                    return Err(ZlibError::InvalidCode);
+                }
+                let distance = DISTANCE_BASE[index] as usize
+                    + reader.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(ZlibError::InvalidDistance(distance));
+                }
+
+                // Copies may overlap the bytes they produce, so go byte by byte
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+            _ => return Err(ZlibError::InvalidCode),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_has_zlib_header() {
+        assert!(has_zlib_header(&[0x78, 0x9c, 0x03, 0x00]));
+        assert!(has_zlib_header(&[0x78, 0xda]));
+        assert!(has_zlib_header(&compress_stored(b"plain")));
+
+        assert!(!has_zlib_header(b"plain text"));
+        assert!(!has_zlib_header(&[0x78]));
+        assert!(!has_zlib_header(&[0x78, 0x9d]));
+        // Preset dictionary flag
+        assert!(!has_zlib_header(&[0x78, 0xbb]));
+    }
+
+    #[test]
+    fn test_decompress_fixed_huffman() {
+        let compressed = [
+            120, 218, 203, 72, 205, 201, 201, 87, 200, 64, 39, 1, 104, 3, 8, 177,
+        ];
+        assert_eq!(decompress(&compressed).unwrap(), b"hello hello hello hello");
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let mut inputs: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            b"a".to_vec(),
+            b"hello hello hello hello".to_vec(),
+            vec![0; 100_000],
+            (0..200_000u32).map(|i| (i * 7 % 251) as u8).collect(),
+        ];
+        // Pseudo-random bytes, which can't be matched and stay as literals
+        let mut x = 0x2545_f491u32;
+        inputs.push(
+            (0..70_000)
+                .map(|_| {
+                    x ^= x << 13;
+                    x ^= x >> 17;
+                    x ^= x << 5;
+                    x as u8
+                })
+                .collect(),
+        );
+
+        for data in inputs {
+            let compressed = compress(&data);
+            assert!(has_zlib_header(&compressed));
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_compress_shrinks_repetitive_data() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 300 / 3) as u8).collect();
+        let compressed = compress(&data);
+
+        assert!(compressed.len() < data.len() / 50, "{} bytes", compressed.len());
+        assert!(compress(&[0; 100_000]).len() < 1_000);
+    }
+
+    #[test]
+    fn test_stored_round_trip() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let compressed = compress_stored(&data);
+
+        assert_eq!(compressed[0], 0x78);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+        assert_eq!(decompress(&compress_stored(b"")).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_decompress_dynamic_huffman() {
+        // download.png's image data is compressed with dynamic Huffman tables
+        let bytes = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/download.png")).unwrap();
+        let png = crate::png::Png::try_from(bytes.as_ref()).unwrap();
+        let idat = png.chunk_by_type("IDAT").unwrap();
+
+        // 248 rows of 204 palette indices, each row led by a filter byte
+        assert_eq!(decompress(idat.data()).unwrap().len(), 248 * (1 + 204));
+    }
+
+    #[test]
+    fn test_gzip_decompress() {
+        // gzip output with a stored file name and fixed Huffman data
+        let compressed = [
+            31, 139, 8, 8, 0, 0, 0, 0, 2, 255, 104, 105, 46, 116, 120, 116, 0, 203, 72, 205, 201,
+            201, 87, 200, 64, 39, 1, 227, 81, 61, 141, 23, 0, 0, 0,
+        ];
+        assert!(has_gzip_header(&compressed));
+        assert_eq!(
+            gzip_decompress(&compressed, usize::MAX).unwrap(),
+            b"hello hello hello hello"
+        );
+
+        let mut corrupted = compressed;
+        corrupted[29] ^= 1;
+        assert_eq!(
+            gzip_decompress(&corrupted, usize::MAX),
+            Err(ZlibError::GzipTrailerMismatch)
+        );
+        assert_eq!(
+            gzip_decompress(&compressed[..20], usize::MAX),
+            Err(ZlibError::Truncated)
+        );
+        assert_eq!(
+            gzip_decompress(b"plain", usize::MAX),
+            Err(ZlibError::InvalidGzipHeader)
+        );
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        let bytes = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/download.png")).unwrap();
+        let gzipped = gzip_stored(&bytes);
+
+        assert!(has_gzip_header(&gzipped));
+        assert_eq!(gzip_decompress(&gzipped, usize::MAX).unwrap(), bytes);
+        assert_eq!(gzip_decompress(&gzip_stored(b""), 0).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_decompress_with_limit() {
+        let compressed = compress(&[7; 10_000]);
+        assert_eq!(decompress_with_limit(&compressed, 10_000).unwrap(), [7; 10_000]);
+        assert_eq!(
+            decompress_with_limit(&compressed, 9_999),
+            Err(ZlibError::OutputTooLarge(9_999))
+        );
+    }
+
+    #[test]
+    fn test_gzip_decompress_limit() {
+        // A megabyte of zeros deflates to about a kilobyte
+        let data = vec![0; 1 << 20];
+        let deflated = compress(&data);
+        let mut gzipped = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+        gzipped.extend_from_slice(&deflated[2..deflated.len() - 4]);
+        gzipped.extend_from_slice(&crc32fast::hash(&data).to_le_bytes());
+        gzipped.extend_from_slice(&(data.len() as u32).to_le_bytes());
+
+        assert_eq!(gzip_decompress(&gzipped, data.len()).unwrap(), data);
+        assert_eq!(
+            gzip_decompress(&gzipped, 4096),
+            Err(ZlibError::OutputTooLarge(4096))
+        );
+        assert_eq!(
+            gzip_decompress(&gzip_stored(&data), 4096),
+            Err(ZlibError::OutputTooLarge(4096))
+        );
+    }
+
+    #[test]
+    fn test_decompress_rejects_corruption() {
+        let mut compressed = compress_stored(b"some data");
+        let last = compressed.len() - 1;
+        compressed[last] ^= 1;
+        assert_eq!(decompress(&compressed), Err(ZlibError::ChecksumMismatch));
+
+        assert_eq!(decompress(&[0x78, 0x01, 1, 5]), Err(ZlibError::Truncated));
+        assert_eq!(
+            decompress(&[0x00, 0x00, 0, 0, 0, 0]),
+            Err(ZlibError::InvalidHeader)
+        );
+    }
+}
@@ -0,0 +1,418 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+const PINGU: &str = env!("CARGO_BIN_EXE_pingu");
+
+#[test]
+fn test_encode_to_stdout_pipes_into_decode() {
+    let input = concat!(env!("CARGO_MANIFEST_DIR"), "/download.png");
+
+    let encoded = Command::new(PINGU)
+        .args([
+            "encode", "--png", input, "-c", "ruSt", "-m", "piped", "--output", "-",
+        ])
+        .output()
+        .unwrap();
+    assert!(encoded.status.success());
+    assert_eq!(encoded.stdout[..8], [137, 80, 78, 71, 13, 10, 26, 10]);
+
+    let mut decode = Command::new(PINGU)
+        .args(["decode", "--png", "-", "-c", "ruSt"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    decode
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&encoded.stdout)
+        .unwrap();
+    let decoded = decode.wait_with_output().unwrap();
+
+    assert!(decoded.status.success());
+    assert_eq!(decoded.stdout, b"piped\n");
+}
+
+#[test]
+fn test_streaming_decode_from_stdin_with_tiny_buffer() {
+    let png = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/download.png")).unwrap();
+    let mut decode = Command::new(PINGU)
+        .args([
+            "decode",
+            "--streaming",
+            "--buffer-size",
+            "1",
+            "-c",
+            "IEND",
+            "--png",
+            "-",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    decode.stdin.take().unwrap().write_all(&png).unwrap();
+    let decoded = decode.wait_with_output().unwrap();
+
+    assert!(decoded.status.success());
+    assert_eq!(decoded.stdout, b"\n");
+}
+
+#[test]
+fn test_oversized_stdin_is_rejected() {
+    let mut print = Command::new(PINGU)
+        .args(["print", "--png", "-", "--max-input-size", "16"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The reader may stop before taking everything, so a broken pipe is fine
+    let _ = print.stdin.take().unwrap().write_all(&[0; 4096]);
+    let printed = print.wait_with_output().unwrap();
+
+    assert!(!printed.status.success());
+    assert!(String::from_utf8_lossy(&printed.stderr).contains("larger than 16 bytes"));
+}
+
+#[test]
+fn test_oversized_stdin_is_rejected_when_streaming() {
+    let png = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/download.png")).unwrap();
+    let commands = [
+        &["print", "--jsonl", "--png", "-", "--max-input-size", "100"][..],
+        &[
+            "decode",
+            "--streaming",
+            "-c",
+            "IEND",
+            "--png",
+            "-",
+            "--max-input-size",
+            "100",
+        ],
+    ];
+
+    for args in commands {
+        let mut child = Command::new(PINGU)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let _ = child.stdin.take().unwrap().write_all(&png);
+        let output = child.wait_with_output().unwrap();
+
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains("larger than 100 bytes"),
+            "{:?}",
+            args
+        );
+    }
+}
+
+#[test]
+fn test_print_several_files() {
+    let first = concat!(env!("CARGO_MANIFEST_DIR"), "/download.png");
+    let second = concat!(env!("CARGO_MANIFEST_DIR"), "/hihi.png");
+
+    let printed = Command::new(PINGU)
+        .args(["print", "--verbose", "--png", first, "--png", second])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(printed.stdout).unwrap();
+
+    assert!(printed.status.success());
+    assert!(stdout.starts_with(&format!("==> {} <==\n  0  IHDR", first)));
+    assert!(stdout.contains(&format!("\n==> {} <==\n  0  IHDR", second)));
+    assert!(stdout.ends_with("\n2 of 2 files printed\n"));
+}
+
+// The default mode prints each chunk in full, binary ones like IHDR included
+#[test]
+fn test_print_several_files_default_mode() {
+    let first = concat!(env!("CARGO_MANIFEST_DIR"), "/download.png");
+    let second = concat!(env!("CARGO_MANIFEST_DIR"), "/hihi.png");
+
+    let printed = Command::new(PINGU)
+        .args(["print", "--png", first, "--png", second])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(printed.stdout).unwrap();
+
+    assert!(
+        printed.status.success(),
+        "{}",
+        String::from_utf8_lossy(&printed.stderr)
+    );
+    assert!(stdout.starts_with(&format!(
+        "==> {} <==\nChunk Type: IHDR\nLength: 13\nData (hex): 000000cc000000f8",
+        first
+    )));
+    assert!(stdout.contains(&format!("\n==> {} <==\nChunk Type: IHDR\n", second)));
+    assert!(stdout.ends_with("\n2 of 2 files printed\n"));
+}
+
+#[test]
+fn test_print_continues_past_a_bad_file() {
+    let good = concat!(env!("CARGO_MANIFEST_DIR"), "/download.png");
+
+    let printed = Command::new(PINGU)
+        .args(["print", "--verbose", "--png", "missing.png", "--png", good])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(printed.stdout).unwrap();
+
+    assert!(!printed.status.success());
+    assert!(String::from_utf8_lossy(&printed.stderr).contains("missing.png: "));
+    assert!(stdout.contains(&format!("==> {} <==\n  0  IHDR", good)));
+    assert!(stdout.ends_with("\n1 of 2 files printed\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_closed_stdout_pipe_exits_quietly() {
+    use std::{io::Read, os::unix::process::ExitStatusExt};
+
+    let input = concat!(env!("CARGO_MANIFEST_DIR"), "/download.png");
+    let message = std::env::temp_dir().join("pingu_sigpipe_message.txt");
+    let encoded = std::env::temp_dir().join("pingu_sigpipe.png");
+    std::fs::write(&message, vec![b'a'; 1 << 20]).unwrap();
+
+    let status = Command::new(PINGU)
+        .args(["encode", "--png", input, "-c", "ruSt", "--message-file"])
+        .arg(&message)
+        .arg("--output")
+        .arg(&encoded)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // Like `| head -c 16`: read a little, then hang up on a 2 MiB hex dump
+    let mut decode = Command::new(PINGU)
+        .args([
+            "decode",
+            "-c",
+            "ruSt",
+            "--format",
+            "hex",
+            "--max-output",
+            "0",
+            "--png",
+        ])
+        .arg(&encoded)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut head = [0; 16];
+    decode.stdout.take().unwrap().read_exact(&mut head).unwrap();
+    let decoded = decode.wait_with_output().unwrap();
+
+    assert_eq!(&head, b"6161616161616161");
+    assert!(decoded.status.success() || decoded.status.signal() == Some(13));
+    assert!(decoded.stderr.is_empty());
+
+    std::fs::remove_file(&message).unwrap();
+    std::fs::remove_file(&encoded).unwrap();
+}
+
+#[test]
+fn test_encode_if_absent_is_idempotent() {
+    let input = concat!(env!("CARGO_MANIFEST_DIR"), "/download.png");
+    let once = std::env::temp_dir().join("pingu_if_absent_once.png");
+    let twice = std::env::temp_dir().join("pingu_if_absent_twice.png");
+
+    let encode = |from: &std::path::Path, to: &std::path::Path| {
+        Command::new(PINGU)
+            .args(["encode", "-c", "ruSt", "-m", "once", "--if-absent", "--png"])
+            .arg(from)
+            .arg("--output")
+            .arg(to)
+            .output()
+            .unwrap()
+    };
+    assert!(encode(input.as_ref(), &once).status.success());
+    let second = encode(&once, &twice);
+    assert!(second.status.success());
+    assert!(String::from_utf8_lossy(&second.stderr).contains("already present"));
+
+    assert_eq!(
+        std::fs::read(&once).unwrap(),
+        std::fs::read(&twice).unwrap()
+    );
+    let listing = Command::new(PINGU)
+        .args(["print", "--verbose", "--png"])
+        .arg(&twice)
+        .output()
+        .unwrap();
+    let listing = String::from_utf8(listing.stdout).unwrap();
+    assert_eq!(listing.matches("ruSt").count(), 1);
+
+    std::fs::remove_file(&once).unwrap();
+    std::fs::remove_file(&twice).unwrap();
+}
+
+#[test]
+fn test_encode_verify_with_little_endian_crcs() {
+    let input = concat!(env!("CARGO_MANIFEST_DIR"), "/download.png");
+    let encoded = Command::new(PINGU)
+        .args(["encode", "--png", input, "-c", "ruSt", "-m", "hello"])
+        .args(["--output", "-", "--crc-endian", "le", "--verify"])
+        .output()
+        .unwrap();
+
+    assert!(
+        encoded.status.success(),
+        "{}",
+        String::from_utf8_lossy(&encoded.stderr)
+    );
+    // The IHDR CRC is written least significant byte first
+    let png = std::fs::read(input).unwrap();
+    let mut crc = png[29..33].to_vec();
+    crc.reverse();
+    assert_eq!(encoded.stdout[29..33], crc);
+}
+
+#[test]
+fn test_in_place_edits_preserve_mtime() {
+    let path = std::env::temp_dir().join("pingu_in_place_mtime.png");
+    std::fs::copy(concat!(env!("CARGO_MANIFEST_DIR"), "/download.png"), &path).unwrap();
+    let old = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+    let set_old = || {
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap()
+    };
+    let mtime = || std::fs::metadata(&path).unwrap().modified().unwrap();
+
+    set_old();
+    let encoded = Command::new(PINGU)
+        .args([
+            "encode",
+            "-c",
+            "ruSt",
+            "-m",
+            "keep",
+            "--preserve-mtime",
+            "--png",
+        ])
+        .arg(&path)
+        .arg("--output")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(encoded.success());
+    assert_eq!(mtime(), old);
+
+    let removed = Command::new(PINGU)
+        .args(["remove", "-c", "ruSt", "--preserve-mtime", "--png"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(removed.status.success());
+    assert_eq!(mtime(), old);
+
+    // Without the flag the edit bumps the time as usual
+    let encoded = Command::new(PINGU)
+        .args(["encode", "-c", "ruSt", "-m", "bump", "--png"])
+        .arg(&path)
+        .arg("--output")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(encoded.success());
+    assert_ne!(mtime(), old);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_apply_spec_appends_in_order() {
+    let spec = std::env::temp_dir().join("pingu_apply_spec.json");
+    let output = std::env::temp_dir().join("pingu_apply_spec.png");
+    std::fs::write(
+        &spec,
+        r#"[{"type": "ruSt", "message": "one"}, {"type": "teSt", "message": "two"}]"#,
+    )
+    .unwrap();
+
+    let applied = Command::new(PINGU)
+        .args([
+            "apply",
+            "--png",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/download.png"),
+        ])
+        .arg("--spec")
+        .arg(&spec)
+        .arg("--output")
+        .arg(&output)
+        .status()
+        .unwrap();
+    assert!(applied.success());
+
+    let listing = Command::new(PINGU)
+        .args(["print", "--verbose", "--png"])
+        .arg(&output)
+        .output()
+        .unwrap();
+    let listing = String::from_utf8(listing.stdout).unwrap();
+    let types: Vec<&str> = listing
+        .lines()
+        .map(|line| line.split_whitespace().nth(1).unwrap())
+        .collect();
+    assert_eq!(types[types.len() - 3..], ["ruSt", "teSt", "IEND"]);
+
+    std::fs::remove_file(&spec).unwrap();
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[cfg(feature = "network")]
+#[test]
+fn test_https_url_is_rejected_clearly() {
+    let printed = Command::new(PINGU)
+        .args(["print", "--png", "https://example.com/img.png"])
+        .output()
+        .unwrap();
+
+    assert!(!printed.status.success());
+    let stderr = String::from_utf8_lossy(&printed.stderr);
+    assert!(
+        stderr.contains("only http:// URLs are supported"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_pngcheck_offsets_without_signature() {
+    let png = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/download.png")).unwrap();
+    let mut print = Command::new(PINGU)
+        .args(["print", "--pngcheck", "--no-signature", "--png", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    print.stdin.take().unwrap().write_all(&png[8..]).unwrap();
+    let printed = print.wait_with_output().unwrap();
+
+    assert!(printed.status.success());
+    let report = String::from_utf8(printed.stdout).unwrap();
+    assert!(
+        report.contains("chunk IHDR at offset 0x00000"),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains("chunk PLTE at offset 0x00019"),
+        "{}",
+        report
+    );
+}